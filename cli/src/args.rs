@@ -1,11 +1,19 @@
 use crate::handlers::config::ConfigArgs;
-use clap::{Args, Parser, Subcommand};
+use crate::handlers::secret::SecretArgs;
+use crate::handlers::stack::StackArgs;
+use crate::handlers::trash::TrashArgs;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use data::models::CommandSort;
 
 #[derive(Debug, Parser)]
 #[clap(version, about)]
 pub struct CmdStackArgs {
     #[clap(subcommand)]
     pub command: Command,
+
+    /// The stack to use instead of the active one (overrides `CMDSTACK_STACK`)
+    #[clap(long = "stack", global = true)]
+    pub stack: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -22,15 +30,48 @@ pub enum Command {
     /// Search for a command in your stack
     Search(SearchArgs),
 
+    /// Print a command in your stack
+    Show(ShowArgs),
+
     /// Export stack to a JSON file
     Export(ImportExportArgs),
 
     /// Import stack from a JSON file
     Import(ImportExportArgs),
 
+    /// Back up the commands in your stack to a new file
+    Backup(BackupArgs),
+
+    /// Replace the commands in your stack with the commands in a backup
+    Restore(RestoreArgs),
+
     #[clap(subcommand)]
     /// Modify the config values
     Config(ConfigArgs),
+
+    #[clap(subcommand)]
+    /// List, restore or permanently delete deleted commands
+    Trash(TrashArgs),
+
+    /// Show the changes made to a command and optionally revert one
+    History(HistoryArgs),
+
+    /// Undo the most recent addition, update, deletion or restoration
+    Undo,
+
+    /// Find duplicate commands and merge them into the most recently used one
+    Dedupe,
+
+    /// Check the database of your stack for problems and optionally repair it
+    Doctor(DoctorArgs),
+
+    #[clap(subcommand)]
+    /// List, create, switch between or delete stacks
+    Stack(StackArgs),
+
+    #[clap(subcommand)]
+    /// Manage the encrypted secrets used by `@{secret:name}` parameters
+    Secret(SecretArgs),
 }
 
 /// Arguments for adding a command
@@ -43,9 +84,9 @@ pub struct AddArgs {
     #[clap(long = "note", short = 'n')]
     pub note: Option<String>,
 
-    /// The tag for the command
+    /// A tag for the command (can be used multiple times)
     #[clap(long = "tag", short = 't')]
-    pub tag: Option<String>,
+    pub tags: Vec<String>,
 
     /// Mark the command as favourite
     #[clap(long = "favourite", short = 'f', action)]
@@ -62,18 +103,100 @@ pub struct SearchArgs {
     #[clap(long = "tag", short = 't')]
     pub tag: Option<String>,
 
-    /// Display commands in order of most recent use
-    #[clap(long = "recent", short = 'r', action)]
+    /// Display commands in order of most recent use (same as `--sort recent`)
+    #[clap(long = "recent", short = 'r', action, conflicts_with = "sort")]
     pub order_by_recently_used: bool,
 
+    /// The order to display commands in
+    #[clap(long = "sort", short = 's', value_enum)]
+    pub sort: Option<SortOrder>,
+
     /// Only display favourite commands
     #[clap(long = "favourite", short = 'f', action)]
     pub favourite: bool,
 }
 
+impl SearchArgs {
+    /// The order requested for the matching commands
+    pub fn command_sort(&self) -> CommandSort {
+        match self.sort {
+            Some(sort) => sort.into(),
+            None if self.order_by_recently_used => CommandSort::RecentlyUsed,
+            None => CommandSort::Insertion,
+        }
+    }
+}
+
+/// Orders in which commands can be displayed
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SortOrder {
+    /// The order the commands were added in
+    Added,
+    /// Most recently used first
+    Recent,
+    /// Most frequently and recently used first
+    Frecency,
+}
+
+impl From<SortOrder> for CommandSort {
+    fn from(sort: SortOrder) -> Self {
+        match sort {
+            SortOrder::Added => CommandSort::Insertion,
+            SortOrder::Recent => CommandSort::RecentlyUsed,
+            SortOrder::Frecency => CommandSort::Frecency,
+        }
+    }
+}
+
+/// Arguments for printing a command
+#[derive(Debug, Args)]
+pub struct ShowArgs {
+    /// The ID of the command
+    pub id: i64,
+}
+
+/// Arguments for viewing the change history of a command
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// The ID of the command (you are asked to search for one if not provided)
+    pub id: Option<i64>,
+
+    /// Select a previous version to revert the command to
+    #[clap(long = "revert", action)]
+    pub revert: bool,
+}
+
 /// Arguments for importing/exporting commands
 #[derive(Debug, Args)]
 pub struct ImportExportArgs {
     /// The relative path of the file
     pub file: String,
 }
+
+/// Arguments for checking the database of the stack
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Repair the problems that can be repaired without losing commands,
+    /// rebuild the indexes and reclaim unused space
+    #[clap(long = "fix", action)]
+    pub fix: bool,
+
+    /// Print the report as JSON
+    #[clap(long = "json", action)]
+    pub json: bool,
+}
+
+/// Arguments for backing up the stack
+#[derive(Debug, Args)]
+pub struct BackupArgs {
+    /// The relative path of the backup (a timestamped file in the backups
+    /// directory by default)
+    pub file: Option<String>,
+}
+
+/// Arguments for restoring a backup
+#[derive(Debug, Args)]
+pub struct RestoreArgs {
+    /// The relative path of the backup
+    pub file: String,
+}
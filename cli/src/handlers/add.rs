@@ -2,13 +2,14 @@ use crate::{
     args::AddArgs,
     handlers::CommandInputValidator,
     outputs::{format_output, print_internal_command_table, spacing},
-    utils::none_if_empty,
+    utils::{none_if_empty, split_tags},
     Cli,
 };
 use data::models::InternalCommand;
 use inquire::error::InquireError;
 use inquire::{Select, Text};
 use log::error;
+use logic::command::AddCommandOutcome;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,7 +24,7 @@ pub enum HandleAddError {
 
 impl Cli {
     /// CLI handler for the add command
-    pub fn handle_add_command(&self, args: AddArgs) -> Result<(), HandleAddError> {
+    pub fn handle_add_command(&self, args: AddArgs) -> Result<AddCommandOutcome, HandleAddError> {
         let add_args_exist = args.command.is_some();
 
         let user_input = if !add_args_exist {
@@ -32,7 +33,7 @@ impl Cli {
             InternalCommand::try_from(args)?
         };
 
-        self.logic.add_command(user_input.clone())?;
+        let outcome = self.logic.add_command(user_input.clone())?;
 
         if add_args_exist {
             // If the user added the command via CLI arguments, we need to
@@ -40,7 +41,7 @@ impl Cli {
             print_internal_command_table(&user_input);
         }
 
-        Ok(())
+        Ok(outcome)
     }
 }
 
@@ -52,10 +53,10 @@ fn prompt_user_for_add_args(args: AddArgs) -> Result<InternalCommand, InquireErr
         .with_validator(CommandInputValidator)
         .prompt()?;
 
-    let tag = Text::new(&format_output(
-        "<bold>Tag</bold> <italics>(Leave blank to skip)</italics><bold>:</bold>",
+    let tags = Text::new(&format_output(
+        "<bold>Tags</bold> <italics>(Comma-separated, leave blank to skip)</italics><bold>:</bold>",
     ))
-    .with_initial_value(&args.tag.unwrap_or_default())
+    .with_initial_value(&args.tags.join(", "))
     .prompt()?;
 
     let note = Text::new(&format_output(
@@ -71,7 +72,7 @@ fn prompt_user_for_add_args(args: AddArgs) -> Result<InternalCommand, InquireErr
 
     Ok(InternalCommand {
         command,
-        tag: none_if_empty(tag),
+        tags: split_tags(&tags),
         note: none_if_empty(note),
         favourite,
     })
@@ -84,7 +85,7 @@ impl TryFrom<AddArgs> for InternalCommand {
         if let Some(command) = args.command {
             Ok(InternalCommand {
                 command,
-                tag: args.tag,
+                tags: args.tags,
                 note: args.note,
                 favourite: args.favourite,
             })
@@ -0,0 +1,21 @@
+use crate::{args::BackupArgs, outputs::Output, Cli};
+use logic::backup::BackupError;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HandleBackupError {
+    #[error("Failed to back up the stack: {0}")]
+    LogicBackup(#[from] BackupError),
+}
+
+impl Cli {
+    /// CLI handler for the backup command
+    pub fn handle_backup_command(
+        &self,
+        args: BackupArgs,
+    ) -> Result<Output<'static>, HandleBackupError> {
+        let backup_path = self.logic.backup(args.file.map(PathBuf::from))?;
+        Ok(Output::BackupSuccess(backup_path))
+    }
+}
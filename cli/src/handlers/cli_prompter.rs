@@ -9,7 +9,8 @@ use data::models::Command;
 use inquire::{InquireError, Select, Text};
 use itertools::interleave;
 use log::error;
-use logic::parameters::parser::SerializableParameter;
+use logic::parameters::blank::BlankParameter;
+use logic::parameters::parser::{input_parameters, SerializableParameter};
 use prettytable::{format, Cell, Row, Table};
 use termion::terminal_size;
 use thiserror::Error;
@@ -139,7 +140,7 @@ impl Cli {
         match self.logic.config.cli_print_style {
             logic::config::CliPrintStyle::All => (
                 self.format_internal_commands(commands),
-                "(Command | Tag | Note | Favourite [*])",
+                "(Command | Tags | Note | Favourite [*])",
             ),
             logic::config::CliPrintStyle::CommandsOnly => (
                 commands
@@ -167,7 +168,7 @@ impl Cli {
 
         for command in commands {
             let truncated_tag = truncate_string(
-                command.internal_command.tag.as_deref().unwrap_or(""),
+                &command.internal_command.tags.join(", "),
                 tag_width as usize,
             );
             let truncated_command =
@@ -198,16 +199,39 @@ impl Cli {
         parsed_params: &[SerializableParameter],
     ) -> Result<Vec<String>, PromptUserForCommandSelectionError> {
         Output::BlankParameter.print();
-        let mut blank_index = 0;
-        let blank_param_values: Vec<String> = parsed_params
-            .iter()
-            .filter_map(|param| match param {
-                SerializableParameter::Blank => {
-                    let prompt_text = format!("<bold>Fill in @{{{}}}:</bold>", blank_index + 1);
-                    blank_index += 1;
-                    Some(Text::new(&format_output(&prompt_text)).prompt())
+        // Unnamed blank parameters are numbered as in the displayed command
+        let mut unnamed_index = 0;
+        let blank_param_values: Vec<String> = input_parameters(parsed_params)
+            .into_iter()
+            .map(|param| {
+                if let SerializableParameter::Choice(choice) = param.unnamed() {
+                    let label = match param.name() {
+                        Some(name) => name.to_string(),
+                        None => format!("choice[{}]", choice.choices.join(",")),
+                    };
+                    let prompt_text = format!("<bold>Choose @{{{}}}:</bold>", label);
+                    return Select::new(&format_output(&prompt_text), choice.choices.clone())
+                        .prompt();
                 }
-                _ => None,
+
+                let label = match param.name() {
+                    Some(name) => name.to_string(),
+                    None => {
+                        unnamed_index += 1;
+                        unnamed_index.to_string()
+                    }
+                };
+                let prompt_text = format!("<bold>Fill in @{{{}}}:</bold>", label);
+                let prompt_text = format_output(&prompt_text);
+                let mut prompt = Text::new(&prompt_text);
+                // The default is filled in, ready to be accepted or changed
+                if let SerializableParameter::Blank(BlankParameter {
+                    default: Some(default),
+                }) = param.unnamed()
+                {
+                    prompt = prompt.with_initial_value(default);
+                }
+                prompt.prompt()
             })
             .collect::<Result<_, _>>()?;
         spacing();
@@ -35,6 +35,18 @@ pub enum ConfigArgs {
 
     /// Modify default terminal used in the UI to execute commands in
     UiDefaultTerminal(UiDefaultTerminalArgs),
+
+    /// Modify the number of days deleted commands are kept in the trash
+    TrashRetentionDays(TrashRetentionDaysArgs),
+
+    /// Modify what happens when an added or imported command is a duplicate
+    DuplicatePolicy(DuplicatePolicyArgs),
+
+    /// Modify whether commands that only differ in whitespace are duplicates
+    DuplicateIgnoreWhitespace(DuplicateIgnoreWhitespaceArgs),
+
+    /// Modify the number of automatic backups that are kept (0 disables them)
+    AutoBackupCount(AutoBackupCountArgs),
 }
 
 #[derive(Debug, Args)]
@@ -75,6 +87,47 @@ pub struct UiDefaultTerminalArgs {
     pub terminal_name: UiDefaultTerminal,
 }
 
+#[derive(Debug, Args)]
+#[command(arg_required_else_help(true))]
+pub struct TrashRetentionDaysArgs {
+    /// The number of days deleted commands are kept for
+    pub value: u32,
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help(true))]
+pub struct DuplicatePolicyArgs {
+    /// The new duplicate policy
+    #[clap(value_enum)]
+    pub policy: DuplicatePolicy,
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help(true))]
+pub struct DuplicateIgnoreWhitespaceArgs {
+    /// Whether whitespace is ignored when looking for duplicates
+    #[arg(action = clap::ArgAction::Set)]
+    pub value: bool,
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help(true))]
+pub struct AutoBackupCountArgs {
+    /// The number of automatic backups that are kept
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, ValueEnum, Serialize, Deserialize, Default)]
+pub enum DuplicatePolicy {
+    /// Do not add the duplicate
+    Reject,
+    /// Merge the tags, note and favourite status into the existing command
+    Merge,
+    /// Add the duplicate as a separate command
+    #[default]
+    KeepBoth,
+}
+
 #[derive(Debug, Clone, ValueEnum, Serialize, Deserialize, Default)]
 pub enum CliPrintStyle {
     #[default]
@@ -189,6 +242,18 @@ impl Cli {
             ConfigArgs::UiDefaultTerminal(ui_default_terminal_args) => {
                 self.logic.config.default_terminal = ui_default_terminal_args.terminal_name.into();
             }
+            ConfigArgs::TrashRetentionDays(trash_retention_args) => {
+                self.logic.config.trash_retention_days = trash_retention_args.value;
+            }
+            ConfigArgs::DuplicatePolicy(duplicate_policy_args) => {
+                self.logic.config.duplicate_policy = duplicate_policy_args.policy.into();
+            }
+            ConfigArgs::DuplicateIgnoreWhitespace(ignore_whitespace_args) => {
+                self.logic.config.duplicate_ignore_whitespace = ignore_whitespace_args.value;
+            }
+            ConfigArgs::AutoBackupCount(auto_backup_args) => {
+                self.logic.config.auto_backup_count = auto_backup_args.value;
+            }
         }
         Ok(self.logic.config.write()?)
     }
@@ -221,3 +286,13 @@ impl From<UiDefaultTerminal> for logic::config::UiDefaultTerminal {
         }
     }
 }
+
+impl From<DuplicatePolicy> for logic::config::DuplicatePolicy {
+    fn from(item: DuplicatePolicy) -> Self {
+        match item {
+            DuplicatePolicy::Reject => Self::Reject,
+            DuplicatePolicy::Merge => Self::Merge,
+            DuplicatePolicy::KeepBoth => Self::KeepBoth,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{
+    outputs::{format_output, spacing, Output},
+    utils::{format_days_ago, truncate_string},
+    Cli,
+};
+use data::models::Command;
+use inquire::{InquireError, Select};
+use prettytable::{format, Attr, Cell, Row, Table};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HandleDedupeError {
+    #[error("Failed to get user input: {0}")]
+    Inquire(#[from] InquireError),
+    #[error("Failed to find duplicate commands: {0}")]
+    Find(#[from] logic::duplicates::FindDuplicatesError),
+    #[error("Failed to merge duplicate commands: {0}")]
+    Dedupe(#[from] logic::duplicates::DedupeError),
+}
+
+impl Cli {
+    /// CLI handler for the dedupe command
+    pub fn handle_dedupe_command(&self) -> Result<Output<'static>, HandleDedupeError> {
+        let groups = self.logic.find_duplicate_commands()?;
+        if groups.is_empty() {
+            return Ok(Output::NoDuplicates);
+        }
+        print_duplicates_table(&groups);
+        Output::DuplicatesListed(groups.len()).print();
+
+        let confirmed = Select::new(
+            &format_output("<bold>Merge each group into its most recently used command?</bold>"),
+            vec!["Yes", "No"],
+        )
+        .prompt()?
+            == "Yes";
+        if !confirmed {
+            return Ok(Output::DedupeCancelled);
+        }
+
+        Ok(Output::DedupeSuccess(self.logic.dedupe_commands()?))
+    }
+}
+
+/// Prints the groups of duplicate commands. The first command of a group is
+/// the one the others are merged into
+fn print_duplicates_table(groups: &[Vec<Command>]) {
+    spacing();
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.set_titles(Row::new(vec![
+        Cell::new("ID").with_style(Attr::Bold),
+        Cell::new("Command").with_style(Attr::Bold),
+        Cell::new("Last Used").with_style(Attr::Bold),
+        Cell::new("").with_style(Attr::Bold),
+    ]));
+
+    for group in groups {
+        for (i, command) in group.iter().enumerate() {
+            table.add_row(Row::new(vec![
+                Cell::new(&command.id.to_string()),
+                Cell::new(&truncate_string(&command.internal_command.command, 80)),
+                Cell::new(&format_days_ago(command.last_used)),
+                Cell::new(if i == 0 { "Kept" } else { "Merged" }),
+            ]));
+        }
+    }
+
+    table.printstd();
+}
@@ -3,9 +3,11 @@ use crate::{
     handlers::cli_prompter::{
         check_search_args_exist, PromptUserForCommandSelectionError, SearchArgsUserInput,
     },
+    outputs::format_output,
     Cli,
 };
-use inquire::InquireError;
+use data::models::CommandQuery;
+use inquire::{InquireError, Select};
 use log::error;
 use logic::command::{SearchCommandArgs, SearchCommandError};
 use thiserror::Error;
@@ -16,6 +18,8 @@ pub enum HandleDeleteError {
     Inquire(#[from] InquireError),
     #[error("No commands found matching query")]
     NoCommandsFound,
+    #[error("The command was not deleted")]
+    Cancelled,
     #[error("Failed to search for command: {0}")]
     Search(#[from] SearchCommandError),
     #[error("Failed to select a command: {0}")]
@@ -34,18 +38,35 @@ impl Cli {
             SearchArgsUserInput::from(args.clone())
         };
 
-        let search_results = self.logic.search_command(SearchCommandArgs {
-            command: search_user_input.command,
-            tag: search_user_input.tag,
-            order_by_recently_used: args.order_by_recently_used,
-            favourites_only: args.favourite,
-        })?;
+        let search_results = self
+            .logic
+            .search_command(SearchCommandArgs {
+                command: search_user_input.command,
+                tag: search_user_input.tag,
+                query: CommandQuery {
+                    sort: args.command_sort(),
+                    favourites_only: args.favourite,
+                    ..Default::default()
+                },
+            })?
+            .commands;
 
         if search_results.is_empty() {
             return Err(HandleDeleteError::NoCommandsFound);
         }
 
         let selected_command = self.prompt_user_for_command_selection(search_results)?;
+
+        let confirmed = Select::new(
+            &format_output("<bold>Move the command to the trash?</bold>"),
+            vec!["Yes", "No"],
+        )
+        .prompt()?
+            == "Yes";
+        if !confirmed {
+            return Err(HandleDeleteError::Cancelled);
+        }
+
         Ok(self.logic.delete_command(selected_command.id)?)
     }
 }
@@ -0,0 +1,128 @@
+use crate::{
+    args::DoctorArgs,
+    outputs::{format_output, spacing, Output},
+    Cli,
+};
+use logic::blocking::BlockingLogic;
+use logic::doctor::{DoctorError, DoctorReport};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HandleDoctorError {
+    #[error("Failed to check the stack: {0}")]
+    Doctor(#[from] DoctorError),
+    #[error("Failed to serialize the report: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+impl Cli {
+    /// CLI handler for the doctor command
+    pub fn handle_doctor_command(&self, args: DoctorArgs) -> Result<(), HandleDoctorError> {
+        let report = self.logic.run_doctor(args.fix)?;
+        print_report(&report, args.json)
+    }
+}
+
+/// CLI handler for the doctor command when the stack could not be opened, in
+/// which case only SQLite's integrity check can be run
+pub fn handle_doctor_unopened_stack(
+    stack: Option<String>,
+    open_error: String,
+    args: DoctorArgs,
+) -> Result<(), HandleDoctorError> {
+    let report = BlockingLogic::check_unopened_stack(stack, open_error)?;
+    print_report(&report, args.json)
+}
+
+/// Prints the report, either for the user or as JSON for other programs
+fn print_report(report: &DoctorReport, json: bool) -> Result<(), HandleDoctorError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    spacing();
+    let integrity = &report.integrity;
+    println!(
+        "{}",
+        format_output(&format!("<bold>Stack:</bold> {}", report.stack))
+    );
+    if let Some(open_error) = &report.open_error {
+        println!("  Could not be opened: {}", open_error);
+    } else {
+        println!(
+            "{}",
+            format_output(&format!(
+                "<bold>Size:</bold> {} ({} unused)",
+                format_bytes(integrity.size_bytes),
+                format_bytes(integrity.unused_bytes)
+            ))
+        );
+    }
+
+    if let Some(repair) = &report.repair {
+        spacing();
+        println!("{}", format_output("<section>Repaired:</section>"));
+        println!("  {} orphaned rows deleted", repair.orphaned_rows_deleted);
+        println!("  {} unreadable rows deleted", repair.invalid_rows_deleted);
+        println!("  {} UUIDs replaced", repair.uuids_replaced);
+        println!("  {} reclaimed", format_bytes(repair.bytes_reclaimed));
+    }
+
+    if report.num_problems() > 0 {
+        spacing();
+        println!("{}", format_output("<section>Problems:</section>"));
+    }
+    for problem in &integrity.corruption {
+        println!("  Corruption: {}", problem);
+    }
+    for orphaned in &integrity.orphaned_rows {
+        println!(
+            "  {} orphaned rows in the {} table",
+            orphaned.count, orphaned.table
+        );
+    }
+    for invalid in &integrity.invalid_rows {
+        println!(
+            "  Row {} of the {} table: {}",
+            invalid.id, invalid.table, invalid.problem
+        );
+    }
+    if integrity.unindexed_commands > 0 {
+        println!(
+            "  {} commands are missing from the search index",
+            integrity.unindexed_commands
+        );
+    }
+    for unparsable in &report.unparsable_commands {
+        println!(
+            "  Command {} ({:?}): {}",
+            unparsable.id, unparsable.command, unparsable.error
+        );
+    }
+
+    match report.num_problems() {
+        0 => Output::DoctorHealthy.print(),
+        num_problems => Output::DoctorProblemsFound(num_problems).print(),
+    }
+
+    Ok(())
+}
+
+/// Formats a number of bytes with the largest unit that keeps it above 1
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
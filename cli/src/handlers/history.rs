@@ -0,0 +1,143 @@
+use crate::{
+    args::HistoryArgs,
+    handlers::cli_prompter::PromptUserForCommandSelectionError,
+    outputs::{format_output, spacing, Output},
+    utils::{format_days_ago, truncate_string},
+    Cli,
+};
+use data::models::RevisionAction;
+use inquire::{InquireError, Select};
+use logic::command::{SearchCommandArgs, SearchCommandError};
+use logic::history::RevisionEntry;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HandleHistoryError {
+    #[error("Failed to get user input: {0}")]
+    Inquire(#[from] InquireError),
+    #[error("No command found")]
+    NoCommandFound,
+    #[error("The command has no previous versions")]
+    NoPreviousVersions,
+    #[error("Failed to search for command: {0}")]
+    Search(#[from] SearchCommandError),
+    #[error("Failed to select a command: {0}")]
+    SelectCommand(#[from] PromptUserForCommandSelectionError),
+    #[error("Failed to fetch command history: {0}")]
+    History(#[from] logic::history::CommandHistoryError),
+    #[error("Failed to revert command: {0}")]
+    Revert(#[from] logic::history::RevertCommandError),
+    #[error("Failed to undo the last change: {0}")]
+    Undo(#[from] logic::history::UndoError),
+}
+
+impl Cli {
+    /// CLI handler for the history command
+    pub fn handle_history_command(
+        &self,
+        args: HistoryArgs,
+    ) -> Result<Output<'static>, HandleHistoryError> {
+        let command_id = match args.id {
+            Some(id) => id,
+            None => self.prompt_user_for_command_id()?,
+        };
+
+        let history = self.logic.get_command_history(command_id)?;
+        if history.is_empty() {
+            return Err(HandleHistoryError::NoCommandFound);
+        }
+        print_history(&history);
+
+        if !args.revert {
+            return Ok(Output::HistoryListed(history.len()));
+        }
+
+        // Reverting to a revision restores the properties from before its change
+        let versions: Vec<&RevisionEntry> = history
+            .iter()
+            .filter(|entry| !entry.changes.is_empty())
+            .collect();
+        if versions.is_empty() {
+            return Err(HandleHistoryError::NoPreviousVersions);
+        }
+
+        spacing();
+        let selected = Select::new(
+            &format_output("<bold>Select a version to revert to:</bold>"),
+            versions
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{} ({})",
+                        truncate_string(&entry.revision.internal_command.command, 80),
+                        format_days_ago(entry.revision.timestamp)
+                    )
+                })
+                .collect(),
+        )
+        .raw_prompt()?;
+
+        self.logic
+            .revert_command(command_id, versions[selected.index].revision.id)?;
+        Ok(Output::RevertCommandSuccess)
+    }
+
+    /// CLI handler for the undo command
+    pub fn handle_undo_command(&self) -> Result<Output<'static>, HandleHistoryError> {
+        Ok(match self.logic.undo_last_change()? {
+            Some(revision) => {
+                Output::UndoSuccess(revision.action, revision.internal_command.command)
+            }
+            None => Output::NothingToUndo,
+        })
+    }
+
+    /// Asks the user to search for and select a command
+    fn prompt_user_for_command_id(&self) -> Result<i64, HandleHistoryError> {
+        let search_user_input = self.prompt_user_for_search_args()?;
+
+        let search_results = self
+            .logic
+            .search_command(SearchCommandArgs {
+                command: search_user_input.command,
+                tag: search_user_input.tag,
+                ..Default::default()
+            })?
+            .commands;
+
+        if search_results.is_empty() {
+            return Err(HandleHistoryError::NoCommandFound);
+        }
+
+        Ok(self.prompt_user_for_command_selection(search_results)?.id)
+    }
+}
+
+/// Prints every change made to a command along with the properties it changed
+fn print_history(history: &[RevisionEntry]) {
+    spacing();
+
+    for entry in history {
+        let action = match entry.revision.action {
+            RevisionAction::Add => "Added",
+            RevisionAction::Update => "Updated",
+            RevisionAction::Delete => "Moved to the trash",
+            RevisionAction::Restore => "Restored from the trash",
+        };
+        println!(
+            "{}",
+            format_output(&format!(
+                "<bold>{}</bold> <italics>{}</italics>",
+                action,
+                format_days_ago(entry.revision.timestamp)
+            ))
+        );
+
+        for change in &entry.changes {
+            println!(
+                "    {}: {:?} -> {:?}",
+                change.field, change.before, change.after
+            );
+        }
+    }
+}
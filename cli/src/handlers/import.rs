@@ -1,5 +1,6 @@
 use crate::{args::ImportExportArgs, Cli};
 use log::error;
+use logic::import_export::ImportSummary;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -14,9 +15,9 @@ impl Cli {
     pub fn handle_import_command(
         &self,
         args: ImportExportArgs,
-    ) -> Result<(u64, PathBuf), HandleImportError> {
+    ) -> Result<(ImportSummary, PathBuf), HandleImportError> {
         let file_path = Path::new(&args.file).to_path_buf();
-        let num = self.logic.import_data(&file_path)?;
-        Ok((num, file_path))
+        let summary = self.logic.import_data(&file_path)?;
+        Ok((summary, file_path))
     }
 }
@@ -1,10 +1,19 @@
 pub mod add;
+pub mod backup;
 pub mod cli_prompter;
 pub mod config;
+pub mod dedupe;
 pub mod delete;
+pub mod doctor;
 pub mod export;
+pub mod history;
 pub mod import;
+pub mod restore;
 pub mod search;
+pub mod secret;
+pub mod show;
+pub mod stack;
+pub mod trash;
 pub mod update;
 
 use inquire::{
@@ -0,0 +1,42 @@
+use crate::{
+    args::RestoreArgs,
+    outputs::{format_output, Output},
+    Cli,
+};
+use inquire::{InquireError, Select};
+use logic::backup::RestoreError;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HandleRestoreError {
+    #[error("Failed to get user input: {0}")]
+    Inquire(#[from] InquireError),
+    #[error("Failed to restore the backup: {0}")]
+    LogicRestore(#[from] RestoreError),
+}
+
+impl Cli {
+    /// CLI handler for the restore command
+    pub fn handle_restore_command(
+        &self,
+        args: RestoreArgs,
+    ) -> Result<Output<'static>, HandleRestoreError> {
+        let confirmed = Select::new(
+            &format_output(&format!(
+                "<bold>Replace every command in the {} stack with the commands in the backup?</bold>",
+                self.logic.stack()
+            )),
+            vec!["Yes", "No"],
+        )
+        .with_starting_cursor(1)
+        .prompt()?
+            == "Yes";
+        if !confirmed {
+            return Ok(Output::RestoreCancelled);
+        }
+
+        self.logic.restore_backup(Path::new(&args.file))?;
+        Ok(Output::RestoreSuccess(args.file))
+    }
+}
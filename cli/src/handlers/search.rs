@@ -4,15 +4,14 @@ use crate::{
         check_search_args_exist, copy_to_clipboard, CopyTextError,
         PromptUserForCommandSelectionError, SearchArgsUserInput,
     },
+    handlers::secret::HandleSecretError,
     outputs::spacing,
     Cli,
 };
+use data::models::{CommandQuery, InternalCommandUsage, UsageAction};
 use inquire::InquireError;
 use log::error;
-use logic::{
-    command::{SearchCommandArgs, SearchCommandError},
-    parameters::parser::SerializableParameter,
-};
+use logic::command::{SearchCommandArgs, SearchCommandError};
 use std::{os::unix::process::CommandExt, process::Command};
 use thiserror::Error;
 
@@ -32,25 +31,33 @@ pub enum HandleSearchError {
     LogicParam(#[from] logic::parameters::ParameterError),
     #[error("Failed to update command: {0}")]
     LogicUpdate(#[from] logic::command::UpdateCommandError),
+    #[error("Failed to unlock secrets: {0}")]
+    Secret(#[from] HandleSecretError),
     #[error("Failed to execute command in terminal: {0}")]
     ExecuteCommandInTerminal(String),
 }
 
 impl Cli {
     /// UI handler for the search command
-    pub fn handle_search_command(&self, args: SearchArgs) -> Result<(), HandleSearchError> {
+    pub fn handle_search_command(&mut self, args: SearchArgs) -> Result<(), HandleSearchError> {
         // Get the arguments used for search
         let search_user_input = if !check_search_args_exist(&args.command, &args.tag) {
             self.prompt_user_for_search_args()?
         } else {
             SearchArgsUserInput::from(args.clone())
         };
-        let search_results = self.logic.search_command(SearchCommandArgs {
-            command: search_user_input.command,
-            tag: search_user_input.tag,
-            order_by_recently_used: args.order_by_recently_used,
-            favourites_only: args.favourite,
-        })?;
+        let search_results = self
+            .logic
+            .search_command(SearchCommandArgs {
+                command: search_user_input.command,
+                tag: search_user_input.tag,
+                query: CommandQuery {
+                    sort: args.command_sort(),
+                    favourites_only: args.favourite,
+                    ..Default::default()
+                },
+            })?
+            .commands;
         if search_results.is_empty() {
             return Err(HandleSearchError::NoCommandFound);
         }
@@ -61,9 +68,11 @@ impl Cli {
             .logic
             .parse_parameters(user_selection.internal_command.command.clone())?;
 
-        let has_blank_params = parsed_params
-            .iter()
-            .any(|item| matches!(item, SerializableParameter::Blank));
+        if parsed_params.iter().any(|item| item.is_secret()) {
+            self.unlock_secrets()?;
+        }
+
+        let has_blank_params = parsed_params.iter().any(|item| item.is_input());
         let blank_param_values = if has_blank_params {
             self.fill_blank_params(&parsed_params)?
         } else {
@@ -76,6 +85,7 @@ impl Cli {
             parsed_params,
             blank_param_values,
             None,
+            None,
         )?;
 
         // Prompt the user to edit the generated command
@@ -85,7 +95,10 @@ impl Cli {
         let action = self.prompt_user_for_action()?;
 
         if action == "Execute" {
-            let _ = self.logic.update_command_last_used_prop(user_selection.id);
+            let _ = self.logic.record_command_usage(
+                user_selection.id,
+                command_usage(UsageAction::Execute, &user_edited_cmd),
+            );
             // Note: using `.exec()` will shutdown our app and execute the command if successful.
             return Err(HandleSearchError::ExecuteCommandInTerminal(
                 Command::new("sh")
@@ -94,11 +107,23 @@ impl Cli {
                     .to_string(),
             ));
         } else {
-            copy_to_clipboard(user_edited_cmd)?;
+            copy_to_clipboard(user_edited_cmd.clone())?;
         }
 
-        Ok(self
-            .logic
-            .update_command_last_used_prop(user_selection.id)?)
+        Ok(self.logic.record_command_usage(
+            user_selection.id,
+            command_usage(UsageAction::Copy, &user_edited_cmd),
+        )?)
+    }
+}
+
+/// Describes a use of a command from the current working directory
+fn command_usage(action: UsageAction, resolved_command: &str) -> InternalCommandUsage {
+    InternalCommandUsage {
+        action,
+        working_directory: std::env::current_dir()
+            .ok()
+            .map(|dir| dir.to_string_lossy().into_owned()),
+        resolved_command: resolved_command.to_string(),
     }
 }
@@ -0,0 +1,116 @@
+use clap::{Args, Subcommand};
+use inquire::{InquireError, Password, Select};
+use logic::secrets::{secrets_exist, SecretError};
+use thiserror::Error;
+
+use crate::{
+    outputs::{format_output, spacing, Output},
+    Cli,
+};
+
+#[derive(Error, Debug)]
+pub enum HandleSecretError {
+    #[error("Failed to get user input: {0}")]
+    Inquire(#[from] InquireError),
+    #[error("Failed to manage secrets: {0}")]
+    Secret(#[from] SecretError),
+}
+
+#[derive(Debug, Subcommand)]
+#[command(arg_required_else_help(true))]
+pub enum SecretArgs {
+    /// List the names of the secrets
+    List,
+
+    /// Add a secret, or replace the value of an existing one
+    Set(SecretNameArgs),
+
+    /// Delete a secret
+    Delete(SecretNameArgs),
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help(true))]
+pub struct SecretNameArgs {
+    /// The name of the secret (letters, digits, '-' and '_')
+    pub name: String,
+}
+
+impl Cli {
+    /// CLI handler for the secret command
+    pub fn handle_secret_command(
+        &mut self,
+        args: SecretArgs,
+    ) -> Result<Output<'static>, HandleSecretError> {
+        self.unlock_secrets()?;
+
+        match args {
+            SecretArgs::List => {
+                let names = self.logic.secret_names()?;
+                print_secret_names(&names);
+                Ok(Output::SecretsListed(names.len()))
+            }
+            SecretArgs::Set(set_args) => {
+                let value = Password::new(&format_output(&format!(
+                    "<bold>Value of {}:</bold>",
+                    set_args.name
+                )))
+                .without_confirmation()
+                .prompt()?;
+
+                self.logic.set_secret(&set_args.name, &value)?;
+                Ok(Output::SecretSet(set_args.name))
+            }
+            SecretArgs::Delete(delete_args) => {
+                let confirmed = Select::new(
+                    &format_output(&format!(
+                        "<bold>Delete the secret {}?</bold>",
+                        delete_args.name
+                    )),
+                    vec!["Yes", "No"],
+                )
+                .with_starting_cursor(1)
+                .prompt()?
+                    == "Yes";
+                if !confirmed {
+                    return Ok(Output::SecretDeleteCancelled);
+                }
+
+                self.logic.delete_secret(&delete_args.name)?;
+                Ok(Output::SecretDeleted(delete_args.name))
+            }
+        }
+    }
+
+    /// Prompts the user for the passphrase of the secrets and unlocks them.
+    /// A new passphrase has to be entered twice
+    pub fn unlock_secrets(&mut self) -> Result<(), HandleSecretError> {
+        if self.logic.secrets_unlocked() {
+            return Ok(());
+        }
+
+        let passphrase = if secrets_exist()? {
+            Password::new(&format_output("<bold>Passphrase of your secrets:</bold>"))
+                .without_confirmation()
+                .prompt()?
+        } else {
+            Password::new(&format_output(
+                "<bold>Choose a passphrase for your secrets:</bold>",
+            ))
+            .with_custom_confirmation_message(&format_output(
+                "<bold>Confirm the passphrase:</bold>",
+            ))
+            .prompt()?
+        };
+
+        Ok(self.logic.unlock_secrets(&passphrase)?)
+    }
+}
+
+/// Prints the name of every secret
+fn print_secret_names(names: &[String]) {
+    spacing();
+    for name in names {
+        println!("  {}", name);
+    }
+}
@@ -0,0 +1,20 @@
+use logic::command::GetCommandError;
+use thiserror::Error;
+
+use crate::{args::ShowArgs, outputs::print_internal_command_table, Cli};
+
+#[derive(Error, Debug)]
+pub enum HandleShowError {
+    #[error("Failed to fetch command: {0}")]
+    Get(#[from] GetCommandError),
+}
+
+impl Cli {
+    /// CLI handler for the show command
+    pub fn handle_show_command(&self, args: ShowArgs) -> Result<(), HandleShowError> {
+        let command = self.logic.get_command(args.id)?;
+        print_internal_command_table(&command.internal_command);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use clap::{Args, Subcommand};
+use inquire::{InquireError, Select};
+use logic::stack::{list_stacks, StackError};
+use thiserror::Error;
+
+use crate::{
+    outputs::{format_output, spacing, Output},
+    Cli,
+};
+
+#[derive(Error, Debug)]
+pub enum HandleStackError {
+    #[error("Failed to get user input: {0}")]
+    Inquire(#[from] InquireError),
+    #[error("Failed to manage stacks: {0}")]
+    Stack(#[from] StackError),
+}
+
+#[derive(Debug, Subcommand)]
+#[command(arg_required_else_help(true))]
+pub enum StackArgs {
+    /// List the stacks
+    List,
+
+    /// Create a stack without any commands
+    Create(StackNameArgs),
+
+    /// Make a stack the active stack
+    Use(StackNameArgs),
+
+    /// Permanently delete a stack along with all of its commands and backups
+    Delete(StackNameArgs),
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help(true))]
+pub struct StackNameArgs {
+    /// The name of the stack (letters, digits, '-' and '_')
+    pub name: String,
+}
+
+impl Cli {
+    /// CLI handler for the stack command
+    pub fn handle_stack_command(
+        &mut self,
+        args: StackArgs,
+    ) -> Result<Output<'static>, HandleStackError> {
+        match args {
+            StackArgs::List => {
+                let stacks = list_stacks()?;
+                print_stacks(&stacks, self.logic.stack());
+                Ok(Output::StacksListed(stacks.len()))
+            }
+            StackArgs::Create(create_args) => {
+                self.logic.create_stack(&create_args.name)?;
+                Ok(Output::StackCreated(create_args.name))
+            }
+            StackArgs::Use(use_args) => {
+                self.logic.use_stack(&use_args.name)?;
+                Ok(Output::StackActivated(use_args.name))
+            }
+            StackArgs::Delete(delete_args) => {
+                let confirmed = Select::new(
+                    &format_output(&format!(
+                        "<bold>Permanently delete the stack {} and all of its commands and backups?</bold>",
+                        delete_args.name
+                    )),
+                    vec!["Yes", "No"],
+                )
+                .with_starting_cursor(1)
+                .prompt()?
+                    == "Yes";
+                if !confirmed {
+                    return Ok(Output::StackDeleteCancelled);
+                }
+
+                self.logic.delete_stack(&delete_args.name)?;
+                Ok(Output::StackDeleted(delete_args.name))
+            }
+        }
+    }
+}
+
+/// Prints the name of every stack, marking the one in use
+fn print_stacks(stacks: &[String], current_stack: &str) {
+    spacing();
+    for stack in stacks {
+        if stack == current_stack {
+            println!("{}", format_output(&format!("<bold>* {}</bold>", stack)));
+        } else {
+            println!("  {}", stack);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use clap::{Args, Subcommand};
+use data::models::Command;
+use prettytable::{format, Attr, Cell, Row, Table};
+use thiserror::Error;
+
+use crate::{
+    handlers::cli_prompter::PromptUserForCommandSelectionError,
+    outputs::{spacing, Output},
+    utils::{format_days_ago, truncate_string},
+    Cli,
+};
+
+#[derive(Error, Debug)]
+pub enum HandleTrashError {
+    #[error("The trash is empty")]
+    EmptyTrash,
+    #[error("Failed to list the trash: {0}")]
+    List(#[from] logic::trash::ListTrashError),
+    #[error("Failed to select a command: {0}")]
+    SelectCommand(#[from] PromptUserForCommandSelectionError),
+    #[error("Failed to restore command: {0}")]
+    Restore(#[from] logic::trash::RestoreCommandError),
+    #[error("Failed to empty the trash: {0}")]
+    Purge(#[from] logic::trash::PurgeTrashError),
+}
+
+#[derive(Debug, Subcommand)]
+#[command(arg_required_else_help(true))]
+pub enum TrashArgs {
+    /// List the deleted commands
+    List,
+
+    /// Move a deleted command back into your stack
+    Restore,
+
+    /// Permanently delete the commands that are older than the retention period
+    Purge(PurgeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct PurgeArgs {
+    /// Permanently delete every command in the trash
+    #[clap(long = "all", short = 'a', action)]
+    pub all: bool,
+}
+
+impl Cli {
+    /// CLI handler for the trash command
+    pub fn handle_trash_command(
+        &self,
+        args: TrashArgs,
+    ) -> Result<Output<'static>, HandleTrashError> {
+        match args {
+            TrashArgs::List => {
+                let trash = self.logic.list_trash()?;
+                if trash.is_empty() {
+                    return Err(HandleTrashError::EmptyTrash);
+                }
+                print_trash_table(&trash);
+                Ok(Output::TrashListed(trash.len()))
+            }
+            TrashArgs::Restore => {
+                let trash = self.logic.list_trash()?;
+                if trash.is_empty() {
+                    return Err(HandleTrashError::EmptyTrash);
+                }
+                let selected_command = self.prompt_user_for_command_selection(trash)?;
+                self.logic.restore_command(selected_command.id)?;
+                Ok(Output::RestoreCommandSuccess)
+            }
+            TrashArgs::Purge(purge_args) => {
+                let purged = self.logic.purge_trash(purge_args.all)?;
+                Ok(Output::TrashPurged(purged))
+            }
+        }
+    }
+}
+
+/// Prints the commands in the trash along with how long ago they were deleted
+fn print_trash_table(commands: &[Command]) {
+    spacing();
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.set_titles(Row::new(vec![
+        Cell::new("ID").with_style(Attr::Bold),
+        Cell::new("Command").with_style(Attr::Bold),
+        Cell::new("Deleted").with_style(Attr::Bold),
+    ]));
+
+    for command in commands {
+        let deleted = command.deleted_at.map(format_days_ago).unwrap_or_default();
+        table.add_row(Row::new(vec![
+            Cell::new(&command.id.to_string()),
+            Cell::new(&truncate_string(&command.internal_command.command, 80)),
+            Cell::new(&deleted),
+        ]));
+    }
+
+    table.printstd();
+}
@@ -7,10 +7,10 @@ use crate::{
         CommandInputValidator,
     },
     outputs::{format_output, Output},
-    utils::none_if_empty,
+    utils::{none_if_empty, split_tags},
     Cli,
 };
-use data::models::InternalCommand;
+use data::models::{CommandQuery, InternalCommand};
 use inquire::{InquireError, Select, Text};
 use log::error;
 use logic::command::{SearchCommandArgs, SearchCommandError};
@@ -35,7 +35,7 @@ pub enum HandleUpdateError {
 /// Arguments:
 /// - cur_command: String - The current command text
 /// - cur_note: Option<String> - The current note of the command
-/// - cur_tag: Option<String> - The current tag of the command
+/// - cur_tags: Vec<String> - The current tags of the command
 /// - cur_favourite: bool - The current favourite status of the command
 pub fn prompt_user_for_command(
     cur_command: InternalCommand,
@@ -45,10 +45,10 @@ pub fn prompt_user_for_command(
         .with_validator(CommandInputValidator)
         .prompt()?;
 
-    let tag = Text::new(&format_output(
-        "<bold>Tag</bold> <italics>(Leave blank to skip)</italics><bold>:</bold>",
+    let tags = Text::new(&format_output(
+        "<bold>Tags</bold> <italics>(Comma-separated, leave blank to skip)</italics><bold>:</bold>",
     ))
-    .with_initial_value(&cur_command.tag.unwrap_or(String::from("")))
+    .with_initial_value(&cur_command.tags.join(", "))
     .prompt()?;
 
     let note = Text::new(&format_output(
@@ -64,7 +64,7 @@ pub fn prompt_user_for_command(
 
     Ok(InternalCommand {
         command,
-        tag: none_if_empty(tag),
+        tags: split_tags(&tags),
         note: none_if_empty(note),
         favourite,
     })
@@ -80,12 +80,18 @@ impl Cli {
             SearchArgsUserInput::from(args.clone())
         };
 
-        let search_results = self.logic.search_command(SearchCommandArgs {
-            command: search_user_input.command,
-            tag: search_user_input.tag,
-            order_by_recently_used: args.order_by_recently_used,
-            favourites_only: args.favourite,
-        })?;
+        let search_results = self
+            .logic
+            .search_command(SearchCommandArgs {
+                command: search_user_input.command,
+                tag: search_user_input.tag,
+                query: CommandQuery {
+                    sort: args.command_sort(),
+                    favourites_only: args.favourite,
+                    ..Default::default()
+                },
+            })?
+            .commands;
         if search_results.is_empty() {
             return Err(HandleUpdateError::NoCommandFound);
         }
@@ -5,16 +5,29 @@ pub mod utils;
 
 use args::{CmdStackArgs, Command};
 use clap::Parser;
+use data::dal::RestoreDatabaseError;
 use handlers::add::HandleAddError;
+use handlers::dedupe::HandleDedupeError;
 use handlers::delete::HandleDeleteError;
+use handlers::doctor::handle_doctor_unopened_stack;
+use handlers::history::HandleHistoryError;
+use handlers::restore::HandleRestoreError;
 use handlers::search::HandleSearchError;
+use handlers::secret::HandleSecretError;
+use handlers::show::HandleShowError;
+use handlers::stack::HandleStackError;
+use handlers::trash::HandleTrashError;
 use handlers::update::HandleUpdateError;
 use log::{error, LevelFilter, SetLoggerError};
 use log4rs::append::file::FileAppender;
 use log4rs::config::runtime::ConfigErrors;
 use log4rs::config::{Appender, Config, Root};
-use logic::Logic;
-use outputs::{ErrorOutput, Output};
+use logic::backup::RestoreError;
+use logic::blocking::BlockingLogic;
+use logic::command::{AddCommandError, AddCommandOutcome, GetCommandError};
+use logic::parameters::ParameterError;
+use logic::secrets::SecretError;
+use outputs::{spacing, ErrorOutput, Output};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -74,7 +87,7 @@ fn initialize_logger() -> Result<(), LoggerInitializationError> {
 }
 
 pub struct Cli {
-    logic: Logic,
+    logic: BlockingLogic,
 }
 
 fn main() {
@@ -84,11 +97,29 @@ fn main() {
         std::process::exit(1);
     });
 
-    let logic = Logic::try_default().unwrap_or_else(|e| {
-        ErrorOutput::Logic.print();
-        println!("{:?}", e);
-        std::process::exit(1);
-    });
+    let args = CmdStackArgs::parse();
+
+    let logic = match BlockingLogic::try_with_stack(args.stack.clone()) {
+        Ok(logic) => logic,
+        Err(e) => {
+            // The doctor can still check a database that cannot be opened
+            if let Command::Doctor(doctor_args) = args.command {
+                if let Err(doctor_error) =
+                    handle_doctor_unopened_stack(args.stack, e.to_string(), doctor_args)
+                {
+                    ErrorOutput::Doctor.print();
+                    error!(
+                        "Error occurred while checking the stack: {:?}",
+                        doctor_error
+                    );
+                }
+                std::process::exit(1);
+            }
+            ErrorOutput::Logic.print();
+            println!("{:?}", e);
+            std::process::exit(1);
+        }
+    };
     let mut cli = Cli { logic };
 
     // Configure inquire
@@ -97,13 +128,15 @@ fn main() {
         ..Default::default()
     });
 
-    let args = CmdStackArgs::parse();
-
     match args.command {
         Command::Add(add_args) => match cli.handle_add_command(add_args) {
-            Ok(_) => Output::AddCommandSuccess.print(),
+            Ok(AddCommandOutcome::Added(_)) => Output::AddCommandSuccess.print(),
+            Ok(AddCommandOutcome::Merged(id)) => Output::CommandMerged(id).print(),
             Err(e) => {
                 match e {
+                    HandleAddError::LogicAdd(AddCommandError::Duplicate(id)) => {
+                        Output::DuplicateCommand(id).print()
+                    }
                     HandleAddError::Inquire(_) => ErrorOutput::UserInput.print(),
                     _ => ErrorOutput::AddCommand.print(),
                 };
@@ -127,6 +160,7 @@ fn main() {
             Err(e) => {
                 match e {
                     HandleDeleteError::NoCommandsFound => Output::NoCommandsFound.print(),
+                    HandleDeleteError::Cancelled => Output::DeleteCommandCancelled.print(),
                     HandleDeleteError::Inquire(_) => ErrorOutput::UserInput.print(),
                     HandleDeleteError::SelectCommand(_) => ErrorOutput::UserInput.print(),
                     _ => ErrorOutput::DeleteCommand.print(),
@@ -141,11 +175,32 @@ fn main() {
                     HandleSearchError::NoCommandFound => Output::NoCommandsFound.print(),
                     HandleSearchError::Inquire(_) => ErrorOutput::UserInput.print(),
                     HandleSearchError::SelectCommand(_) => ErrorOutput::UserInput.print(),
+                    HandleSearchError::Secret(HandleSecretError::Inquire(_)) => {
+                        ErrorOutput::UserInput.print()
+                    }
+                    HandleSearchError::Secret(HandleSecretError::Secret(
+                        SecretError::IncorrectPassphrase,
+                    )) => ErrorOutput::IncorrectPassphrase.print(),
+                    HandleSearchError::LogicParam(ParameterError::Secret(
+                        SecretError::NotFound(ref name),
+                    )) => Output::SecretNotFound(name.clone()).print(),
                     _ => ErrorOutput::SearchCommand.print(),
                 };
                 error!("Error occurred while searching commands: {:?}", e);
             }
         },
+        Command::Show(show_args) => match cli.handle_show_command(show_args) {
+            Ok(()) => spacing(),
+            Err(e) => {
+                match e {
+                    HandleShowError::Get(GetCommandError::NotFound(_)) => {
+                        Output::NoCommandsFound.print()
+                    }
+                    _ => ErrorOutput::ShowCommand.print(),
+                };
+                error!("Error occurred while fetching command: {:?}", e);
+            }
+        },
         Command::Export(export_args) => match cli.handle_export_command(export_args) {
             Ok(file_path) => Output::ExportCommandsSuccess(&file_path).print(),
             Err(e) => {
@@ -154,12 +209,34 @@ fn main() {
             }
         },
         Command::Import(import_args) => match cli.handle_import_command(import_args) {
-            Ok((num, file_path)) => Output::ImportCommandsSuccess(num, &file_path).print(),
+            Ok((summary, file_path)) => Output::ImportCommandsSuccess(summary, &file_path).print(),
             Err(e) => {
                 ErrorOutput::Import.print();
                 error!("Error occurred while importing commands: {:?}", e);
             }
         },
+        Command::Backup(backup_args) => match cli.handle_backup_command(backup_args) {
+            Ok(output) => output.print(),
+            Err(e) => {
+                ErrorOutput::Backup.print();
+                error!("Error occurred while backing up the stack: {:?}", e);
+            }
+        },
+        Command::Restore(restore_args) => match cli.handle_restore_command(restore_args) {
+            Ok(output) => output.print(),
+            Err(e) => {
+                match e {
+                    HandleRestoreError::Inquire(_) => ErrorOutput::UserInput.print(),
+                    HandleRestoreError::LogicRestore(RestoreError::Database(
+                        ref db_error @ (RestoreDatabaseError::NotADatabase
+                        | RestoreDatabaseError::NewerSchemaVersion(_, _)
+                        | RestoreDatabaseError::IntegrityCheck(_)),
+                    )) => Output::InvalidBackup(db_error.to_string()).print(),
+                    _ => ErrorOutput::Restore.print(),
+                };
+                error!("Error occurred while restoring a backup: {:?}", e);
+            }
+        },
         Command::Config(config_args) => match cli.handle_config_command(config_args) {
             Ok(()) => Output::ConfigUpdate.print(),
             Err(e) => {
@@ -167,5 +244,79 @@ fn main() {
                 error!("Error occurred while updating config: {:?}", e);
             }
         },
+        Command::Trash(trash_args) => match cli.handle_trash_command(trash_args) {
+            Ok(output) => output.print(),
+            Err(e) => {
+                match e {
+                    HandleTrashError::EmptyTrash => Output::TrashEmpty.print(),
+                    HandleTrashError::SelectCommand(_) => ErrorOutput::UserInput.print(),
+                    _ => ErrorOutput::Trash.print(),
+                };
+                error!("Error occurred while updating the trash: {:?}", e);
+            }
+        },
+        Command::History(history_args) => match cli.handle_history_command(history_args) {
+            Ok(output) => output.print(),
+            Err(e) => {
+                match e {
+                    HandleHistoryError::NoCommandFound => Output::NoCommandsFound.print(),
+                    HandleHistoryError::NoPreviousVersions => Output::NoPreviousVersions.print(),
+                    HandleHistoryError::Inquire(_) => ErrorOutput::UserInput.print(),
+                    HandleHistoryError::SelectCommand(_) => ErrorOutput::UserInput.print(),
+                    _ => ErrorOutput::History.print(),
+                };
+                error!("Error occurred while fetching command history: {:?}", e);
+            }
+        },
+        Command::Undo => match cli.handle_undo_command() {
+            Ok(output) => output.print(),
+            Err(e) => {
+                ErrorOutput::Undo.print();
+                error!("Error occurred while undoing the last change: {:?}", e);
+            }
+        },
+        Command::Doctor(doctor_args) => match cli.handle_doctor_command(doctor_args) {
+            Ok(()) => {}
+            Err(e) => {
+                ErrorOutput::Doctor.print();
+                error!("Error occurred while checking the stack: {:?}", e);
+            }
+        },
+        Command::Stack(stack_args) => match cli.handle_stack_command(stack_args) {
+            Ok(output) => output.print(),
+            Err(e) => {
+                match e {
+                    HandleStackError::Inquire(_) => ErrorOutput::UserInput.print(),
+                    _ => ErrorOutput::Stack.print(),
+                };
+                error!("Error occurred while updating the stacks: {:?}", e);
+            }
+        },
+        Command::Secret(secret_args) => match cli.handle_secret_command(secret_args) {
+            Ok(output) => output.print(),
+            Err(e) => {
+                match e {
+                    HandleSecretError::Inquire(_) => ErrorOutput::UserInput.print(),
+                    HandleSecretError::Secret(SecretError::IncorrectPassphrase) => {
+                        ErrorOutput::IncorrectPassphrase.print()
+                    }
+                    HandleSecretError::Secret(SecretError::NotFound(ref name)) => {
+                        Output::SecretNotFound(name.clone()).print()
+                    }
+                    _ => ErrorOutput::Secret.print(),
+                };
+                error!("Error occurred while updating the secrets: {:?}", e);
+            }
+        },
+        Command::Dedupe => match cli.handle_dedupe_command() {
+            Ok(output) => output.print(),
+            Err(e) => {
+                match e {
+                    HandleDedupeError::Inquire(_) => ErrorOutput::UserInput.print(),
+                    _ => ErrorOutput::Dedupe.print(),
+                };
+                error!("Error occurred while merging duplicate commands: {:?}", e);
+            }
+        },
     }
 }
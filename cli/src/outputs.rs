@@ -1,9 +1,10 @@
-use data::models::InternalCommand;
+use data::models::{InternalCommand, RevisionAction};
 use lazy_static::lazy_static;
+use logic::import_export::ImportSummary;
 use prettytable::{format, Attr, Cell, Row, Table};
 use std::collections::HashMap;
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 lazy_static! {
     /// To see how colours are rendered refer to this Wikipedia page:
@@ -41,10 +42,10 @@ pub fn print_internal_command_table(internal_command: &InternalCommand) {
         Cell::new("Command:").with_style(Attr::Bold),
         Cell::new(&internal_command.command),
     ]));
-    if let Some(tag) = &internal_command.tag {
+    if !internal_command.tags.is_empty() {
         table.add_row(Row::new(vec![
-            Cell::new("Tag:").with_style(Attr::Bold),
-            Cell::new(tag),
+            Cell::new("Tags:").with_style(Attr::Bold),
+            Cell::new(&internal_command.tags.join(", ")),
         ]));
     }
     if let Some(note) = &internal_command.note {
@@ -76,9 +77,41 @@ pub enum Output<'a> {
     UpdateCommandSectionTitle,
     UpdateCommandSuccess,
     AddCommandSuccess,
+    CommandMerged(i64),
+    DuplicateCommand(i64),
     DeleteCommandSuccess,
+    DeleteCommandCancelled,
+    RestoreCommandSuccess,
+    TrashEmpty,
+    TrashListed(usize),
+    TrashPurged(u64),
+    HistoryListed(usize),
+    RevertCommandSuccess,
+    NoPreviousVersions,
+    UndoSuccess(RevisionAction, String),
+    NothingToUndo,
     ExportCommandsSuccess(&'a Path),
-    ImportCommandsSuccess(u64, &'a Path),
+    ImportCommandsSuccess(ImportSummary, &'a Path),
+    BackupSuccess(PathBuf),
+    RestoreSuccess(String),
+    RestoreCancelled,
+    InvalidBackup(String),
+    DoctorHealthy,
+    DoctorProblemsFound(usize),
+    DuplicatesListed(usize),
+    NoDuplicates,
+    DedupeSuccess(u64),
+    DedupeCancelled,
+    StacksListed(usize),
+    StackCreated(String),
+    StackActivated(String),
+    StackDeleted(String),
+    StackDeleteCancelled,
+    SecretsListed(usize),
+    SecretSet(String),
+    SecretDeleted(String),
+    SecretDeleteCancelled,
+    SecretNotFound(String),
     CommandCopiedToClipboard,
     ConfigUpdate,
     BlankParameter,
@@ -91,16 +124,107 @@ impl fmt::Display for Output<'_> {
             Output::UpdateCommandSectionTitle => "<section>Update Command:</section>".to_string(),
             Output::UpdateCommandSuccess => "✅ <bold>Command updated</bold>\n".to_string(),
             Output::AddCommandSuccess => "✅ <bold>Command added</bold>\n".to_string(),
-            Output::DeleteCommandSuccess => "✅ <bold>Command deleted</bold>\n".to_string(),
+            Output::CommandMerged(id) => {
+                format!(
+                    "✅ <bold>Command merged into its duplicate (ID {})</bold>\n",
+                    id
+                )
+            }
+            Output::DuplicateCommand(id) => {
+                format!(
+                    "<bold>The command is a duplicate of the command with ID {}</bold>\n",
+                    id
+                )
+            }
+            Output::DeleteCommandSuccess => {
+                "✅ <bold>Command moved to the trash</bold>\n".to_string()
+            }
+            Output::DeleteCommandCancelled => "<bold>Command not deleted</bold>\n".to_string(),
+            Output::RestoreCommandSuccess => "✅ <bold>Command restored</bold>\n".to_string(),
+            Output::TrashEmpty => "<bold>The trash is empty</bold>\n".to_string(),
+            Output::TrashListed(num_cmds) => {
+                format!("<bold>{} commands in the trash</bold>\n", num_cmds)
+            }
+            Output::TrashPurged(num_cmds) => {
+                format!(
+                    "✅ <bold>{} commands permanently deleted</bold>\n",
+                    num_cmds
+                )
+            }
+            Output::HistoryListed(num_changes) => {
+                format!("<bold>{} changes</bold>\n", num_changes)
+            }
+            Output::RevertCommandSuccess => "✅ <bold>Command reverted</bold>\n".to_string(),
+            Output::NoPreviousVersions => {
+                "<bold>The command has not been updated</bold>\n".to_string()
+            }
+            Output::UndoSuccess(action, command) => {
+                let change = match action {
+                    RevisionAction::Add => "Addition",
+                    RevisionAction::Update => "Update",
+                    RevisionAction::Delete => "Deletion",
+                    RevisionAction::Restore => "Restoration",
+                };
+                format!("✅ <bold>{} of {:?} undone</bold>\n", change, command)
+            }
+            Output::NothingToUndo => "<bold>Nothing to undo</bold>\n".to_string(),
             Output::ExportCommandsSuccess(file) => {
                 format!("✅ <bold>Commands exported to {:?}</bold>\n", file)
             }
-            Output::ImportCommandsSuccess(num_cmds, file) => {
-                format!(
+            Output::ImportCommandsSuccess(summary, file) => {
+                let mut message = format!(
                     "✅ <bold>{} commands imported from {:?}</bold>\n",
-                    num_cmds, file
+                    summary.imported, file
+                );
+                if summary.merged > 0 {
+                    message += &format!("{} duplicates merged\n", summary.merged);
+                }
+                if summary.skipped > 0 {
+                    message += &format!("{} duplicates skipped\n", summary.skipped);
+                }
+                message
+            }
+            Output::BackupSuccess(file) => {
+                format!("✅ <bold>Stack backed up to {:?}</bold>\n", file)
+            }
+            Output::RestoreSuccess(file) => {
+                format!("✅ <bold>Backup {:?} restored</bold>\n", file)
+            }
+            Output::RestoreCancelled => "<bold>Backup not restored</bold>\n".to_string(),
+            Output::InvalidBackup(reason) => {
+                format!("<bold>The backup cannot be restored: {}</bold>\n", reason)
+            }
+            Output::DoctorHealthy => "✅ <bold>No problems found</bold>\n".to_string(),
+            Output::DoctorProblemsFound(num_problems) => {
+                format!("<bold>{} problems found</bold>\n", num_problems)
+            }
+            Output::DuplicatesListed(num_groups) => {
+                format!("<bold>{} groups of duplicate commands</bold>\n", num_groups)
+            }
+            Output::NoDuplicates => "<bold>No duplicate commands found</bold>\n".to_string(),
+            Output::DedupeSuccess(num_cmds) => {
+                format!(
+                    "✅ <bold>{} duplicates merged and moved to the trash</bold>\n",
+                    num_cmds
                 )
             }
+            Output::DedupeCancelled => "<bold>Duplicates not merged</bold>\n".to_string(),
+            Output::StacksListed(num_stacks) => format!("<bold>{} stacks</bold>\n", num_stacks),
+            Output::StackCreated(name) => format!("✅ <bold>Stack {} created</bold>\n", name),
+            Output::StackActivated(name) => {
+                format!("✅ <bold>Stack {} is now active</bold>\n", name)
+            }
+            Output::StackDeleted(name) => format!("✅ <bold>Stack {} deleted</bold>\n", name),
+            Output::StackDeleteCancelled => "<bold>Stack not deleted</bold>\n".to_string(),
+            Output::SecretsListed(num_secrets) => {
+                format!("<bold>{} secrets</bold>\n", num_secrets)
+            }
+            Output::SecretSet(name) => format!("✅ <bold>Secret {} saved</bold>\n", name),
+            Output::SecretDeleted(name) => format!("✅ <bold>Secret {} deleted</bold>\n", name),
+            Output::SecretDeleteCancelled => "<bold>Secret not deleted</bold>\n".to_string(),
+            Output::SecretNotFound(name) => {
+                format!("<bold>The secret {} does not exist</bold>\n", name)
+            }
             Output::CommandCopiedToClipboard => {
                 "✅ <bold>Command copied to clipboard</bold>\n".to_string()
             }
@@ -125,8 +249,19 @@ pub enum ErrorOutput {
     UpdateCommand,
     DeleteCommand,
     SearchCommand,
+    ShowCommand,
+    Dedupe,
+    Doctor,
+    Stack,
+    Secret,
+    IncorrectPassphrase,
+    Trash,
+    History,
+    Undo,
     Export,
     Import,
+    Backup,
+    Restore,
     Logger,
     Logic,
     Config,
@@ -140,8 +275,19 @@ impl fmt::Display for ErrorOutput {
             ErrorOutput::UpdateCommand => "Failed to update command",
             ErrorOutput::DeleteCommand => "Failed to delete command",
             ErrorOutput::SearchCommand => "Failed to search command",
+            ErrorOutput::ShowCommand => "Failed to fetch command",
+            ErrorOutput::Dedupe => "Failed to merge duplicate commands",
+            ErrorOutput::Doctor => "Failed to check the stack",
+            ErrorOutput::Stack => "Failed to update the stacks",
+            ErrorOutput::Secret => "Failed to update the secrets",
+            ErrorOutput::IncorrectPassphrase => "Incorrect passphrase",
+            ErrorOutput::Trash => "Failed to update the trash",
+            ErrorOutput::History => "Failed to fetch the command history",
+            ErrorOutput::Undo => "Failed to undo the last change",
             ErrorOutput::Export => "Failed to export stack",
             ErrorOutput::Import => "Failed to import stack",
+            ErrorOutput::Backup => "Failed to back up stack",
+            ErrorOutput::Restore => "Failed to restore the backup",
             ErrorOutput::Logger => "Failed to initialize the logger",
             ErrorOutput::Logic => "Failed to initialize the logic crate",
             ErrorOutput::Config => "Failed to update the config",
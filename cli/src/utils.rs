@@ -1,3 +1,5 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub fn truncate_string(s: &str, width: usize) -> String {
     if s.chars().count() > width {
         if width < 3 {
@@ -20,6 +22,28 @@ pub fn none_if_empty(s: String) -> Option<String> {
     }
 }
 
+/// Describes how many days ago a unix timestamp was
+pub fn format_days_ago(timestamp: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+
+    match (now - timestamp) / (24 * 60 * 60) {
+        days if days <= 0 => "Today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{} days ago", days),
+    }
+}
+
+/// Splits a comma-separated list of tags, skipping blank entries
+pub fn split_tags(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +76,15 @@ mod tests {
             Some("  non-empty  ".to_string())
         );
     }
+
+    #[test]
+    fn test_split_tags() {
+        assert!(split_tags("").is_empty());
+        assert!(split_tags(" , ,").is_empty());
+        assert_eq!(split_tags("k8s/deploy"), vec!["k8s/deploy".to_string()]);
+        assert_eq!(
+            split_tags("k8s/deploy, oncall/runbook ,"),
+            vec!["k8s/deploy".to_string(), "oncall/runbook".to_string()]
+        );
+    }
 }
@@ -0,0 +1,323 @@
+//! # Backups
+//!
+//! A backup is a complete copy of a database that is written with
+//! `VACUUM INTO`, which gives a consistent snapshot even while other
+//! connections are using the database.
+//!
+//! Restoring a backup never replaces the database file, as other connections
+//! (e.g. the GUI app) may be using it. Instead, the backup is validated and
+//! migrated to the current schema in a temporary copy, which is then attached
+//! to the database so that every command, tag, use and revision can be
+//! replaced in a single transaction.
+
+use sea_query::Iden;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, Row, SqliteConnection, SqlitePool};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{env, fs};
+use uuid::Uuid;
+
+use super::migrations::SCHEMA_VERSION;
+use super::sqlite::{self, SqliteConnectionPool};
+use super::{BackupDatabaseError, RestoreDatabaseError, SqlTxError};
+
+/// Writes a consistent copy of the database to a new file
+pub(crate) async fn vacuum_into(
+    pool: &SqlitePool,
+    destination: &Path,
+) -> Result<(), BackupDatabaseError> {
+    let destination_str = destination
+        .to_str()
+        .ok_or_else(|| BackupDatabaseError::InvalidPath(destination.to_path_buf()))?;
+    if destination.exists() {
+        return Err(BackupDatabaseError::AlreadyExists(
+            destination_str.to_string(),
+        ));
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(destination_str)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Checks that a file is a database that can be restored, without modifying
+/// it. Returns the schema version of the database
+pub async fn validate_backup(path: &Path) -> Result<i64, RestoreDatabaseError> {
+    let copy_path = temp_copy(path)?;
+    let result = validate_copy(&copy_path).await;
+    let _ = sqlite::remove_database(&copy_path);
+
+    result
+}
+
+/// Copies a backup to a new file in the temporary directory, as checking the
+/// search index of a database and migrating it both write to the file
+fn temp_copy(backup: &Path) -> Result<PathBuf, RestoreDatabaseError> {
+    let copy_path = env::temp_dir().join(format!("cmdstack-restore-{}.sqlite", Uuid::new_v4()));
+    fs::copy(backup, &copy_path)?;
+
+    Ok(copy_path)
+}
+
+async fn validate_copy(copy_path: &Path) -> Result<i64, RestoreDatabaseError> {
+    let copy_str = copy_path
+        .to_str()
+        .ok_or_else(|| RestoreDatabaseError::InvalidPath(copy_path.to_path_buf()))?;
+    let mut conn = SqliteConnectOptions::from_str(copy_str)?.connect().await?;
+    let result = check_backup(&mut conn).await;
+    conn.close().await?;
+
+    result
+}
+
+async fn check_backup(conn: &mut SqliteConnection) -> Result<i64, RestoreDatabaseError> {
+    // Reading the schema is the first access to the file, so it fails if the
+    // file is not a database at all
+    let command_tables: i64 =
+        sqlx::query("SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(sqlite::Command::Table.to_string())
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|_| RestoreDatabaseError::NotADatabase)?
+            .get(0);
+    if command_tables == 0 {
+        return Err(RestoreDatabaseError::NotADatabase);
+    }
+
+    let schema_version: i64 = sqlx::query("PRAGMA user_version")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0);
+    if schema_version > SCHEMA_VERSION {
+        return Err(RestoreDatabaseError::NewerSchemaVersion(
+            schema_version,
+            SCHEMA_VERSION,
+        ));
+    }
+
+    let integrity: String = sqlx::query("PRAGMA quick_check")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0);
+    if integrity != "ok" {
+        return Err(RestoreDatabaseError::IntegrityCheck(integrity));
+    }
+
+    Ok(schema_version)
+}
+
+/// Replaces the contents of the database with the contents of a backup
+pub(crate) async fn restore_from(
+    pool: &SqlitePool,
+    backup: &Path,
+) -> Result<(), RestoreDatabaseError> {
+    // The backup itself is left untouched
+    let copy_path = temp_copy(backup)?;
+    let result = restore_copy(pool, &copy_path).await;
+    let _ = sqlite::remove_database(&copy_path);
+
+    result
+}
+
+async fn restore_copy(pool: &SqlitePool, copy_path: &Path) -> Result<(), RestoreDatabaseError> {
+    validate_copy(copy_path).await?;
+
+    let copy_str = sqlite::path_to_string(copy_path.to_path_buf())?;
+    SqliteConnectionPool::new(Some(copy_str.clone()))
+        .await?
+        .pool
+        .close()
+        .await;
+
+    // Attached databases belong to a connection, so every statement has to
+    // run on the same one
+    let mut conn = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS backup")
+        .bind(&copy_str)
+        .execute(&mut *conn)
+        .await?;
+    let result = replace_contents(&mut conn).await;
+    sqlx::query("DETACH DATABASE backup")
+        .execute(&mut *conn)
+        .await?;
+
+    result
+}
+
+/// Replaces every row of the tables of the database with the rows of the same
+/// tables in the attached backup. The search index is kept in sync by its
+/// triggers
+async fn replace_contents(conn: &mut SqliteConnection) -> Result<(), RestoreDatabaseError> {
+    // Tables that reference another table come after it
+    let tables = [
+        sqlite::Command::Table.to_string(),
+        sqlite::Tag::Table.to_string(),
+        sqlite::CommandTag::Table.to_string(),
+        sqlite::CommandUsage::Table.to_string(),
+        sqlite::CommandRevision::Table.to_string(),
+    ];
+
+    let mut tx = conn.begin().await.map_err(SqlTxError::TxBegin)?;
+
+    for table in tables.iter().rev() {
+        sqlx::query(&format!("DELETE FROM main.{}", table))
+            .execute(&mut *tx)
+            .await?;
+    }
+    for table in tables.iter() {
+        let columns = sqlx::query("SELECT name FROM pragma_table_info(?, 'main')")
+            .bind(table)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| format!("\"{}\"", row.get::<String, _>(0)))
+            .collect::<Vec<String>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.{table} ({columns}) SELECT {columns} FROM backup.{table}"
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dal::sqlite_dal::SqliteDal;
+    use crate::dal::store::CommandStore;
+    use crate::models::*;
+    use tempfile::{tempdir, TempDir};
+
+    fn db_path(dir: &TempDir, name: &str) -> PathBuf {
+        dir.path().join(name)
+    }
+
+    async fn dal(dir: &TempDir, name: &str) -> SqliteDal {
+        SqliteDal::new_with_custom_path(db_path(dir, name).to_str().unwrap().to_string())
+            .await
+            .unwrap()
+    }
+
+    fn command(command: &str, tags: &[&str]) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            note: None,
+            favourite: false,
+        }
+    }
+
+    async fn commands(store: &SqliteDal) -> Vec<(String, Vec<String>)> {
+        store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.internal_command.command, c.internal_command.tags))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = tempdir().unwrap();
+        let store = dal(&dir, "database.sqlite").await;
+
+        let kept_id = store
+            .insert_command(command("kubectl get pods", &["k8s"]))
+            .await
+            .unwrap();
+        store
+            .record_command_usage(
+                kept_id,
+                InternalCommandUsage {
+                    action: UsageAction::Copy,
+                    working_directory: None,
+                    resolved_command: "kubectl get pods".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        let before = commands(&store).await;
+
+        let backup_path = db_path(&dir, "backup.sqlite");
+        store.backup(&backup_path).await.unwrap();
+        assert_eq!(validate_backup(&backup_path).await.unwrap(), SCHEMA_VERSION);
+
+        // A backup never overwrites a file
+        assert!(matches!(
+            store.backup(&backup_path).await,
+            Err(BackupDatabaseError::AlreadyExists(_))
+        ));
+
+        // Changes made after the backup are undone by restoring it
+        store.insert_command(command("ls", &["fs"])).await.unwrap();
+        store.delete_command(kept_id).await.unwrap();
+        store.purge_trash(i64::MAX).await.unwrap();
+        assert_ne!(commands(&store).await, before);
+
+        store.restore(&backup_path).await.unwrap();
+        assert_eq!(commands(&store).await, before);
+        let usage = store.get_command_usage(kept_id).await.unwrap();
+        assert_eq!(usage.len(), 1);
+
+        // The search index is rebuilt along with the commands
+        let found = store
+            .search_commands(Some("kubectl"), Some("k8s"), &CommandQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(found.commands.len(), 1);
+        let found = store
+            .search_commands(Some("ls"), None, &CommandQuery::default())
+            .await
+            .unwrap();
+        assert!(found.commands.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_backups_are_rejected() {
+        let dir = tempdir().unwrap();
+        let store = dal(&dir, "database.sqlite").await;
+        store.insert_command(command("pwd", &[])).await.unwrap();
+
+        let missing = db_path(&dir, "missing.sqlite");
+        assert!(matches!(
+            store.restore(&missing).await,
+            Err(RestoreDatabaseError::Io(_))
+        ));
+
+        let not_a_database = db_path(&dir, "notes.txt");
+        fs::write(&not_a_database, "not a database").unwrap();
+        assert!(matches!(
+            store.restore(&not_a_database).await,
+            Err(RestoreDatabaseError::NotADatabase)
+        ));
+
+        let newer = db_path(&dir, "newer.sqlite");
+        store.backup(&newer).await.unwrap();
+        let newer_pool = SqlitePool::connect(newer.to_str().unwrap()).await.unwrap();
+        sqlx::query(&format!("PRAGMA user_version = {}", SCHEMA_VERSION + 1))
+            .execute(&newer_pool)
+            .await
+            .unwrap();
+        newer_pool.close().await;
+        assert!(matches!(
+            store.restore(&newer).await,
+            Err(RestoreDatabaseError::NewerSchemaVersion(_, _))
+        ));
+
+        // Nothing was changed by the failed restores
+        assert_eq!(
+            commands(&store).await,
+            vec![("pwd".to_string(), Vec::new())]
+        );
+    }
+}
@@ -0,0 +1,469 @@
+//! # Maintenance
+//!
+//! Checks for problems that the rest of the application assumes never happen:
+//! a corrupted database file, rows that refer to rows that no longer exist,
+//! rows that cannot be read and commands that are missing from the search
+//! index. Foreign keys are enforced on every connection, so orphaned rows are
+//! only left behind by databases that were changed outside of the application.
+//!
+//! Repairing the database deletes what cannot be read or is orphaned, gives
+//! commands with a broken UUID a new one, rebuilds the indexes and reclaims
+//! unused space.
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, Row, SqliteConnection, SqlitePool};
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::{MaintenanceError, SqlTxError};
+use crate::models::*;
+
+/// The rows of each table that are orphaned. Tables that refer to another
+/// table come before it, so that deleting the orphans of a table can only
+/// orphan rows of the tables after it
+const ORPHAN_CONDITIONS: &[(&str, &str)] = &[
+    (
+        "command_tag",
+        "command_id NOT IN (SELECT id FROM command) OR tag_id NOT IN (SELECT id FROM tag)",
+    ),
+    ("tag", "id NOT IN (SELECT tag_id FROM command_tag)"),
+    (
+        "command_usage",
+        "command_id NOT IN (SELECT id FROM command)",
+    ),
+    (
+        "command_revision",
+        "command_id NOT IN (SELECT id FROM command)",
+    ),
+    ("command_fts", "rowid NOT IN (SELECT id FROM command)"),
+];
+
+/// Runs SQLite's integrity check on a database file without migrating it,
+/// which works even if the database cannot be opened by the application.
+/// Returns the problems that were found
+pub async fn check_database_file(path: &Path) -> Result<Vec<String>, MaintenanceError> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| MaintenanceError::InvalidPath(path.to_path_buf()))?;
+    let mut conn = SqliteConnectOptions::from_str(path_str)?.connect().await?;
+    // A file that is not a database at all fails the check instead of
+    // returning problems
+    let corruption = match integrity_check(&mut conn).await {
+        Ok(corruption) => corruption,
+        Err(e) => vec![e.to_string()],
+    };
+    conn.close().await?;
+
+    Ok(corruption)
+}
+
+/// Checks the database for every problem that can be repaired or reported
+pub(crate) async fn check_integrity(
+    pool: &SqlitePool,
+) -> Result<IntegrityReport, MaintenanceError> {
+    let mut conn = pool.acquire().await?;
+
+    let mut orphaned_rows = Vec::new();
+    for (table, condition) in ORPHAN_CONDITIONS {
+        let count: i64 = sqlx::query(&format!("SELECT count(*) FROM {table} WHERE {condition}"))
+            .fetch_one(&mut *conn)
+            .await?
+            .get(0);
+        if count > 0 {
+            orphaned_rows.push(OrphanedRows {
+                table: table.to_string(),
+                count: count as u64,
+            });
+        }
+    }
+
+    let unindexed_commands: i64 =
+        sqlx::query("SELECT count(*) FROM command WHERE id NOT IN (SELECT rowid FROM command_fts)")
+            .fetch_one(&mut *conn)
+            .await?
+            .get(0);
+    let (size_bytes, unused_bytes) = database_size(&mut conn).await?;
+
+    Ok(IntegrityReport {
+        corruption: integrity_check(&mut conn).await?,
+        orphaned_rows,
+        invalid_rows: invalid_rows(&mut conn).await?,
+        unindexed_commands: unindexed_commands as u64,
+        size_bytes,
+        unused_bytes,
+    })
+}
+
+/// Returns the ID and text of every command, including the commands in the
+/// trash, without reading any of their other properties
+pub(crate) async fn command_texts(pool: &SqlitePool) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    Ok(sqlx::query("SELECT id, command FROM command ORDER BY id")
+        .fetch_all(pool)
+        .await?
+        .iter()
+        // Commands that cannot be read are reported by the integrity check
+        .filter_map(|row| Some((row.get(0), row.try_get(1).ok()?)))
+        .collect())
+}
+
+/// Deletes orphaned and unreadable rows, replaces broken UUIDs, rebuilds the
+/// search index and the other indexes and reclaims unused space.
+///
+/// Commands with an empty or unreadable text are left for the user to fix, as
+/// deleting them would lose their tags and note
+pub(crate) async fn repair(pool: &SqlitePool) -> Result<RepairSummary, MaintenanceError> {
+    let mut conn = pool.acquire().await?;
+    let (size_before, _) = database_size(&mut conn).await?;
+    let mut summary = RepairSummary::default();
+
+    let mut tx = conn.begin().await.map_err(SqlTxError::TxBegin)?;
+    for (table, condition) in ORPHAN_CONDITIONS {
+        summary.orphaned_rows_deleted +=
+            sqlx::query(&format!("DELETE FROM {table} WHERE {condition}"))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+    }
+
+    for row in invalid_rows(&mut tx).await? {
+        match row.problem {
+            RowProblem::EmptyCommand => {}
+            RowProblem::Unreadable(_) if row.table == "command" => {}
+            RowProblem::InvalidUuid => {
+                sqlx::query("UPDATE command SET uuid = ? WHERE id = ?")
+                    .bind(Uuid::new_v4().to_string())
+                    .bind(row.id)
+                    .execute(&mut *tx)
+                    .await?;
+                summary.uuids_replaced += 1;
+            }
+            RowProblem::UnknownAction(_) | RowProblem::InvalidTags | RowProblem::Unreadable(_) => {
+                sqlx::query(&format!("DELETE FROM {} WHERE id = ?", row.table))
+                    .bind(row.id)
+                    .execute(&mut *tx)
+                    .await?;
+                summary.invalid_rows_deleted += 1;
+            }
+        }
+    }
+
+    // The search index stores its own copy of every command, so it is
+    // rebuilt from the commands instead of being reindexed
+    sqlx::query("DELETE FROM command_fts")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO command_fts (rowid, command, tags, note) \
+         SELECT command.id, command.command, \
+         (SELECT group_concat(tag.name, ' ') FROM command_tag \
+         INNER JOIN tag ON tag.id = command_tag.tag_id \
+         WHERE command_tag.command_id = command.id), \
+         command.note FROM command",
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("INSERT INTO command_fts (command_fts) VALUES ('optimize')")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+    sqlx::query("REINDEX").execute(&mut *conn).await?;
+    // VACUUM cannot run inside a transaction
+    sqlx::query("VACUUM").execute(&mut *conn).await?;
+
+    let (size_after, _) = database_size(&mut conn).await?;
+    summary.bytes_reclaimed = size_before.saturating_sub(size_after);
+
+    Ok(summary)
+}
+
+/// Runs SQLite's integrity check and returns the problems it found
+async fn integrity_check(conn: &mut SqliteConnection) -> Result<Vec<String>, sqlx::Error> {
+    let problems: Vec<String> = sqlx::query("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    Ok(problems
+        .into_iter()
+        .filter(|problem| problem != "ok")
+        .collect())
+}
+
+/// Returns the size of the database file and how much of it is unused
+async fn database_size(conn: &mut SqliteConnection) -> Result<(u64, u64), sqlx::Error> {
+    let page_size: i64 = sqlx::query("PRAGMA page_size")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0);
+    let page_count: i64 = sqlx::query("PRAGMA page_count")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0);
+    let freelist_count: i64 = sqlx::query("PRAGMA freelist_count")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0);
+
+    Ok((
+        (page_size * page_count) as u64,
+        (page_size * freelist_count) as u64,
+    ))
+}
+
+/// Returns the commands, uses and revisions that cannot be read
+async fn invalid_rows(conn: &mut SqliteConnection) -> Result<Vec<InvalidRow>, sqlx::Error> {
+    let mut invalid_rows = Vec::new();
+    let mut invalid = |table: &str, id: i64, problem: RowProblem| {
+        invalid_rows.push(InvalidRow {
+            table: table.to_string(),
+            id,
+            problem,
+        })
+    };
+
+    for row in sqlx::query("SELECT id, command, uuid FROM command ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await?
+    {
+        let id: i64 = row.get(0);
+        match row.try_get::<String, _>(1) {
+            Ok(command) if command.trim().is_empty() => {
+                invalid("command", id, RowProblem::EmptyCommand)
+            }
+            Ok(_) => {}
+            Err(_) => invalid("command", id, unreadable("command")),
+        }
+        // A UUID that cannot be read is replaced like any other invalid UUID
+        let uuid: Option<String> = row.try_get(2).unwrap_or_default();
+        if uuid.is_none_or(|uuid| Uuid::parse_str(&uuid).is_err()) {
+            invalid("command", id, RowProblem::InvalidUuid);
+        }
+    }
+
+    for row in sqlx::query("SELECT id, action FROM command_usage ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await?
+    {
+        let id: i64 = row.get(0);
+        match row.try_get::<String, _>(1) {
+            Ok(action) if UsageAction::from_str(&action).is_err() => {
+                invalid("command_usage", id, RowProblem::UnknownAction(action))
+            }
+            Ok(_) => {}
+            Err(_) => invalid("command_usage", id, unreadable("action")),
+        }
+    }
+
+    for row in sqlx::query("SELECT id, action, tags FROM command_revision ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await?
+    {
+        let id: i64 = row.get(0);
+        let problem = match (row.try_get::<String, _>(1), row.try_get::<String, _>(2)) {
+            (Err(_), _) => Some(unreadable("action")),
+            (Ok(action), _) if RevisionAction::from_str(&action).is_err() => {
+                Some(RowProblem::UnknownAction(action))
+            }
+            (_, Err(_)) => Some(unreadable("tags")),
+            (_, Ok(tags)) if serde_json::from_str::<Vec<String>>(&tags).is_err() => {
+                Some(RowProblem::InvalidTags)
+            }
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            invalid("command_revision", id, problem);
+        }
+    }
+
+    Ok(invalid_rows)
+}
+
+fn unreadable(column: &str) -> RowProblem {
+    RowProblem::Unreadable(column.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dal::sqlite_dal::SqliteDal;
+    use crate::dal::store::CommandStore;
+    use tempfile::TempDir;
+
+    fn command(command: &str, tags: &[&str]) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            note: None,
+            favourite: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_and_repair() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("database.sqlite");
+        let store = SqliteDal::new_with_custom_path(path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let first_id = store.insert_command(command("ls", &["fs"])).await.unwrap();
+        let second_id = store.insert_command(command("pwd", &["fs"])).await.unwrap();
+
+        let report = store.check_integrity().await.unwrap();
+        assert!(report.is_healthy(), "{:?}", report);
+
+        // Break the database the way other tools could, without foreign keys
+        let pool = &store.sqlite_conn.pool;
+        let mut conn = pool.acquire().await.unwrap();
+        for statement in [
+            "PRAGMA foreign_keys = OFF".to_string(),
+            "INSERT INTO command_usage (command_id, timestamp, action, resolved_command) \
+             VALUES (999, 0, 'Copy', 'ls')"
+                .to_string(),
+            "INSERT INTO tag (name) VALUES ('unused')".to_string(),
+            "UPDATE command_revision SET action = 'Rename' WHERE id = 1".to_string(),
+            format!("UPDATE command SET uuid = 'not-a-uuid' WHERE id = {second_id}"),
+            format!("DELETE FROM command_fts WHERE rowid = {first_id}"),
+            "PRAGMA foreign_keys = ON".to_string(),
+        ] {
+            sqlx::query(&statement).execute(&mut *conn).await.unwrap();
+        }
+        drop(conn);
+
+        let report = store.check_integrity().await.unwrap();
+        assert!(report.corruption.is_empty());
+        assert_eq!(
+            report.orphaned_rows,
+            vec![
+                OrphanedRows {
+                    table: "tag".to_string(),
+                    count: 1
+                },
+                OrphanedRows {
+                    table: "command_usage".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            report.invalid_rows,
+            vec![
+                InvalidRow {
+                    table: "command".to_string(),
+                    id: second_id,
+                    problem: RowProblem::InvalidUuid,
+                },
+                InvalidRow {
+                    table: "command_revision".to_string(),
+                    id: 1,
+                    problem: RowProblem::UnknownAction("Rename".to_string()),
+                },
+            ]
+        );
+        assert_eq!(report.unindexed_commands, 1);
+
+        let summary = store.repair().await.unwrap();
+        assert_eq!(summary.orphaned_rows_deleted, 2);
+        assert_eq!(summary.invalid_rows_deleted, 1);
+        assert_eq!(summary.uuids_replaced, 1);
+
+        let report = store.check_integrity().await.unwrap();
+        assert!(report.is_healthy(), "{:?}", report);
+        let found = store
+            .search_commands(Some("ls"), None, &CommandQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(found.commands.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_and_repair_unreadable_rows() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("database.sqlite");
+        let store = SqliteDal::new_with_custom_path(path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let first_id = store.insert_command(command("ls", &["fs"])).await.unwrap();
+        let second_id = store.insert_command(command("pwd", &[])).await.unwrap();
+        store
+            .record_command_usage(
+                first_id,
+                InternalCommandUsage {
+                    action: UsageAction::Copy,
+                    working_directory: None,
+                    resolved_command: "ls".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        // Values of the wrong type, the way other tools could write them
+        let pool = &store.sqlite_conn.pool;
+        for statement in [
+            format!("UPDATE command SET command = X'6C73' WHERE id = {first_id}"),
+            format!("UPDATE command SET uuid = X'00' WHERE id = {second_id}"),
+            "UPDATE command_usage SET action = X'436F7079'".to_string(),
+            "UPDATE command_revision SET tags = X'5B5D' WHERE id = 1".to_string(),
+        ] {
+            sqlx::query(&statement).execute(pool).await.unwrap();
+        }
+
+        let report = store.check_integrity().await.unwrap();
+        assert_eq!(
+            report.invalid_rows,
+            vec![
+                InvalidRow {
+                    table: "command".to_string(),
+                    id: first_id,
+                    problem: RowProblem::Unreadable("command".to_string()),
+                },
+                InvalidRow {
+                    table: "command".to_string(),
+                    id: second_id,
+                    problem: RowProblem::InvalidUuid,
+                },
+                InvalidRow {
+                    table: "command_usage".to_string(),
+                    id: 1,
+                    problem: RowProblem::Unreadable("action".to_string()),
+                },
+                InvalidRow {
+                    table: "command_revision".to_string(),
+                    id: 1,
+                    problem: RowProblem::Unreadable("tags".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            store.get_command_texts().await.unwrap(),
+            vec![(second_id, "pwd".to_string())]
+        );
+
+        // The command is left for the user to fix, and the rest is repaired
+        let summary = store.repair().await.unwrap();
+        assert_eq!(summary.invalid_rows_deleted, 2);
+        assert_eq!(summary.uuids_replaced, 1);
+        let report = store.check_integrity().await.unwrap();
+        assert_eq!(report.invalid_rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_database_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("database.sqlite");
+        SqliteDal::new_with_custom_path(path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        assert!(check_database_file(&path).await.unwrap().is_empty());
+
+        let not_a_database = dir.path().join("notes.txt");
+        std::fs::write(&not_a_database, "not a database").unwrap();
+        assert!(!check_database_file(&not_a_database)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
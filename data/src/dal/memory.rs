@@ -0,0 +1,575 @@
+use async_trait::async_trait;
+use std::cmp::Reverse;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+use super::store::{search_words, unix_timestamp, ChangeWatcher, CommandStore, StoreTransaction};
+use super::store::{FRECENCY_BASE_WEIGHT, FRECENCY_BUCKETS};
+use super::{BackupDatabaseError, DeleteCommandError, InsertCommandError, SelectAllCommandsError};
+use super::{MaintenanceError, UndoChangeError, UpdateCommandError, WatchChangesError};
+use super::{
+    RestoreDatabaseError, SelectCommandError, SelectCommandRevisionError, SelectCommandUsageError,
+    SqlTxError,
+};
+use crate::models::*;
+
+/// A command store that only lives in memory
+///
+/// Nothing is persisted, which makes it useful for tests and for trying out
+/// the application without touching the database
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default, Clone)]
+struct MemoryState {
+    /// Commands in the order they were inserted
+    commands: Vec<Command>,
+    usages: Vec<CommandUsage>,
+    /// Revisions in the order they were recorded
+    revisions: Vec<CommandRevision>,
+    next_command_id: i64,
+    next_usage_id: i64,
+    next_revision_id: i64,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        // The state is never left half-updated, so a poisoned lock is still usable
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl MemoryState {
+    fn insert(
+        &mut self,
+        mut command: InternalCommand,
+        identity: CommandIdentity,
+        current_time: i64,
+    ) -> Result<i64, InsertCommandError> {
+        // The database only accepts a UUID once
+        if self.commands.iter().any(|c| c.uuid == identity.uuid) {
+            return Err(InsertCommandError::NoRowsAffected);
+        }
+
+        self.next_command_id += 1;
+        command.tags = clean_tags(&command.tags);
+        self.commands.push(Command {
+            id: self.next_command_id,
+            uuid: identity.uuid,
+            last_used: current_time,
+            created_at: identity.created_at,
+            updated_at: identity.updated_at,
+            deleted_at: None,
+            internal_command: command,
+        });
+        self.record(self.next_command_id, RevisionAction::Add, current_time);
+        Ok(self.next_command_id)
+    }
+
+    fn command_mut(&mut self, command_id: i64) -> Option<&mut Command> {
+        self.commands.iter_mut().find(|c| c.id == command_id)
+    }
+
+    fn update(
+        &mut self,
+        command_id: i64,
+        mut new_command_props: InternalCommand,
+        current_time: i64,
+    ) -> Result<(), UpdateCommandError> {
+        new_command_props.tags = clean_tags(&new_command_props.tags);
+
+        self.record(command_id, RevisionAction::Update, current_time);
+        let command = self
+            .command_mut(command_id)
+            .ok_or(UpdateCommandError::NoRowsAffected)?;
+        command.internal_command = new_command_props;
+        command.updated_at = current_time;
+
+        Ok(())
+    }
+
+    fn delete(&mut self, command_id: i64, current_time: i64) -> Result<(), DeleteCommandError> {
+        let command = self
+            .command_mut(command_id)
+            .filter(|c| c.deleted_at.is_none())
+            .ok_or(DeleteCommandError::NoRowsAffected)?;
+        command.deleted_at = Some(current_time);
+        self.record(command_id, RevisionAction::Delete, current_time);
+
+        Ok(())
+    }
+
+    fn restore(&mut self, command_id: i64, current_time: i64) -> Result<(), UpdateCommandError> {
+        let command = self
+            .command_mut(command_id)
+            .filter(|c| c.deleted_at.is_some())
+            .ok_or(UpdateCommandError::NoRowsAffected)?;
+        command.deleted_at = None;
+        self.record(command_id, RevisionAction::Restore, current_time);
+
+        Ok(())
+    }
+
+    /// Records the current properties of a command along with a change made
+    /// to it. Nothing is recorded if the command does not exist
+    fn record(&mut self, command_id: i64, action: RevisionAction, current_time: i64) {
+        let Some(command) = self.commands.iter().find(|c| c.id == command_id) else {
+            return;
+        };
+        let internal_command = command.internal_command.clone();
+
+        self.next_revision_id += 1;
+        self.revisions.push(CommandRevision {
+            id: self.next_revision_id,
+            command_id,
+            timestamp: current_time,
+            action,
+            internal_command,
+        });
+    }
+
+    /// Returns a page of the commands that pass both the filter and the query
+    fn select(
+        &self,
+        filter: impl Fn(&Command) -> bool,
+        query: &CommandQuery,
+        current_time: i64,
+    ) -> CommandPage {
+        let current_time = query.cursor.map_or(current_time, |cursor| cursor.scored_at);
+        let text = query.text_contains.as_deref().map(str::to_lowercase);
+        let tag_prefix = query.tag_prefix.as_deref().map(str::to_lowercase);
+        let matches_query = |c: &Command| {
+            let internal = &c.internal_command;
+            (!query.favourites_only || internal.favourite)
+                && text
+                    .as_ref()
+                    .is_none_or(|t| internal.command.to_lowercase().contains(t))
+                && tag_prefix.as_ref().is_none_or(|p| {
+                    internal
+                        .tags
+                        .iter()
+                        .any(|t| t.to_lowercase().starts_with(p))
+                })
+        };
+
+        let mut commands: Vec<(Command, CommandCursor)> = self
+            .commands
+            .iter()
+            .filter(|c| c.deleted_at.is_none())
+            .filter(|c| matches_query(c) && filter(c))
+            .map(|c| {
+                let cursor = CommandCursor {
+                    id: c.id,
+                    last_used: c.last_used,
+                    frecency: self.frecency(c.id, current_time),
+                    scored_at: current_time,
+                };
+                (c.clone(), cursor)
+            })
+            .collect();
+        commands.sort_by_key(|(_, cursor)| sort_key(query.sort, cursor));
+
+        if let Some(after) = &query.cursor {
+            let after = sort_key(query.sort, after);
+            commands.retain(|(_, cursor)| sort_key(query.sort, cursor) > after);
+        }
+        let mut commands: Vec<(Command, CommandCursor)> =
+            commands.into_iter().skip(query.offset as usize).collect();
+
+        let mut next_cursor = None;
+        if let Some(limit) = query.limit.map(|limit| limit as usize) {
+            if commands.len() > limit {
+                commands.truncate(limit);
+                next_cursor = commands.last().map(|(_, cursor)| *cursor);
+            }
+        }
+
+        CommandPage {
+            commands: commands.into_iter().map(|(command, _)| command).collect(),
+            next_cursor,
+        }
+    }
+
+    fn frecency(&self, command_id: i64, current_time: i64) -> i64 {
+        self.usages
+            .iter()
+            .filter(|u| u.command_id == command_id)
+            .map(|u| {
+                FRECENCY_BUCKETS
+                    .iter()
+                    .find(|(max_age, _)| u.timestamp >= current_time - max_age)
+                    .map_or(FRECENCY_BASE_WEIGHT, |(_, weight)| *weight)
+            })
+            .sum()
+    }
+}
+
+#[async_trait]
+impl CommandStore for MemoryStore {
+    async fn query_commands(
+        &self,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError> {
+        let current_time = unix_timestamp()?;
+        Ok(self.state().select(|_| true, query, current_time))
+    }
+
+    async fn get_command(&self, command_id: i64) -> Result<Command, SelectCommandError> {
+        self.state()
+            .commands
+            .iter()
+            .find(|c| c.id == command_id)
+            .cloned()
+            .ok_or(SelectCommandError::NotFound(command_id))
+    }
+
+    async fn get_command_by_uuid(&self, uuid: Uuid) -> Result<Command, SelectCommandError> {
+        self.state()
+            .commands
+            .iter()
+            .find(|c| c.uuid == uuid)
+            .cloned()
+            .ok_or(SelectCommandError::UuidNotFound(uuid))
+    }
+
+    async fn get_command_by_text(
+        &self,
+        command: &str,
+    ) -> Result<Option<Command>, SelectAllCommandsError> {
+        Ok(self
+            .state()
+            .commands
+            .iter()
+            .find(|c| c.deleted_at.is_none() && c.internal_command.command == command)
+            .cloned())
+    }
+
+    async fn search_commands(
+        &self,
+        command: Option<&str>,
+        tag: Option<&str>,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError> {
+        let command_search = command.map(lowercase_words).unwrap_or_default();
+        let tag_search = tag.map(lowercase_words).unwrap_or_default();
+
+        let current_time = unix_timestamp()?;
+        let matches = |c: &Command| {
+            let internal = &c.internal_command;
+            let tag_text = internal.tags.join(" ");
+            let all_text = [
+                internal.command.as_str(),
+                tag_text.as_str(),
+                internal.note.as_deref().unwrap_or_default(),
+            ]
+            .join(" ");
+
+            words_match(&command_search, &all_text) || words_match(&tag_search, &tag_text)
+        };
+
+        Ok(self.state().select(matches, query, current_time))
+    }
+
+    async fn insert_command(&self, command: InternalCommand) -> Result<i64, InsertCommandError> {
+        let current_time = unix_timestamp()?;
+        self.state()
+            .insert(command, CommandIdentity::new(current_time), current_time)
+    }
+
+    async fn insert_mulitple_commands(
+        &self,
+        commands: Vec<InternalCommand>,
+    ) -> Result<u64, InsertCommandError> {
+        if commands.is_empty() {
+            return Err(InsertCommandError::NoRowsAffected);
+        }
+        let current_time = unix_timestamp()?;
+
+        let mut state = self.state();
+        let rows_affected = commands.len() as u64;
+        for command in commands {
+            state.insert(command, CommandIdentity::new(current_time), current_time)?;
+        }
+
+        Ok(rows_affected)
+    }
+
+    async fn update_command_last_used_property(
+        &self,
+        command_id: i64,
+    ) -> Result<(), UpdateCommandError> {
+        let current_time = unix_timestamp()?;
+
+        let mut state = self.state();
+        let command = state
+            .command_mut(command_id)
+            .ok_or(UpdateCommandError::NoRowsAffected)?;
+        command.last_used = current_time;
+
+        Ok(())
+    }
+
+    async fn record_command_usage(
+        &self,
+        command_id: i64,
+        usage: InternalCommandUsage,
+    ) -> Result<(), UpdateCommandError> {
+        let current_time = unix_timestamp()?;
+
+        let mut state = self.state();
+        let command = state
+            .command_mut(command_id)
+            .ok_or(UpdateCommandError::NoRowsAffected)?;
+        command.last_used = current_time;
+
+        state.next_usage_id += 1;
+        let id = state.next_usage_id;
+        state.usages.push(CommandUsage {
+            id,
+            command_id,
+            timestamp: current_time,
+            internal_usage: usage,
+        });
+
+        Ok(())
+    }
+
+    async fn get_command_usage(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandUsage>, SelectCommandUsageError> {
+        let mut usages: Vec<CommandUsage> = self
+            .state()
+            .usages
+            .iter()
+            .filter(|u| u.command_id == command_id)
+            .cloned()
+            .collect();
+        usages.sort_by_key(|u| std::cmp::Reverse((u.timestamp, u.id)));
+
+        Ok(usages)
+    }
+
+    async fn delete_command(&self, command_id: i64) -> Result<(), DeleteCommandError> {
+        let current_time = unix_timestamp()?;
+        self.state().delete(command_id, current_time)
+    }
+
+    async fn get_trashed_commands(&self) -> Result<Vec<Command>, SelectAllCommandsError> {
+        let mut commands: Vec<Command> = self
+            .state()
+            .commands
+            .iter()
+            .filter(|c| c.deleted_at.is_some())
+            .cloned()
+            .collect();
+        commands.sort_by_key(|c| std::cmp::Reverse(c.deleted_at));
+
+        Ok(commands)
+    }
+
+    async fn restore_command(&self, command_id: i64) -> Result<(), UpdateCommandError> {
+        let current_time = unix_timestamp()?;
+        self.state().restore(command_id, current_time)
+    }
+
+    async fn purge_trash(&self, deleted_before: i64) -> Result<u64, DeleteCommandError> {
+        let mut state = self.state();
+        let is_expired = |c: &Command| c.deleted_at.is_some_and(|t| t < deleted_before);
+
+        let purged: Vec<i64> = state
+            .commands
+            .iter()
+            .filter(|c| is_expired(c))
+            .map(|c| c.id)
+            .collect();
+        state.commands.retain(|c| !is_expired(c));
+        state.usages.retain(|u| !purged.contains(&u.command_id));
+        state.revisions.retain(|r| !purged.contains(&r.command_id));
+
+        Ok(purged.len() as u64)
+    }
+
+    async fn update_command(
+        &self,
+        command_id: i64,
+        new_command_props: InternalCommand,
+    ) -> Result<(), UpdateCommandError> {
+        let current_time = unix_timestamp()?;
+        self.state()
+            .update(command_id, new_command_props, current_time)
+    }
+
+    async fn get_command_revisions(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandRevision>, SelectCommandRevisionError> {
+        Ok(self
+            .state()
+            .revisions
+            .iter()
+            .rev()
+            .filter(|r| r.command_id == command_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn undo_last_change(&self) -> Result<Option<CommandRevision>, UndoChangeError> {
+        let current_time = unix_timestamp()?;
+
+        let mut state = self.state();
+        let Some(revision) = state.revisions.last().cloned() else {
+            return Ok(None);
+        };
+        let command = state
+            .command_mut(revision.command_id)
+            .ok_or(UpdateCommandError::NoRowsAffected)?;
+
+        match revision.action {
+            RevisionAction::Add | RevisionAction::Restore => {
+                command.deleted_at = Some(current_time)
+            }
+            RevisionAction::Delete => command.deleted_at = None,
+            RevisionAction::Update => {
+                command.internal_command = revision.internal_command.clone();
+                command.updated_at = current_time;
+            }
+        }
+        state.revisions.pop();
+
+        Ok(Some(revision))
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StoreTransaction + '_>, SqlTxError> {
+        let state = self.state().clone();
+        Ok(Box::new(MemoryTransaction { store: self, state }))
+    }
+
+    async fn backup(&self, _destination: &Path) -> Result<(), BackupDatabaseError> {
+        Err(BackupDatabaseError::Unsupported)
+    }
+
+    async fn restore(&self, _backup: &Path) -> Result<(), RestoreDatabaseError> {
+        Err(RestoreDatabaseError::Unsupported)
+    }
+
+    async fn get_command_texts(&self) -> Result<Vec<(i64, String)>, SelectAllCommandsError> {
+        Ok(self
+            .state()
+            .commands
+            .iter()
+            .map(|c| (c.id, c.internal_command.command.clone()))
+            .collect())
+    }
+
+    // The store only holds what it was given, so there is nothing to break
+    async fn check_integrity(&self) -> Result<IntegrityReport, MaintenanceError> {
+        Ok(IntegrityReport::default())
+    }
+
+    async fn repair(&self) -> Result<RepairSummary, MaintenanceError> {
+        Ok(RepairSummary::default())
+    }
+
+    // Nothing outside of the process can change the store
+    async fn watch_changes(&self) -> Result<Box<dyn ChangeWatcher>, WatchChangesError> {
+        Err(WatchChangesError::Unsupported)
+    }
+}
+
+/// A transaction on a copy of the store's state, which replaces the state of
+/// the store when committed. Changes made to the store outside of the
+/// transaction in the meantime are lost
+struct MemoryTransaction<'a> {
+    store: &'a MemoryStore,
+    state: MemoryState,
+}
+
+#[async_trait]
+impl StoreTransaction for MemoryTransaction<'_> {
+    async fn insert_command(
+        &mut self,
+        command: InternalCommand,
+    ) -> Result<i64, InsertCommandError> {
+        let current_time = unix_timestamp()?;
+        self.state
+            .insert(command, CommandIdentity::new(current_time), current_time)
+    }
+
+    async fn insert_command_with_identity(
+        &mut self,
+        command: InternalCommand,
+        identity: CommandIdentity,
+    ) -> Result<i64, InsertCommandError> {
+        self.state.insert(command, identity, unix_timestamp()?)
+    }
+
+    async fn update_command(
+        &mut self,
+        command_id: i64,
+        new_command_props: InternalCommand,
+    ) -> Result<(), UpdateCommandError> {
+        self.state
+            .update(command_id, new_command_props, unix_timestamp()?)
+    }
+
+    async fn delete_command(&mut self, command_id: i64) -> Result<(), DeleteCommandError> {
+        self.state.delete(command_id, unix_timestamp()?)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), SqlTxError> {
+        *self.store.state() = self.state;
+        Ok(())
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), SqlTxError> {
+        Ok(())
+    }
+}
+
+/// Trims the tags and removes blank and repeated tags, as the database does
+fn clean_tags(tags: &[String]) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !cleaned.iter().any(|t| t == tag) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    cleaned
+}
+
+/// Orders commands like the database does, where commands with a lower key
+/// come first. Commands that tie are ordered by ID, which is the order they
+/// were added in
+fn sort_key(sort: CommandSort, cursor: &CommandCursor) -> (Reverse<i64>, Reverse<i64>, i64) {
+    match sort {
+        CommandSort::Insertion => (Reverse(0), Reverse(0), cursor.id),
+        CommandSort::RecentlyUsed => (Reverse(0), Reverse(cursor.last_used), cursor.id),
+        CommandSort::Frecency => (
+            Reverse(cursor.frecency),
+            Reverse(cursor.last_used),
+            cursor.id,
+        ),
+    }
+}
+
+fn lowercase_words(search: &str) -> Vec<String> {
+    search_words(search).map(str::to_lowercase).collect()
+}
+
+/// Whether every searched word starts a word in the text. An empty search
+/// matches nothing
+fn words_match(search: &[String], text: &str) -> bool {
+    let text = lowercase_words(text);
+    !search.is_empty()
+        && search
+            .iter()
+            .all(|word| text.iter().any(|t| t.starts_with(word.as_str())))
+}
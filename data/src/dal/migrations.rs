@@ -0,0 +1,709 @@
+//! # Migrations
+//!
+//! Versioned schema migrations for the SQLite database. The schema version of a
+//! database is stored in `PRAGMA user_version` and every migration that has not
+//! been applied yet is run, in order, inside a single transaction on connect.
+
+use sea_query::{
+    ColumnDef, Expr, ForeignKey, ForeignKeyAction, Index, Query, SqliteQueryBuilder, Table,
+};
+use sqlx::{Row, SqlitePool};
+use thiserror::Error;
+
+use super::sqlite;
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Database schema version {0} is newer than the supported version {1}")]
+    UnsupportedSchemaVersion(i64, i64),
+
+    #[error("Could not read the schema version: {0}")]
+    ReadSchemaVersion(#[source] sqlx::Error),
+
+    #[error("Could not apply migration {0} ({1}): {2}")]
+    Apply(i64, &'static str, #[source] sqlx::Error),
+
+    #[error("Failed to begin transaction: {0}")]
+    TxBegin(#[source] sqlx::Error),
+
+    #[error("Failed to commit transaction: {0}")]
+    TxCommit(#[source] sqlx::Error),
+}
+
+/// A single schema change. The version of a migration is its position in
+/// [`MIGRATIONS`] (starting from 1), so migrations must only ever be appended.
+struct Migration {
+    description: &'static str,
+    statements: fn() -> Vec<String>,
+}
+
+/// Every migration known to this binary, in the order they must be applied
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Create the command table",
+        statements: create_command_table,
+    },
+    Migration {
+        description: "Move tags into their own table",
+        statements: create_tag_tables,
+    },
+    Migration {
+        description: "Create the command usage table",
+        statements: create_command_usage_table,
+    },
+    Migration {
+        description: "Create the full-text search index",
+        statements: create_command_search_index,
+    },
+    Migration {
+        description: "Add the trash to the command table",
+        statements: add_command_deleted_at,
+    },
+    Migration {
+        description: "Create the command revision table",
+        statements: create_command_revision_table,
+    },
+    Migration {
+        description: "Add UUIDs and creation and update times to the command table",
+        statements: add_command_identity,
+    },
+    Migration {
+        description: "Index the text of commands",
+        statements: create_command_text_index,
+    },
+];
+
+/// The schema version a database has once every migration has been applied
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Returns the schema version stored in the database
+pub(crate) async fn schema_version(pool: &SqlitePool) -> Result<i64, MigrationError> {
+    sqlx::query("PRAGMA user_version")
+        .fetch_one(pool)
+        .await
+        .map(|row| row.get::<i64, _>(0))
+        .map_err(MigrationError::ReadSchemaVersion)
+}
+
+/// Applies all pending migrations to the database.
+///
+/// Fails without modifying the database if it was created by a newer version
+/// of the application.
+pub(crate) async fn run_migrations(pool: &SqlitePool) -> Result<(), MigrationError> {
+    let current_version = schema_version(pool).await?;
+
+    if current_version > SCHEMA_VERSION {
+        return Err(MigrationError::UnsupportedSchemaVersion(
+            current_version,
+            SCHEMA_VERSION,
+        ));
+    }
+    if current_version == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await.map_err(MigrationError::TxBegin)?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current_version as usize) {
+        let version = index as i64 + 1;
+        for statement in (migration.statements)() {
+            sqlx::query(&statement)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| MigrationError::Apply(version, migration.description, e))?;
+        }
+    }
+
+    // PRAGMA statements do not support bound parameters
+    sqlx::query(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| MigrationError::Apply(SCHEMA_VERSION, "Set schema version", e))?;
+
+    tx.commit().await.map_err(MigrationError::TxCommit)
+}
+
+/// Version 1: the original command table.
+///
+/// `IF NOT EXISTS` is kept so databases created before versioning was
+/// introduced are adopted without changes.
+fn create_command_table() -> Vec<String> {
+    vec![Table::create()
+        .table(sqlite::Command::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(sqlite::Command::Id)
+                .integer()
+                .not_null()
+                .primary_key()
+                .auto_increment(),
+        )
+        .col(ColumnDef::new(sqlite::Command::Command).string().not_null())
+        .col(ColumnDef::new(sqlite::Command::Tag).string())
+        .col(ColumnDef::new(sqlite::Command::Note).string())
+        .col(
+            ColumnDef::new(sqlite::Command::LastUsed)
+                .integer()
+                .default(0),
+        )
+        .col(
+            ColumnDef::new(sqlite::Command::Favourite)
+                .boolean()
+                .default(false),
+        )
+        .build(SqliteQueryBuilder)]
+}
+
+/// Version 2: a normalized tag table so a command can have many tags.
+///
+/// The existing single tag of every command is carried over before the
+/// `tag` column is dropped from the command table.
+fn create_tag_tables() -> Vec<String> {
+    let tag_table = Table::create()
+        .table(sqlite::Tag::Table)
+        .col(
+            ColumnDef::new(sqlite::Tag::Id)
+                .integer()
+                .not_null()
+                .primary_key()
+                .auto_increment(),
+        )
+        .col(
+            ColumnDef::new(sqlite::Tag::Name)
+                .string()
+                .not_null()
+                .unique_key(),
+        )
+        .build(SqliteQueryBuilder);
+
+    let command_tag_table = Table::create()
+        .table(sqlite::CommandTag::Table)
+        .col(
+            ColumnDef::new(sqlite::CommandTag::CommandId)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandTag::TagId)
+                .integer()
+                .not_null(),
+        )
+        .primary_key(
+            Index::create()
+                .col(sqlite::CommandTag::CommandId)
+                .col(sqlite::CommandTag::TagId),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .from(sqlite::CommandTag::Table, sqlite::CommandTag::CommandId)
+                .to(sqlite::Command::Table, sqlite::Command::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .from(sqlite::CommandTag::Table, sqlite::CommandTag::TagId)
+                .to(sqlite::Tag::Table, sqlite::Tag::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
+    let copy_tags = Query::insert()
+        .into_table(sqlite::Tag::Table)
+        .columns([sqlite::Tag::Name])
+        .select_from(
+            Query::select()
+                .distinct()
+                .column(sqlite::Command::Tag)
+                .from(sqlite::Command::Table)
+                .and_where(Expr::col(sqlite::Command::Tag).is_not_null())
+                .and_where(Expr::col(sqlite::Command::Tag).ne(""))
+                .to_owned(),
+        )
+        .expect("The select and insert column counts match")
+        .to_string(SqliteQueryBuilder);
+
+    let link_tags = Query::insert()
+        .into_table(sqlite::CommandTag::Table)
+        .columns([sqlite::CommandTag::CommandId, sqlite::CommandTag::TagId])
+        .select_from(
+            Query::select()
+                .column((sqlite::Command::Table, sqlite::Command::Id))
+                .column((sqlite::Tag::Table, sqlite::Tag::Id))
+                .from(sqlite::Command::Table)
+                .inner_join(
+                    sqlite::Tag::Table,
+                    Expr::col((sqlite::Tag::Table, sqlite::Tag::Name))
+                        .equals((sqlite::Command::Table, sqlite::Command::Tag)),
+                )
+                .to_owned(),
+        )
+        .expect("The select and insert column counts match")
+        .to_string(SqliteQueryBuilder);
+
+    let drop_tag_column = Table::alter()
+        .table(sqlite::Command::Table)
+        .drop_column(sqlite::Command::Tag)
+        .build(SqliteQueryBuilder);
+
+    vec![
+        tag_table,
+        command_tag_table,
+        copy_tags,
+        link_tags,
+        drop_tag_column,
+    ]
+}
+
+/// Version 3: a history of every time a command was copied or executed
+fn create_command_usage_table() -> Vec<String> {
+    let command_usage_table = Table::create()
+        .table(sqlite::CommandUsage::Table)
+        .col(
+            ColumnDef::new(sqlite::CommandUsage::Id)
+                .integer()
+                .not_null()
+                .primary_key()
+                .auto_increment(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandUsage::CommandId)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandUsage::Timestamp)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandUsage::Action)
+                .string()
+                .not_null(),
+        )
+        .col(ColumnDef::new(sqlite::CommandUsage::WorkingDirectory).string())
+        .col(
+            ColumnDef::new(sqlite::CommandUsage::ResolvedCommand)
+                .string()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .from(sqlite::CommandUsage::Table, sqlite::CommandUsage::CommandId)
+                .to(sqlite::Command::Table, sqlite::Command::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
+    let command_id_index = Index::create()
+        .name("idx_command_usage_command_id")
+        .table(sqlite::CommandUsage::Table)
+        .col(sqlite::CommandUsage::CommandId)
+        .build(SqliteQueryBuilder);
+
+    vec![command_usage_table, command_id_index]
+}
+
+/// Version 4: an FTS5 index over the command, tags and note of every command.
+///
+/// The row ID of an entry in the index is the ID of its command. Triggers keep
+/// the index in sync with the `command` and `command_tag` tables. These are
+/// written by hand as `sea_query` cannot build virtual tables or triggers.
+fn create_command_search_index() -> Vec<String> {
+    // The tags of a command are indexed as a single space separated string
+    let command_tags = |command_id: &str| {
+        format!(
+            "(SELECT group_concat(tag.name, ' ') FROM command_tag \
+             INNER JOIN tag ON tag.id = command_tag.tag_id \
+             WHERE command_tag.command_id = {command_id})"
+        )
+    };
+
+    vec![
+        "CREATE VIRTUAL TABLE command_fts USING fts5(command, tags, note)".to_string(),
+        format!(
+            "INSERT INTO command_fts (rowid, command, tags, note) \
+             SELECT command.id, command.command, {}, command.note FROM command",
+            command_tags("command.id")
+        ),
+        "CREATE TRIGGER command_fts_insert AFTER INSERT ON command BEGIN \
+         INSERT INTO command_fts (rowid, command, note) VALUES (new.id, new.command, new.note); \
+         END"
+        .to_string(),
+        "CREATE TRIGGER command_fts_update AFTER UPDATE OF command, note ON command BEGIN \
+         UPDATE command_fts SET command = new.command, note = new.note WHERE rowid = new.id; \
+         END"
+        .to_string(),
+        "CREATE TRIGGER command_fts_delete AFTER DELETE ON command BEGIN \
+         DELETE FROM command_fts WHERE rowid = old.id; \
+         END"
+        .to_string(),
+        format!(
+            "CREATE TRIGGER command_fts_tag_insert AFTER INSERT ON command_tag BEGIN \
+             UPDATE command_fts SET tags = {} WHERE rowid = new.command_id; \
+             END",
+            command_tags("new.command_id")
+        ),
+        format!(
+            "CREATE TRIGGER command_fts_tag_delete AFTER DELETE ON command_tag BEGIN \
+             UPDATE command_fts SET tags = {} WHERE rowid = old.command_id; \
+             END",
+            command_tags("old.command_id")
+        ),
+    ]
+}
+
+/// Version 5: commands are moved to the trash instead of being deleted
+fn add_command_deleted_at() -> Vec<String> {
+    let deleted_at_column = Table::alter()
+        .table(sqlite::Command::Table)
+        .add_column(ColumnDef::new(sqlite::Command::DeletedAt).integer())
+        .build(SqliteQueryBuilder);
+
+    vec![deleted_at_column]
+}
+
+/// Version 6: the properties of a command before every change made to it, so
+/// that changes can be undone
+fn create_command_revision_table() -> Vec<String> {
+    let command_revision_table = Table::create()
+        .table(sqlite::CommandRevision::Table)
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::Id)
+                .integer()
+                .not_null()
+                .primary_key()
+                .auto_increment(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::CommandId)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::Timestamp)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::Action)
+                .string()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::Command)
+                .string()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::Tags)
+                .string()
+                .not_null(),
+        )
+        .col(ColumnDef::new(sqlite::CommandRevision::Note).string())
+        .col(
+            ColumnDef::new(sqlite::CommandRevision::Favourite)
+                .boolean()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .from(
+                    sqlite::CommandRevision::Table,
+                    sqlite::CommandRevision::CommandId,
+                )
+                .to(sqlite::Command::Table, sqlite::Command::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder);
+
+    let command_id_index = Index::create()
+        .name("idx_command_revision_command_id")
+        .table(sqlite::CommandRevision::Table)
+        .col(sqlite::CommandRevision::CommandId)
+        .build(SqliteQueryBuilder);
+
+    vec![command_revision_table, command_id_index]
+}
+
+/// Version 7: a UUID that identifies a command across databases, along with
+/// when the command was created and last updated.
+///
+/// Existing commands are given a random (version 4) UUID. They were created at
+/// their first recorded change, or when they were last used if there is none,
+/// and were last updated by their most recent recorded update
+fn add_command_identity() -> Vec<String> {
+    let add_columns = [
+        ColumnDef::new(sqlite::Command::Uuid).string().to_owned(),
+        ColumnDef::new(sqlite::Command::CreatedAt)
+            .integer()
+            .not_null()
+            .default(0)
+            .to_owned(),
+        ColumnDef::new(sqlite::Command::UpdatedAt)
+            .integer()
+            .not_null()
+            .default(0)
+            .to_owned(),
+    ]
+    .map(|mut column| {
+        Table::alter()
+            .table(sqlite::Command::Table)
+            .add_column(&mut column)
+            .build(SqliteQueryBuilder)
+    });
+
+    let random_hex = |bytes: u32| format!("lower(hex(randomblob({bytes})))");
+    let random_uuid = format!(
+        "{} || '-' || {} || '-4' || substr({}, 2) || '-' || \
+         substr('89ab', 1 + abs(random() % 4), 1) || substr({}, 2) || '-' || {}",
+        random_hex(4),
+        random_hex(2),
+        random_hex(2),
+        random_hex(2),
+        random_hex(6)
+    );
+
+    let uuid_index = Index::create()
+        .name("idx_command_uuid")
+        .table(sqlite::Command::Table)
+        .col(sqlite::Command::Uuid)
+        .unique()
+        .build(SqliteQueryBuilder);
+
+    let mut statements = add_columns.to_vec();
+    statements.extend([
+        format!("UPDATE command SET uuid = {random_uuid}"),
+        "UPDATE command SET created_at = coalesce(\
+         (SELECT min(timestamp) FROM command_revision WHERE command_id = command.id), \
+         last_used, 0)"
+            .to_string(),
+        "UPDATE command SET updated_at = coalesce(\
+         (SELECT max(timestamp) FROM command_revision \
+         WHERE command_id = command.id AND action = 'Update'), created_at)"
+            .to_string(),
+        uuid_index,
+    ]);
+    statements
+}
+
+/// Version 8: an index on the text of a command, so that duplicates of a new
+/// command are found without reading every command
+fn create_command_text_index() -> Vec<String> {
+    let command_index = Index::create()
+        .name("idx_command_command")
+        .table(sqlite::Command::Table)
+        .col(sqlite::Command::Command)
+        .build(SqliteQueryBuilder);
+
+    vec![command_index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dal::sqlite::{SqliteConnectionPool, SqliteDbConnectionError};
+    use sqlx::sqlite::SqliteConnectOptions;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn db_path(dir: &TempDir) -> String {
+        dir.path()
+            .join("database.sqlite")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_new_database_is_migrated() {
+        let dir = TempDir::new().unwrap();
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir))).await;
+        assert!(conn.is_ok());
+
+        let version = schema_version(&conn.unwrap().pool).await;
+        assert_eq!(version.unwrap(), SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_migrations_are_idempotent() {
+        let dir = TempDir::new().unwrap();
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir))).await;
+        assert!(conn.is_ok());
+        conn.unwrap().pool.close().await;
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir))).await;
+        assert!(conn.is_ok());
+        let version = schema_version(&conn.unwrap().pool).await;
+        assert_eq!(version.unwrap(), SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_newer_database_is_rejected() {
+        let dir = TempDir::new().unwrap();
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir)))
+            .await
+            .unwrap();
+        sqlx::query(&format!("PRAGMA user_version = {}", SCHEMA_VERSION + 1))
+            .execute(&conn.pool)
+            .await
+            .unwrap();
+        conn.pool.close().await;
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir))).await;
+        assert!(matches!(
+            conn,
+            Err(SqliteDbConnectionError::Migration(
+                MigrationError::UnsupportedSchemaVersion(_, _)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tags_are_carried_over() {
+        let dir = TempDir::new().unwrap();
+
+        // Create a database that only has the original command table
+        let options = SqliteConnectOptions::from_str(&db_path(&dir))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        for statement in create_command_table() {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO command (command, tag) VALUES ('ls', 'files'), ('pwd', NULL), ('cat', 'files')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("PRAGMA user_version = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir)))
+            .await
+            .unwrap();
+        let rows = sqlx::query(
+            "SELECT command.command, tag.name FROM command_tag \
+             INNER JOIN command ON command.id = command_tag.command_id \
+             INNER JOIN tag ON tag.id = command_tag.tag_id ORDER BY command.id",
+        )
+        .fetch_all(&conn.pool)
+        .await
+        .unwrap();
+
+        let links: Vec<(String, String)> = rows
+            .iter()
+            .map(|row| (row.get("command"), row.get("name")))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("ls".to_string(), "files".to_string()),
+                ("cat".to_string(), "files".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_index_is_kept_in_sync() {
+        let dir = TempDir::new().unwrap();
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir)))
+            .await
+            .unwrap();
+
+        let statements = [
+            "INSERT INTO command (id, command, note) VALUES (1, 'ls -la', 'list files'), (2, 'pwd', NULL)",
+            "INSERT INTO tag (id, name) VALUES (1, 'files'), (2, 'shell')",
+            "INSERT INTO command_tag (command_id, tag_id) VALUES (1, 1), (1, 2), (2, 2)",
+            "UPDATE command SET note = 'print directory' WHERE id = 2",
+            "DELETE FROM command_tag WHERE command_id = 1 AND tag_id = 1",
+        ];
+        for statement in statements {
+            sqlx::query(statement).execute(&conn.pool).await.unwrap();
+        }
+
+        let index = |search: &'static str| {
+            let pool = conn.pool.clone();
+            async move {
+                sqlx::query(
+                    "SELECT rowid FROM command_fts WHERE command_fts MATCH ? ORDER BY rowid",
+                )
+                .bind(search)
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.get::<i64, _>("rowid"))
+                .collect::<Vec<i64>>()
+            }
+        };
+
+        assert_eq!(index("ls").await, vec![1]);
+        assert_eq!(index("tags : shell").await, vec![1, 2]);
+        assert_eq!(index("tags : files").await, Vec::<i64>::new());
+        assert_eq!(index("direct*").await, vec![2]);
+
+        // Deleting a command removes it, and its tags, from the index
+        sqlx::query("DELETE FROM command WHERE id = 1")
+            .execute(&conn.pool)
+            .await
+            .unwrap();
+        assert_eq!(index("ls").await, Vec::<i64>::new());
+        assert_eq!(index("tags : shell").await, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_existing_commands_are_given_an_identity() {
+        let dir = TempDir::new().unwrap();
+
+        // Create a database from before commands had UUIDs
+        let options = SqliteConnectOptions::from_str(&db_path(&dir))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        for migration in &MIGRATIONS[..6] {
+            for statement in (migration.statements)() {
+                sqlx::query(&statement).execute(&pool).await.unwrap();
+            }
+        }
+        let statements = [
+            "INSERT INTO command (id, command, last_used) VALUES (1, 'ls', 50), (2, 'pwd', 70)",
+            "INSERT INTO command_revision (command_id, timestamp, action, command, tags, favourite) \
+             VALUES (1, 10, 'Add', 'ls', '[]', 0), (1, 20, 'Update', 'ls', '[]', 0), \
+             (1, 30, 'Delete', 'ls', '[]', 0)",
+            "PRAGMA user_version = 6",
+        ];
+        for statement in statements {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        pool.close().await;
+
+        let conn = SqliteConnectionPool::new(Some(db_path(&dir)))
+            .await
+            .unwrap();
+        let rows = sqlx::query("SELECT uuid, created_at, updated_at FROM command ORDER BY id")
+            .fetch_all(&conn.pool)
+            .await
+            .unwrap();
+
+        let uuids: Vec<uuid::Uuid> = rows
+            .iter()
+            .map(|row| uuid::Uuid::parse_str(row.get("uuid")).unwrap())
+            .collect();
+        assert_ne!(uuids[0], uuids[1]);
+        assert!(uuids.iter().all(
+            |uuid| uuid.get_version_num() == 4 && uuid.get_variant() == uuid::Variant::RFC4122
+        ));
+
+        let times: Vec<(i64, i64)> = rows
+            .iter()
+            .map(|row| (row.get("created_at"), row.get("updated_at")))
+            .collect();
+        assert_eq!(times, vec![(10, 20), (70, 70)]);
+    }
+}
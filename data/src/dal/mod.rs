@@ -1,5 +1,11 @@
+pub mod backup;
+pub mod maintenance;
+pub mod memory;
+pub mod migrations;
 pub mod sqlite;
 pub mod sqlite_dal;
+pub mod store;
+pub mod watch;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,6 +18,8 @@ pub enum InsertCommandError {
     Query(#[from] sqlx::Error),
     #[error("Failed to build SQL query to insert: {0}")]
     QueryBuilder(#[from] sea_query::error::Error),
+    #[error("Failed to run transaction: {0}")]
+    Tx(#[from] SqlTxError),
 }
 
 #[derive(Error, Debug)]
@@ -22,18 +30,122 @@ pub enum UpdateCommandError {
     NoRowsAffected,
     #[error("Failed to execute SQL query: {0}")]
     Query(#[from] sqlx::Error),
+    #[error("Failed to run transaction: {0}")]
+    Tx(#[from] SqlTxError),
 }
 
 #[derive(Error, Debug)]
 pub enum DeleteCommandError {
+    #[error("Failed to get the unix timestamp: {0}")]
+    UnixTimestamp(#[from] std::time::SystemTimeError),
     #[error("Expected rows to be affected after deletion but none were affected")]
     NoRowsAffected,
     #[error("Failed to execute SQL query: {0}")]
     Query(#[from] sqlx::Error),
+    #[error("Failed to run transaction: {0}")]
+    Tx(#[from] SqlTxError),
 }
 
 #[derive(Error, Debug)]
 pub enum SelectAllCommandsError {
+    #[error("Failed to get the unix timestamp: {0}")]
+    UnixTimestamp(#[from] std::time::SystemTimeError),
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+    #[error("Invalid UUID stored in the database: {0}")]
+    InvalidUuid(#[from] uuid::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum SelectCommandError {
+    #[error("No command with ID {0}")]
+    NotFound(i64),
+    #[error("No command with UUID {0}")]
+    UuidNotFound(uuid::Uuid),
+    #[error("Failed to select command: {0}")]
+    Select(#[from] SelectAllCommandsError),
+}
+
+#[derive(Error, Debug)]
+pub enum SelectCommandUsageError {
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+    #[error("Unknown usage action stored in the database: {0}")]
+    InvalidAction(String),
+}
+
+#[derive(Error, Debug)]
+pub enum SelectCommandRevisionError {
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+    #[error("Unknown revision action stored in the database: {0}")]
+    InvalidAction(String),
+    #[error("Invalid tags stored in the database: {0}")]
+    InvalidTags(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum UndoChangeError {
+    #[error("Failed to get the unix timestamp: {0}")]
+    UnixTimestamp(#[from] std::time::SystemTimeError),
+    #[error("Failed to read the last change: {0}")]
+    Revision(#[from] SelectCommandRevisionError),
+    #[error("Failed to revert the command: {0}")]
+    Update(#[from] UpdateCommandError),
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+    #[error("Failed to run transaction: {0}")]
+    Tx(#[from] SqlTxError),
+}
+
+#[derive(Error, Debug)]
+pub enum BackupDatabaseError {
+    #[error("Backups are not supported by this store")]
+    Unsupported,
+    #[error("The file {0} already exists")]
+    AlreadyExists(String),
+    #[error("The path {0:?} is not valid UTF-8")]
+    InvalidPath(std::path::PathBuf),
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum RestoreDatabaseError {
+    #[error("Backups are not supported by this store")]
+    Unsupported,
+    #[error("The file is not a CmdStack database")]
+    NotADatabase,
+    #[error("The backup has schema version {0}, which is newer than the supported version {1}")]
+    NewerSchemaVersion(i64, i64),
+    #[error("The backup failed the integrity check: {0}")]
+    IntegrityCheck(String),
+    #[error("The path {0:?} is not valid UTF-8")]
+    InvalidPath(std::path::PathBuf),
+    #[error("Failed to read the backup: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to migrate the backup: {0}")]
+    Migration(#[from] sqlite::SqliteDbConnectionError),
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+    #[error("Failed to run transaction: {0}")]
+    Tx(#[from] SqlTxError),
+}
+
+#[derive(Error, Debug)]
+pub enum MaintenanceError {
+    #[error("Failed to execute SQL query: {0}")]
+    Query(#[from] sqlx::Error),
+    #[error("Failed to run transaction: {0}")]
+    Tx(#[from] SqlTxError),
+    #[error("The path {0:?} is not valid UTF-8")]
+    InvalidPath(std::path::PathBuf),
+}
+
+#[derive(Error, Debug)]
+pub enum WatchChangesError {
+    #[error("Changes to this store cannot be watched")]
+    Unsupported,
     #[error("Failed to execute SQL query: {0}")]
     Query(#[from] sqlx::Error),
 }
@@ -1,10 +1,15 @@
-use sea_query::{ColumnDef, Iden, SqliteQueryBuilder, Table};
-use sqlx::sqlite::SqliteConnectOptions;
+use sea_query::Iden;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
 use sqlx::SqlitePool;
 use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
 
+use super::migrations::{self, MigrationError};
+
 #[derive(Error, Debug)]
 pub enum SqliteDbConnectionError {
     #[error("Could not get the database path: {0}")]
@@ -16,10 +21,20 @@ pub enum SqliteDbConnectionError {
     #[error("Could not connect to the file: {0}")]
     PoolInitialization(#[source] sqlx::Error),
 
-    #[error("Could not create command table: {0}")]
-    Command(#[source] sqlx::Error),
+    #[error("Could not migrate the database: {0}")]
+    Migration(#[from] MigrationError),
 }
 
+/// The stack that is kept in the database from before there were multiple stacks
+pub const DEFAULT_STACK: &str = "default";
+
+/// How long a connection waits for another connection, such as one from the
+/// CLI while the GUI app is open, to finish writing before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The files that SQLite keeps next to a database in WAL mode
+const WAL_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
 pub(crate) struct SqliteConnectionPool {
     pub(crate) pool: SqlitePool,
 }
@@ -28,87 +43,198 @@ impl SqliteConnectionPool {
     pub async fn new(db_path: Option<String>) -> Result<Self, SqliteDbConnectionError> {
         let db_path = match db_path {
             Some(path) => path,
-            None => Self::default_db_path()?,
+            None => path_to_string(stack_db_path(DEFAULT_STACK)?)?,
         };
 
         let pool = Self::create_connection_pool(db_path).await?;
 
-        Self::create_tables(&pool).await?;
+        migrations::run_migrations(&pool).await?;
 
         Ok(SqliteConnectionPool { pool })
     }
 
-    /// Returns the default path to the database file. The default location for the database
-    /// file is in the `cmdstack` directory which is located in the OS config directory.
-    ///
-    /// If the `cmdstack` directory does not exist, it is created
-    fn default_db_path() -> Result<String, SqliteDbConnectionError> {
-        let mut path = dirs::config_dir().ok_or_else(|| {
-            SqliteDbConnectionError::DbPath("Could not get config directory".to_string())
-        })?;
-        path.push("cmdstack");
-
-        // Create the config directory if it does not exist
-        fs::create_dir_all(path.as_path()).map_err(|_| {
-            SqliteDbConnectionError::DbPath(format!(
-                "Could not create config directory: {:?}",
-                path.to_str()
-            ))
-        })?;
-
-        path.push("database.sqlite"); // Add the database file to the path
-        path.to_str().map(|s| s.to_string()).ok_or_else(|| {
-            SqliteDbConnectionError::DbPath("Could not generate the default db path".to_string())
-        })
-    }
-
+    /// Connects to the database in WAL mode, so that reads never wait for
+    /// writes and a write only waits for other writes, which lets the CLI and
+    /// the GUI app use the same database at the same time
     async fn create_connection_pool(
         db_path: String,
     ) -> Result<SqlitePool, SqliteDbConnectionError> {
         let connect_options = SqliteConnectOptions::from_str(&db_path)
             .map_err(SqliteDbConnectionError::SqliteOptionsInitialization)?
-            .create_if_missing(true);
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            // Only a power loss can undo a commit in WAL mode with this setting
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
 
         SqlitePool::connect_with(connect_options)
             .await
             .map_err(SqliteDbConnectionError::PoolInitialization)
     }
+}
 
-    /// Initializes the tables in the Sqlite database if they do not exist
-    async fn create_tables(pool: &SqlitePool) -> Result<(), SqliteDbConnectionError> {
-        let command_table_sql = Table::create()
-            .table(Command::Table)
-            .if_not_exists()
-            .col(
-                ColumnDef::new(Command::Id)
-                    .integer()
-                    .not_null()
-                    .primary_key()
-                    .auto_increment(),
-            )
-            .col(ColumnDef::new(Command::Command).string().not_null())
-            .col(ColumnDef::new(Command::Tag).string())
-            .col(ColumnDef::new(Command::Note).string())
-            .col(ColumnDef::new(Command::LastUsed).integer().default(0))
-            .col(ColumnDef::new(Command::Favourite).boolean().default(false))
-            .build(SqliteQueryBuilder);
-
-        sqlx::query(&command_table_sql)
-            .execute(pool)
-            .await
-            .map(|_| ())
-            .map_err(SqliteDbConnectionError::Command)
+/// Returns the path to the database file of a stack. The default stack is kept
+/// in `database.sqlite` in the `cmdstack` directory, which is located in the OS
+/// config directory, and every other stack in `stacks/<name>.sqlite`.
+///
+/// If the directory of the database file does not exist, it is created
+pub fn stack_db_path(stack: &str) -> Result<PathBuf, SqliteDbConnectionError> {
+    if stack == DEFAULT_STACK {
+        Ok(database_dir(&[])?.join("database.sqlite"))
+    } else {
+        Ok(database_dir(&["stacks"])?.join(format!("{}.sqlite", stack)))
     }
 }
 
+/// Returns the directory that the backups of a stack are kept in, which is
+/// `backups/<name>` in the `cmdstack` directory.
+///
+/// If the directory does not exist, it is created
+pub fn stack_backup_dir(stack: &str) -> Result<PathBuf, SqliteDbConnectionError> {
+    database_dir(&["backups", stack])
+}
+
+/// Returns the names of the stacks that have a database file, in alphabetical
+/// order. The default stack is always included
+pub fn stack_names() -> Result<Vec<String>, SqliteDbConnectionError> {
+    let stacks_dir = database_dir(&["stacks"])?;
+    let entries = fs::read_dir(&stacks_dir).map_err(|_| {
+        SqliteDbConnectionError::DbPath(format!(
+            "Could not read the stacks directory: {:?}",
+            stacks_dir.to_str()
+        ))
+    })?;
+
+    let mut names = vec![DEFAULT_STACK.to_string()];
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_some_and(|ext| ext == "sqlite") {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+
+    Ok(names)
+}
+
+/// Deletes a database file along with the files SQLite keeps next to it in WAL
+/// mode. Files that do not exist are ignored
+pub fn remove_database(path: &Path) -> io::Result<()> {
+    let mut paths = vec![path.to_path_buf()];
+    for suffix in WAL_SUFFIXES {
+        let mut wal_path = path.as_os_str().to_owned();
+        wal_path.push(suffix);
+        paths.push(wal_path.into());
+    }
+
+    for path in paths {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the `cmdstack` directory in the OS config directory, or a directory
+/// inside it, such as the one that stacks other than the default one are kept in.
+///
+/// If the directory does not exist, it is created
+fn database_dir(subdirs: &[&str]) -> Result<PathBuf, SqliteDbConnectionError> {
+    let mut path = dirs::config_dir().ok_or_else(|| {
+        SqliteDbConnectionError::DbPath("Could not get config directory".to_string())
+    })?;
+    path.push("cmdstack");
+    path.extend(subdirs);
+
+    // Create the config directory if it does not exist
+    fs::create_dir_all(path.as_path()).map_err(|_| {
+        SqliteDbConnectionError::DbPath(format!(
+            "Could not create config directory: {:?}",
+            path.to_str()
+        ))
+    })?;
+
+    Ok(path)
+}
+
+/// Converts the path of a database file to the string it is connected with
+pub(crate) fn path_to_string(path: PathBuf) -> Result<String, SqliteDbConnectionError> {
+    path.to_str().map(|s| s.to_string()).ok_or_else(|| {
+        SqliteDbConnectionError::DbPath("Could not generate the db path".to_string())
+    })
+}
+
 #[derive(Iden)]
 /// Command Table Schema
 pub enum Command {
     Table,
     Id,
     Command,
+    /// Dropped in favour of the [`Tag`] and [`CommandTag`] tables
     Tag,
     Note,
     LastUsed,
     Favourite,
+    /// When the command was moved to the trash. `NULL` for commands that are not
+    /// in the trash
+    DeletedAt,
+    /// A UUID that identifies the command across databases, stored as text
+    Uuid,
+    CreatedAt,
+    /// When the properties of the command were last changed
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+/// Tag Table Schema
+pub enum Tag {
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(Iden)]
+/// Command Usage Table Schema
+pub enum CommandUsage {
+    Table,
+    Id,
+    CommandId,
+    Timestamp,
+    Action,
+    WorkingDirectory,
+    ResolvedCommand,
+}
+
+#[derive(Iden)]
+/// Command Revision Table Schema
+pub enum CommandRevision {
+    Table,
+    Id,
+    CommandId,
+    Timestamp,
+    Action,
+    Command,
+    /// The tags of the command as a JSON array
+    Tags,
+    Note,
+    Favourite,
+}
+
+#[derive(Iden)]
+/// Schema of the table linking commands to their tags
+pub enum CommandTag {
+    Table,
+    CommandId,
+    TagId,
+}
+
+#[derive(Iden)]
+/// Full-text search index over the command, tags and note of every command.
+/// The row ID of an entry is the ID of its command
+pub enum CommandFts {
+    Table,
 }
@@ -1,13 +1,31 @@
+use super::backup::{restore_from, vacuum_into};
+use super::maintenance::{self, check_integrity, command_texts};
+use super::sqlite::{path_to_string, stack_db_path};
 use super::sqlite::{SqliteConnectionPool, SqliteDbConnectionError};
-use super::{sqlite, DeleteCommandError, UpdateCommandError};
-use super::{InsertCommandError, SelectAllCommandsError};
-use sea_query::{Expr, Query, SqliteQueryBuilder};
+use super::store::{search_words, unix_timestamp, ChangeWatcher, CommandStore, StoreTransaction};
+use super::store::{FRECENCY_BASE_WEIGHT, FRECENCY_BUCKETS};
+use super::watch::SqliteChangeWatcher;
+use super::{sqlite, DeleteCommandError, SqlTxError, UndoChangeError, UpdateCommandError};
+use super::{BackupDatabaseError, MaintenanceError, RestoreDatabaseError, WatchChangesError};
+use super::{InsertCommandError, SelectAllCommandsError, SelectCommandUsageError};
+use super::{SelectCommandError, SelectCommandRevisionError};
+use async_trait::async_trait;
+use sea_query::{
+    Alias, CaseStatement, Expr, Func, LikeExpr, OnConflict, Query, SelectStatement, SimpleExpr,
+    SqliteQueryBuilder, SubQueryStatement,
+};
+use sea_query_binder::{SqlxBinder, SqlxValues};
 use sqlx::sqlite::{SqliteQueryResult, SqliteRow};
-use sqlx::Row;
-use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use sqlx::{Row, Sqlite, SqliteConnection, SqliteExecutor, Transaction};
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
 
 use crate::models::*;
 
+/// The most command IDs that are bound to a single query for their tags
+const TAG_QUERY_CHUNK_SIZE: usize = 500;
+
 /// The Data Access Layer
 ///
 /// The interface of this struct allows for the use of transactions
@@ -17,7 +35,6 @@ pub struct SqliteDal {
 
 impl SqliteDal {
     /// Connects to the database at the default location
-    #[tokio::main]
     pub async fn new() -> Result<SqliteDal, SqliteDbConnectionError> {
         let sqlite_db = SqliteConnectionPool::new(None).await?;
         Ok(SqliteDal {
@@ -25,8 +42,13 @@ impl SqliteDal {
         })
     }
 
+    /// Connects to the database of a stack, which is created if it does not exist
+    pub async fn new_for_stack(stack: &str) -> Result<SqliteDal, SqliteDbConnectionError> {
+        let db_path = path_to_string(stack_db_path(stack)?)?;
+        Self::new_with_custom_path(db_path).await
+    }
+
     /// Connects to the database at the provided file path
-    #[tokio::main]
     pub async fn new_with_custom_path(
         custom_path: String,
     ) -> Result<SqliteDal, SqliteDbConnectionError> {
@@ -36,172 +58,412 @@ impl SqliteDal {
         })
     }
 
-    /// Returns the current unix timestamp in seconds
-    fn get_unix_timestamp(&self) -> Result<i64, SystemTimeError> {
-        let duration_since_epoch = SystemTime::now().duration_since(UNIX_EPOCH)?;
-        Ok(duration_since_epoch.as_secs() as i64)
-    }
-
     /// Executes a query as a prepared statement on the database
-    async fn execute_query(&self, query: &str) -> Result<SqliteQueryResult, sqlx::Error> {
-        sqlx::query(query).execute(&self.sqlite_conn.pool).await
+    async fn execute_query(
+        &self,
+        query: &str,
+        values: SqlxValues,
+    ) -> Result<SqliteQueryResult, sqlx::Error> {
+        sqlx::query_with(query, values)
+            .execute(&self.sqlite_conn.pool)
+            .await
     }
 
     /// Reads rows based on the query as a prepared statement from the database
-    async fn read_rows(&self, query: &str) -> Result<Vec<SqliteRow>, sqlx::Error> {
-        sqlx::query(query).fetch_all(&self.sqlite_conn.pool).await
+    async fn read_rows(
+        &self,
+        query: &str,
+        values: SqlxValues,
+    ) -> Result<Vec<SqliteRow>, sqlx::Error> {
+        sqlx::query_with(query, values)
+            .fetch_all(&self.sqlite_conn.pool)
+            .await
     }
 
-    pub async fn get_all_commands(
+    /// Starts a transaction on the database
+    async fn begin_tx(&self) -> Result<Transaction<'static, Sqlite>, SqlTxError> {
+        self.sqlite_conn
+            .pool
+            .begin()
+            .await
+            .map_err(SqlTxError::TxBegin)
+    }
+
+    /// Returns a page of the commands that match both the filter and the query
+    async fn select_commands(
         &self,
-        order_by_use: bool,
-        favourites_only: bool,
-    ) -> Result<Vec<Command>, SelectAllCommandsError> {
-        let query = Query::select()
+        filter: SimpleExpr,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError> {
+        let current_time = match &query.cursor {
+            Some(cursor) => cursor.scored_at,
+            None => unix_timestamp()?,
+        };
+
+        let mut select = Query::select();
+        select
             .columns([
-                sqlite::Command::Command,
-                sqlite::Command::Tag,
-                sqlite::Command::Note,
-                sqlite::Command::Favourite,
-                sqlite::Command::Id,
-                sqlite::Command::LastUsed,
+                (sqlite::Command::Table, sqlite::Command::Command),
+                (sqlite::Command::Table, sqlite::Command::Note),
+                (sqlite::Command::Table, sqlite::Command::Favourite),
+                (sqlite::Command::Table, sqlite::Command::Id),
+                (sqlite::Command::Table, sqlite::Command::LastUsed),
+                (sqlite::Command::Table, sqlite::Command::DeletedAt),
+                (sqlite::Command::Table, sqlite::Command::Uuid),
+                (sqlite::Command::Table, sqlite::Command::CreatedAt),
+                (sqlite::Command::Table, sqlite::Command::UpdatedAt),
             ])
-            .conditions(
-                // Ternary operator that allows us to add expressions at runtime
-                order_by_use,
-                |q| {
-                    q.order_by(sqlite::Command::LastUsed, sea_query::Order::Desc);
-                },
-                |_| {},
-            )
-            .conditions(
-                favourites_only,
-                |q| {
-                    q.and_where(Expr::col(sqlite::Command::Favourite).is_in([true]));
-                },
-                |_| {},
-            )
             .from(sqlite::Command::Table)
-            .to_string(SqliteQueryBuilder);
+            .and_where(filter);
 
-        let rows = self
-            .read_rows(&query)
+        // The score is only worth calculating when it is sorted by
+        let frecency = match query.sort {
+            CommandSort::Frecency => frecency_score(current_time),
+            _ => Expr::val(0).into(),
+        };
+        select.expr_as(frecency.clone(), Alias::new("frecency"));
+
+        if query.favourites_only {
+            select.and_where(
+                Expr::col((sqlite::Command::Table, sqlite::Command::Favourite)).is_in([true]),
+            );
+        }
+        if let Some(text) = &query.text_contains {
+            select.and_where(
+                Expr::col((sqlite::Command::Table, sqlite::Command::Command))
+                    .like(like_pattern(text, true)),
+            );
+        }
+        if let Some(prefix) = &query.tag_prefix {
+            let tagged_ids = Query::select()
+                .column((sqlite::CommandTag::Table, sqlite::CommandTag::CommandId))
+                .from(sqlite::CommandTag::Table)
+                .inner_join(
+                    sqlite::Tag::Table,
+                    Expr::col((sqlite::Tag::Table, sqlite::Tag::Id))
+                        .equals((sqlite::CommandTag::Table, sqlite::CommandTag::TagId)),
+                )
+                .and_where(
+                    Expr::col((sqlite::Tag::Table, sqlite::Tag::Name))
+                        .like(like_pattern(prefix, false)),
+                )
+                .to_owned();
+            select.and_where(
+                Expr::col((sqlite::Command::Table, sqlite::Command::Id)).in_subquery(tagged_ids),
+            );
+        }
+        if let Some(cursor) = &query.cursor {
+            select.and_where(after_cursor(query.sort, cursor, frecency));
+        }
+
+        match query.sort {
+            CommandSort::Insertion => {}
+            CommandSort::RecentlyUsed => {
+                select.order_by(
+                    (sqlite::Command::Table, sqlite::Command::LastUsed),
+                    sea_query::Order::Desc,
+                );
+            }
+            CommandSort::Frecency => {
+                // Ties (e.g. commands that were never used) fall back to recency
+                select
+                    .order_by(Alias::new("frecency"), sea_query::Order::Desc)
+                    .order_by(
+                        (sqlite::Command::Table, sqlite::Command::LastUsed),
+                        sea_query::Order::Desc,
+                    );
+            }
+        }
+        select.order_by(
+            (sqlite::Command::Table, sqlite::Command::Id),
+            sea_query::Order::Asc,
+        );
+
+        // One more command than requested is read to find out if there is a
+        // next page. SQLite only accepts an offset after a limit
+        match query.limit {
+            Some(limit) => {
+                select.limit(u64::from(limit) + 1);
+            }
+            None if query.offset > 0 => {
+                select.limit(i64::MAX as u64);
+            }
+            None => {}
+        }
+        if query.offset > 0 {
+            select.offset(u64::from(query.offset));
+        }
+
+        let (select, values) = select.build_sqlx(SqliteQueryBuilder);
+        let mut rows = self
+            .read_rows(&select, values)
             .await
             .map_err(SelectAllCommandsError::Query)?;
 
-        let commands: Vec<Command> = rows
+        let mut next_cursor = None;
+        if let Some(limit) = query.limit.map(|limit| limit as usize) {
+            if rows.len() > limit {
+                rows.truncate(limit);
+                next_cursor = rows.last().map(|row| CommandCursor {
+                    id: row.get("id"),
+                    last_used: row.get("last_used"),
+                    frecency: row.get("frecency"),
+                    scored_at: current_time,
+                });
+            }
+        }
+
+        let ids: Vec<i64> = rows.iter().map(|row| row.get("id")).collect();
+        let mut tags = self
+            .get_command_tags(&ids)
+            .await
+            .map_err(SelectAllCommandsError::Query)?;
+
+        let commands = rows
             .into_iter()
-            .map(|row| Command {
-                internal_command: InternalCommand {
-                    command: row.get("command"),
-                    tag: row.get("tag"),
-                    note: row.get("note"),
-                    favourite: row.get("favourite"),
-                },
-                id: row.get("id"),
-                last_used: row.get("last_used"),
+            .map(|row| {
+                let id: i64 = row.get("id");
+                let uuid: String = row.get("uuid");
+                Ok(Command {
+                    internal_command: InternalCommand {
+                        command: row.get("command"),
+                        tags: tags.remove(&id).unwrap_or_default(),
+                        note: row.get("note"),
+                        favourite: row.get("favourite"),
+                    },
+                    id,
+                    uuid: Uuid::parse_str(&uuid)?,
+                    last_used: row.get("last_used"),
+                    created_at: row.get("created_at"),
+                    updated_at: row.get("updated_at"),
+                    deleted_at: row.get("deleted_at"),
+                })
             })
-            .collect();
+            .collect::<Result<Vec<Command>, SelectAllCommandsError>>()?;
 
-        Ok(commands)
+        Ok(CommandPage {
+            commands,
+            next_cursor,
+        })
     }
 
-    /// Inserts a command and returns the ID of the inserted command
-    pub async fn insert_command(
+    /// Returns the tags of the commands, keyed by command ID.
+    ///
+    /// Tags are listed in the order they were added to the command
+    async fn get_command_tags(
         &self,
-        command: InternalCommand,
-    ) -> Result<i64, InsertCommandError> {
-        let current_time = self.get_unix_timestamp()?;
+        command_ids: &[i64],
+    ) -> Result<HashMap<i64, Vec<String>>, sqlx::Error> {
+        let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
 
-        let query = Query::insert()
-            .into_table(sqlite::Command::Table)
-            .columns([
-                sqlite::Command::Command,
-                sqlite::Command::Tag,
-                sqlite::Command::Note,
-                sqlite::Command::Favourite,
-                sqlite::Command::LastUsed,
-            ])
-            .values_panic([
-                command.command.into(),
-                command.tag.into(),
-                command.note.into(),
-                command.favourite.into(),
-                current_time.into(),
-            ])
-            .to_string(SqliteQueryBuilder);
+        // The IDs are bound as parameters, of which SQLite only accepts so many
+        for ids in command_ids.chunks(TAG_QUERY_CHUNK_SIZE) {
+            let (query, values) = Query::select()
+                .column((sqlite::CommandTag::Table, sqlite::CommandTag::CommandId))
+                .column((sqlite::Tag::Table, sqlite::Tag::Name))
+                .from(sqlite::CommandTag::Table)
+                .inner_join(
+                    sqlite::Tag::Table,
+                    Expr::col((sqlite::Tag::Table, sqlite::Tag::Id))
+                        .equals((sqlite::CommandTag::Table, sqlite::CommandTag::TagId)),
+                )
+                .and_where(
+                    Expr::col((sqlite::CommandTag::Table, sqlite::CommandTag::CommandId))
+                        .is_in(ids.iter().copied()),
+                )
+                .order_by(
+                    (sqlite::CommandTag::Table, Alias::new("rowid")),
+                    sea_query::Order::Asc,
+                )
+                .build_sqlx(SqliteQueryBuilder);
 
-        let result = self
-            .execute_query(&query)
-            .await
-            .map_err(InsertCommandError::Query)?;
-
-        if result.rows_affected() == 0 {
-            return Err(InsertCommandError::NoRowsAffected);
+            for row in self.read_rows(&query, values).await? {
+                tags.entry(row.get("command_id"))
+                    .or_default()
+                    .push(row.get("name"));
+            }
         }
 
-        Ok(result.last_insert_rowid())
+        Ok(tags)
     }
+}
 
-    pub async fn insert_mulitple_commands(
+#[async_trait]
+impl CommandStore for SqliteDal {
+    async fn query_commands(
+        &self,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError> {
+        self.select_commands(not_trashed(), query).await
+    }
+
+    async fn get_command(&self, command_id: i64) -> Result<Command, SelectCommandError> {
+        self.select_commands(
+            Expr::col((sqlite::Command::Table, sqlite::Command::Id)).eq(command_id),
+            &CommandQuery::default(),
+        )
+        .await?
+        .commands
+        .pop()
+        .ok_or(SelectCommandError::NotFound(command_id))
+    }
+
+    async fn get_command_by_uuid(&self, uuid: Uuid) -> Result<Command, SelectCommandError> {
+        self.select_commands(
+            Expr::col((sqlite::Command::Table, sqlite::Command::Uuid)).eq(uuid.to_string()),
+            &CommandQuery::default(),
+        )
+        .await?
+        .commands
+        .pop()
+        .ok_or(SelectCommandError::UuidNotFound(uuid))
+    }
+
+    async fn get_command_by_text(
+        &self,
+        command: &str,
+    ) -> Result<Option<Command>, SelectAllCommandsError> {
+        let query = CommandQuery {
+            limit: Some(1),
+            ..Default::default()
+        };
+        Ok(self
+            .select_commands(
+                not_trashed()
+                    .and(Expr::col((sqlite::Command::Table, sqlite::Command::Command)).eq(command)),
+                &query,
+            )
+            .await?
+            .commands
+            .pop())
+    }
+
+    async fn search_commands(
+        &self,
+        command: Option<&str>,
+        tag: Option<&str>,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError> {
+        let Some(search) = full_text_query(command, tag) else {
+            return Ok(CommandPage::default());
+        };
+
+        let matching_ids = Query::select()
+            .column(Alias::new("rowid"))
+            .from(sqlite::CommandFts::Table)
+            .and_where(Expr::cust_with_values("command_fts MATCH ?", [search]))
+            .to_owned();
+
+        self.select_commands(
+            not_trashed().and(
+                Expr::col((sqlite::Command::Table, sqlite::Command::Id)).in_subquery(matching_ids),
+            ),
+            query,
+        )
+        .await
+    }
+
+    async fn insert_command(&self, command: InternalCommand) -> Result<i64, InsertCommandError> {
+        let current_time = unix_timestamp()?;
+
+        let mut tx = self.begin_tx().await?;
+        let command_id = insert_command_row(
+            &mut tx,
+            command,
+            CommandIdentity::new(current_time),
+            current_time,
+        )
+        .await?;
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+        Ok(command_id)
+    }
+
+    async fn insert_mulitple_commands(
         &self,
         commands: Vec<InternalCommand>,
     ) -> Result<u64, InsertCommandError> {
         if commands.is_empty() {
             return Err(InsertCommandError::NoRowsAffected);
         }
-        let current_time = self.get_unix_timestamp()?;
-
-        let mut builder = Query::insert()
-            .into_table(sqlite::Command::Table)
-            .columns([
-                sqlite::Command::Command,
-                sqlite::Command::Tag,
-                sqlite::Command::Note,
-                sqlite::Command::Favourite,
-                sqlite::Command::LastUsed,
-            ])
-            .to_owned();
+        let current_time = unix_timestamp()?;
 
+        let mut tx = self.begin_tx().await?;
+        let mut rows_affected = 0;
         for command in commands {
-            builder
-                .values([
-                    command.command.into(),
-                    command.tag.into(),
-                    command.note.into(),
-                    command.favourite.into(),
-                    current_time.into(),
-                ])
-                .map_err(InsertCommandError::QueryBuilder)?;
+            insert_command_row(
+                &mut tx,
+                command,
+                CommandIdentity::new(current_time),
+                current_time,
+            )
+            .await?;
+            rows_affected += 1;
         }
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+        Ok(rows_affected)
+    }
+
+    async fn update_command_last_used_property(
+        &self,
+        command_id: i64,
+    ) -> Result<(), UpdateCommandError> {
+        let current_time = unix_timestamp()?;
+
+        let (query, values) = Query::update()
+            .table(sqlite::Command::Table)
+            .values([(sqlite::Command::LastUsed, current_time.into())])
+            .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
+            .build_sqlx(SqliteQueryBuilder);
 
-        let query = builder.to_string(SqliteQueryBuilder);
         let result = self
-            .execute_query(&query)
+            .execute_query(&query, values)
             .await
-            .map_err(InsertCommandError::Query)?;
+            .map_err(UpdateCommandError::Query)?;
 
         if result.rows_affected() == 0 {
-            return Err(InsertCommandError::NoRowsAffected);
+            return Err(UpdateCommandError::NoRowsAffected);
         }
 
-        Ok(result.rows_affected())
+        Ok(())
     }
 
-    pub async fn update_command_last_used_property(
+    async fn record_command_usage(
         &self,
         command_id: i64,
+        usage: InternalCommandUsage,
     ) -> Result<(), UpdateCommandError> {
-        let current_time = self.get_unix_timestamp()?;
+        let current_time = unix_timestamp()?;
 
-        let query = Query::update()
+        let (last_used_query, last_used_values) = Query::update()
             .table(sqlite::Command::Table)
             .values([(sqlite::Command::LastUsed, current_time.into())])
             .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
-            .to_string(SqliteQueryBuilder);
+            .build_sqlx(SqliteQueryBuilder);
 
-        let result = self
-            .execute_query(&query)
+        let (usage_query, usage_values) = Query::insert()
+            .into_table(sqlite::CommandUsage::Table)
+            .columns([
+                sqlite::CommandUsage::CommandId,
+                sqlite::CommandUsage::Timestamp,
+                sqlite::CommandUsage::Action,
+                sqlite::CommandUsage::WorkingDirectory,
+                sqlite::CommandUsage::ResolvedCommand,
+            ])
+            .values_panic([
+                command_id.into(),
+                current_time.into(),
+                usage.action.as_str().into(),
+                usage.working_directory.into(),
+                usage.resolved_command.into(),
+            ])
+            .build_sqlx(SqliteQueryBuilder);
+
+        let mut tx = self.begin_tx().await?;
+
+        let result = sqlx::query_with(&last_used_query, last_used_values)
+            .execute(&mut *tx)
             .await
             .map_err(UpdateCommandError::Query)?;
 
@@ -209,55 +471,869 @@ impl SqliteDal {
             return Err(UpdateCommandError::NoRowsAffected);
         }
 
+        sqlx::query_with(&usage_query, usage_values)
+            .execute(&mut *tx)
+            .await
+            .map_err(UpdateCommandError::Query)?;
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
         Ok(())
     }
 
-    pub async fn delete_command(&self, command_id: i64) -> Result<(), DeleteCommandError> {
-        let query = Query::delete()
-            .from_table(sqlite::Command::Table)
+    async fn get_command_usage(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandUsage>, SelectCommandUsageError> {
+        let (query, values) = Query::select()
+            .columns([
+                sqlite::CommandUsage::Id,
+                sqlite::CommandUsage::CommandId,
+                sqlite::CommandUsage::Timestamp,
+                sqlite::CommandUsage::Action,
+                sqlite::CommandUsage::WorkingDirectory,
+                sqlite::CommandUsage::ResolvedCommand,
+            ])
+            .from(sqlite::CommandUsage::Table)
+            .and_where(Expr::col(sqlite::CommandUsage::CommandId).eq(command_id))
+            .order_by(sqlite::CommandUsage::Timestamp, sea_query::Order::Desc)
+            .order_by(sqlite::CommandUsage::Id, sea_query::Order::Desc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        let rows = self
+            .read_rows(&query, values)
+            .await
+            .map_err(SelectCommandUsageError::Query)?;
+
+        rows.into_iter()
+            .map(|row| {
+                let action: String = row.get("action");
+                Ok(CommandUsage {
+                    id: row.get("id"),
+                    command_id: row.get("command_id"),
+                    timestamp: row.get("timestamp"),
+                    internal_usage: InternalCommandUsage {
+                        action: action
+                            .parse()
+                            .map_err(SelectCommandUsageError::InvalidAction)?,
+                        working_directory: row.get("working_directory"),
+                        resolved_command: row.get("resolved_command"),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_command(&self, command_id: i64) -> Result<(), DeleteCommandError> {
+        let mut tx = self.begin_tx().await?;
+        delete_command_row(&mut tx, command_id).await?;
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+        Ok(())
+    }
+
+    async fn get_trashed_commands(&self) -> Result<Vec<Command>, SelectAllCommandsError> {
+        let mut commands = self
+            .select_commands(not_trashed().not(), &CommandQuery::default())
+            .await?
+            .commands;
+        commands.sort_by_key(|c| std::cmp::Reverse(c.deleted_at));
+
+        Ok(commands)
+    }
+
+    async fn restore_command(&self, command_id: i64) -> Result<(), UpdateCommandError> {
+        let current_time = unix_timestamp()?;
+
+        let (query, values) = Query::update()
+            .table(sqlite::Command::Table)
+            .values([(sqlite::Command::DeletedAt, Option::<i64>::None.into())])
             .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
-            .to_string(SqliteQueryBuilder);
+            .and_where(Expr::col(sqlite::Command::DeletedAt).is_not_null())
+            .build_sqlx(SqliteQueryBuilder);
 
-        let result = self
-            .execute_query(&query)
+        let mut tx = self.begin_tx().await?;
+
+        let result = sqlx::query_with(&query, values)
+            .execute(&mut *tx)
             .await
-            .map_err(DeleteCommandError::Query)?;
+            .map_err(UpdateCommandError::Query)?;
 
         if result.rows_affected() == 0 {
-            return Err(DeleteCommandError::NoRowsAffected);
+            return Err(UpdateCommandError::NoRowsAffected);
         }
 
+        record_revision(&mut tx, command_id, RevisionAction::Restore, current_time).await?;
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
         Ok(())
     }
 
-    pub async fn update_command(
+    async fn purge_trash(&self, deleted_before: i64) -> Result<u64, DeleteCommandError> {
+        let (query, values) = Query::delete()
+            .from_table(sqlite::Command::Table)
+            .and_where(Expr::col(sqlite::Command::DeletedAt).lt(deleted_before))
+            .build_sqlx(SqliteQueryBuilder);
+
+        let mut tx = self.begin_tx().await?;
+
+        // The usage history, revisions and tag links are removed by the foreign
+        // key cascade
+        let result = sqlx::query_with(&query, values)
+            .execute(&mut *tx)
+            .await
+            .map_err(DeleteCommandError::Query)?;
+
+        delete_unused_tags(&mut tx).await?;
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn update_command(
         &self,
         command_id: i64,
         new_command_props: InternalCommand,
     ) -> Result<(), UpdateCommandError> {
-        let query = Query::update()
-            .table(sqlite::Command::Table)
-            .values([
-                (sqlite::Command::Command, new_command_props.command.into()),
-                (sqlite::Command::Tag, new_command_props.tag.into()),
-                (sqlite::Command::Note, new_command_props.note.into()),
-                (
-                    sqlite::Command::Favourite,
-                    new_command_props.favourite.into(),
-                ),
-            ])
-            .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
-            .to_string(SqliteQueryBuilder);
+        let mut tx = self.begin_tx().await?;
+        update_command_row(&mut tx, command_id, new_command_props).await?;
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
 
-        let result = self
-            .execute_query(&query)
+        Ok(())
+    }
+
+    async fn get_command_revisions(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandRevision>, SelectCommandRevisionError> {
+        let query = revisions_query()
+            .and_where(Expr::col(sqlite::CommandRevision::CommandId).eq(command_id))
+            .to_owned();
+
+        select_revisions(&self.sqlite_conn.pool, query).await
+    }
+
+    async fn undo_last_change(&self) -> Result<Option<CommandRevision>, UndoChangeError> {
+        let current_time = unix_timestamp()?;
+
+        let mut tx = self.begin_tx().await?;
+
+        let query = revisions_query().limit(1).to_owned();
+        let Some(revision) = select_revisions(&mut *tx, query).await?.pop() else {
+            return Ok(None);
+        };
+
+        match revision.action {
+            RevisionAction::Add | RevisionAction::Restore => {
+                set_deleted_at(&mut tx, revision.command_id, Some(current_time)).await?
+            }
+            RevisionAction::Delete => set_deleted_at(&mut tx, revision.command_id, None).await?,
+            RevisionAction::Update => {
+                write_command_row(
+                    &mut tx,
+                    revision.command_id,
+                    revision.internal_command.clone(),
+                    current_time,
+                )
+                .await?
+            }
+        }
+
+        let (query, values) = Query::delete()
+            .from_table(sqlite::CommandRevision::Table)
+            .and_where(Expr::col(sqlite::CommandRevision::Id).eq(revision.id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&query, values).execute(&mut *tx).await?;
+
+        tx.commit().await.map_err(SqlTxError::TxCommit)?;
+
+        Ok(Some(revision))
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StoreTransaction + '_>, SqlTxError> {
+        Ok(Box::new(SqliteTransaction {
+            tx: self.begin_tx().await?,
+        }))
+    }
+
+    async fn backup(&self, destination: &Path) -> Result<(), BackupDatabaseError> {
+        vacuum_into(&self.sqlite_conn.pool, destination).await
+    }
+
+    async fn restore(&self, backup: &Path) -> Result<(), RestoreDatabaseError> {
+        restore_from(&self.sqlite_conn.pool, backup).await
+    }
+
+    async fn get_command_texts(&self) -> Result<Vec<(i64, String)>, SelectAllCommandsError> {
+        command_texts(&self.sqlite_conn.pool)
             .await
-            .map_err(UpdateCommandError::Query)?;
+            .map_err(SelectAllCommandsError::Query)
+    }
 
-        if result.rows_affected() == 0 {
-            return Err(UpdateCommandError::NoRowsAffected);
+    async fn check_integrity(&self) -> Result<IntegrityReport, MaintenanceError> {
+        check_integrity(&self.sqlite_conn.pool).await
+    }
+
+    async fn repair(&self) -> Result<RepairSummary, MaintenanceError> {
+        maintenance::repair(&self.sqlite_conn.pool).await
+    }
+
+    async fn watch_changes(&self) -> Result<Box<dyn ChangeWatcher>, WatchChangesError> {
+        Ok(Box::new(
+            SqliteChangeWatcher::new(&self.sqlite_conn.pool).await?,
+        ))
+    }
+}
+
+/// A transaction on the database. Dropping it without committing rolls it back
+struct SqliteTransaction {
+    tx: Transaction<'static, Sqlite>,
+}
+
+#[async_trait]
+impl StoreTransaction for SqliteTransaction {
+    async fn insert_command(
+        &mut self,
+        command: InternalCommand,
+    ) -> Result<i64, InsertCommandError> {
+        let current_time = unix_timestamp()?;
+        insert_command_row(
+            &mut self.tx,
+            command,
+            CommandIdentity::new(current_time),
+            current_time,
+        )
+        .await
+    }
+
+    async fn insert_command_with_identity(
+        &mut self,
+        command: InternalCommand,
+        identity: CommandIdentity,
+    ) -> Result<i64, InsertCommandError> {
+        insert_command_row(&mut self.tx, command, identity, unix_timestamp()?).await
+    }
+
+    async fn update_command(
+        &mut self,
+        command_id: i64,
+        new_command_props: InternalCommand,
+    ) -> Result<(), UpdateCommandError> {
+        update_command_row(&mut self.tx, command_id, new_command_props).await
+    }
+
+    async fn delete_command(&mut self, command_id: i64) -> Result<(), DeleteCommandError> {
+        delete_command_row(&mut self.tx, command_id).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), SqlTxError> {
+        self.tx.commit().await.map_err(SqlTxError::TxCommit)
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), SqlTxError> {
+        self.tx.rollback().await.map_err(SqlTxError::TxRollback)
+    }
+}
+
+/// Inserts a command along with its tags and returns the ID of the inserted command
+async fn insert_command_row(
+    conn: &mut SqliteConnection,
+    command: InternalCommand,
+    identity: CommandIdentity,
+    current_time: i64,
+) -> Result<i64, InsertCommandError> {
+    let (query, values) = Query::insert()
+        .into_table(sqlite::Command::Table)
+        .columns([
+            sqlite::Command::Command,
+            sqlite::Command::Note,
+            sqlite::Command::Favourite,
+            sqlite::Command::LastUsed,
+            sqlite::Command::Uuid,
+            sqlite::Command::CreatedAt,
+            sqlite::Command::UpdatedAt,
+        ])
+        .values_panic([
+            command.command.into(),
+            command.note.into(),
+            command.favourite.into(),
+            current_time.into(),
+            identity.uuid.to_string().into(),
+            identity.created_at.into(),
+            identity.updated_at.into(),
+        ])
+        .build_sqlx(SqliteQueryBuilder);
+
+    let result = sqlx::query_with(&query, values)
+        .execute(&mut *conn)
+        .await
+        .map_err(InsertCommandError::Query)?;
+
+    if result.rows_affected() == 0 {
+        return Err(InsertCommandError::NoRowsAffected);
+    }
+
+    let command_id = result.last_insert_rowid();
+    set_command_tags(conn, command_id, &command.tags).await?;
+    record_revision(conn, command_id, RevisionAction::Add, current_time).await?;
+
+    Ok(command_id)
+}
+
+/// Replaces the properties of a command, including its tags, and records the
+/// properties it had before
+async fn update_command_row(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+    new_command_props: InternalCommand,
+) -> Result<(), UpdateCommandError> {
+    let current_time = unix_timestamp()?;
+    record_revision(conn, command_id, RevisionAction::Update, current_time).await?;
+    write_command_row(conn, command_id, new_command_props, current_time).await
+}
+
+/// Replaces the properties of a command, including its tags
+async fn write_command_row(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+    new_command_props: InternalCommand,
+    current_time: i64,
+) -> Result<(), UpdateCommandError> {
+    let (query, values) = Query::update()
+        .table(sqlite::Command::Table)
+        .values([
+            (sqlite::Command::Command, new_command_props.command.into()),
+            (sqlite::Command::Note, new_command_props.note.into()),
+            (
+                sqlite::Command::Favourite,
+                new_command_props.favourite.into(),
+            ),
+            (sqlite::Command::UpdatedAt, current_time.into()),
+        ])
+        .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
+        .build_sqlx(SqliteQueryBuilder);
+
+    let result = sqlx::query_with(&query, values)
+        .execute(&mut *conn)
+        .await
+        .map_err(UpdateCommandError::Query)?;
+
+    if result.rows_affected() == 0 {
+        return Err(UpdateCommandError::NoRowsAffected);
+    }
+
+    set_command_tags(conn, command_id, &new_command_props.tags).await?;
+    delete_unused_tags(conn).await?;
+
+    Ok(())
+}
+
+/// Moves a command to the trash
+async fn delete_command_row(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+) -> Result<(), DeleteCommandError> {
+    let current_time = unix_timestamp()?;
+
+    let (query, values) = Query::update()
+        .table(sqlite::Command::Table)
+        .values([(sqlite::Command::DeletedAt, current_time.into())])
+        .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
+        .and_where(Expr::col(sqlite::Command::DeletedAt).is_null())
+        .build_sqlx(SqliteQueryBuilder);
+
+    let result = sqlx::query_with(&query, values)
+        .execute(&mut *conn)
+        .await
+        .map_err(DeleteCommandError::Query)?;
+
+    if result.rows_affected() == 0 {
+        return Err(DeleteCommandError::NoRowsAffected);
+    }
+
+    record_revision(conn, command_id, RevisionAction::Delete, current_time).await?;
+
+    Ok(())
+}
+
+/// Moves a command in or out of the trash without recording a revision
+async fn set_deleted_at(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+    deleted_at: Option<i64>,
+) -> Result<(), UpdateCommandError> {
+    let (query, values) = Query::update()
+        .table(sqlite::Command::Table)
+        .values([(sqlite::Command::DeletedAt, deleted_at.into())])
+        .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
+        .build_sqlx(SqliteQueryBuilder);
+
+    let result = sqlx::query_with(&query, values)
+        .execute(&mut *conn)
+        .await
+        .map_err(UpdateCommandError::Query)?;
+
+    if result.rows_affected() == 0 {
+        return Err(UpdateCommandError::NoRowsAffected);
+    }
+
+    Ok(())
+}
+
+/// Returns the properties of a command, whether or not it is in the trash
+async fn select_internal_command(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+) -> Result<Option<InternalCommand>, sqlx::Error> {
+    let (query, values) = Query::select()
+        .columns([
+            sqlite::Command::Command,
+            sqlite::Command::Note,
+            sqlite::Command::Favourite,
+        ])
+        .from(sqlite::Command::Table)
+        .and_where(Expr::col(sqlite::Command::Id).eq(command_id))
+        .build_sqlx(SqliteQueryBuilder);
+
+    let Some(row) = sqlx::query_with(&query, values)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let (tags_query, tags_values) = Query::select()
+        .column((sqlite::Tag::Table, sqlite::Tag::Name))
+        .from(sqlite::CommandTag::Table)
+        .inner_join(
+            sqlite::Tag::Table,
+            Expr::col((sqlite::Tag::Table, sqlite::Tag::Id))
+                .equals((sqlite::CommandTag::Table, sqlite::CommandTag::TagId)),
+        )
+        .and_where(
+            Expr::col((sqlite::CommandTag::Table, sqlite::CommandTag::CommandId)).eq(command_id),
+        )
+        .order_by(
+            (sqlite::CommandTag::Table, Alias::new("rowid")),
+            sea_query::Order::Asc,
+        )
+        .build_sqlx(SqliteQueryBuilder);
+
+    let tags = sqlx::query_with(&tags_query, tags_values)
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+
+    Ok(Some(InternalCommand {
+        command: row.get("command"),
+        tags,
+        note: row.get("note"),
+        favourite: row.get("favourite"),
+    }))
+}
+
+/// Records the current properties of a command along with a change made to
+/// it. Nothing is recorded if the command does not exist
+async fn record_revision(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+    action: RevisionAction,
+    current_time: i64,
+) -> Result<(), sqlx::Error> {
+    let Some(command) = select_internal_command(conn, command_id).await? else {
+        return Ok(());
+    };
+    let tags = serde_json::to_string(&command.tags).expect("A list of strings is valid JSON");
+
+    let (query, values) = Query::insert()
+        .into_table(sqlite::CommandRevision::Table)
+        .columns([
+            sqlite::CommandRevision::CommandId,
+            sqlite::CommandRevision::Timestamp,
+            sqlite::CommandRevision::Action,
+            sqlite::CommandRevision::Command,
+            sqlite::CommandRevision::Tags,
+            sqlite::CommandRevision::Note,
+            sqlite::CommandRevision::Favourite,
+        ])
+        .values_panic([
+            command_id.into(),
+            current_time.into(),
+            action.as_str().into(),
+            command.command.into(),
+            tags.into(),
+            command.note.into(),
+            command.favourite.into(),
+        ])
+        .build_sqlx(SqliteQueryBuilder);
+
+    sqlx::query_with(&query, values)
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
+
+/// Builds a query for revisions, most recent first
+fn revisions_query() -> SelectStatement {
+    Query::select()
+        .columns([
+            sqlite::CommandRevision::Id,
+            sqlite::CommandRevision::CommandId,
+            sqlite::CommandRevision::Timestamp,
+            sqlite::CommandRevision::Action,
+            sqlite::CommandRevision::Command,
+            sqlite::CommandRevision::Tags,
+            sqlite::CommandRevision::Note,
+            sqlite::CommandRevision::Favourite,
+        ])
+        .from(sqlite::CommandRevision::Table)
+        .order_by(sqlite::CommandRevision::Id, sea_query::Order::Desc)
+        .to_owned()
+}
+
+/// Reads the revisions returned by a query built from [`revisions_query`]
+async fn select_revisions(
+    executor: impl SqliteExecutor<'_>,
+    query: SelectStatement,
+) -> Result<Vec<CommandRevision>, SelectCommandRevisionError> {
+    let (query, values) = query.build_sqlx(SqliteQueryBuilder);
+    let rows = sqlx::query_with(&query, values).fetch_all(executor).await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let action: String = row.get("action");
+            let tags: String = row.get("tags");
+            Ok(CommandRevision {
+                id: row.get("id"),
+                command_id: row.get("command_id"),
+                timestamp: row.get("timestamp"),
+                action: action
+                    .parse()
+                    .map_err(SelectCommandRevisionError::InvalidAction)?,
+                internal_command: InternalCommand {
+                    command: row.get("command"),
+                    tags: serde_json::from_str(&tags)?,
+                    note: row.get("note"),
+                    favourite: row.get("favourite"),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Replaces the tags of a command. Blank tags are skipped and tags that do
+/// not exist yet are created.
+async fn set_command_tags(
+    conn: &mut SqliteConnection,
+    command_id: i64,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    let (unlink_query, unlink_values) = Query::delete()
+        .from_table(sqlite::CommandTag::Table)
+        .and_where(Expr::col(sqlite::CommandTag::CommandId).eq(command_id))
+        .build_sqlx(SqliteQueryBuilder);
+    sqlx::query_with(&unlink_query, unlink_values)
+        .execute(&mut *conn)
+        .await?;
+
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        let (tag_query, tag_values) = Query::insert()
+            .into_table(sqlite::Tag::Table)
+            .columns([sqlite::Tag::Name])
+            .values_panic([tag.into()])
+            .on_conflict(
+                OnConflict::column(sqlite::Tag::Name)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&tag_query, tag_values)
+            .execute(&mut *conn)
+            .await?;
+
+        let (link_query, link_values) = Query::insert()
+            .into_table(sqlite::CommandTag::Table)
+            .columns([sqlite::CommandTag::CommandId, sqlite::CommandTag::TagId])
+            .select_from(
+                Query::select()
+                    .expr(Expr::val(command_id))
+                    .column(sqlite::Tag::Id)
+                    .from(sqlite::Tag::Table)
+                    .and_where(Expr::col(sqlite::Tag::Name).eq(tag))
+                    .to_owned(),
+            )
+            .expect("The select and insert column counts match")
+            .on_conflict(
+                OnConflict::columns([sqlite::CommandTag::CommandId, sqlite::CommandTag::TagId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&link_query, link_values)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Removes tags that are no longer linked to any command
+async fn delete_unused_tags(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let (query, values) = Query::delete()
+        .from_table(sqlite::Tag::Table)
+        .and_where(
+            Expr::col(sqlite::Tag::Id).not_in_subquery(
+                Query::select()
+                    .column(sqlite::CommandTag::TagId)
+                    .from(sqlite::CommandTag::Table)
+                    .to_owned(),
+            ),
+        )
+        .build_sqlx(SqliteQueryBuilder);
+
+    sqlx::query_with(&query, values)
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
+
+/// Matches the commands that are not in the trash
+fn not_trashed() -> SimpleExpr {
+    Expr::col((sqlite::Command::Table, sqlite::Command::DeletedAt)).is_null()
+}
+
+/// The condition for a command to come after the cursor in the given order,
+/// which must match the order used by [`SqliteDal::select_commands`]
+fn after_cursor(sort: CommandSort, cursor: &CommandCursor, frecency: SimpleExpr) -> SimpleExpr {
+    let after_id = Expr::col((sqlite::Command::Table, sqlite::Command::Id)).gt(cursor.id);
+    let after_last_used = Expr::col((sqlite::Command::Table, sqlite::Command::LastUsed))
+        .lt(cursor.last_used)
+        .or(
+            Expr::col((sqlite::Command::Table, sqlite::Command::LastUsed))
+                .eq(cursor.last_used)
+                .and(after_id.clone()),
+        );
+
+    match sort {
+        CommandSort::Insertion => after_id,
+        CommandSort::RecentlyUsed => after_last_used,
+        CommandSort::Frecency => Expr::expr(frecency.clone())
+            .lt(cursor.frecency)
+            .or(Expr::expr(frecency)
+                .eq(cursor.frecency)
+                .and(after_last_used)),
+    }
+}
+
+/// Returns a `LIKE` pattern that matches text starting with, or if `anywhere`
+/// is set containing, the given text. Wildcards in the text are taken literally
+fn like_pattern(text: &str, anywhere: bool) -> LikeExpr {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let pattern = if anywhere {
+        format!("%{}%", escaped)
+    } else {
+        format!("{}%", escaped)
+    };
+    LikeExpr::new(pattern).escape('\\')
+}
+
+/// Builds a subquery that scores the command row of the outer query by
+/// summing the weights of all of its recorded uses
+fn frecency_score(current_time: i64) -> SimpleExpr {
+    let timestamp = Expr::col((sqlite::CommandUsage::Table, sqlite::CommandUsage::Timestamp));
+    let weight = FRECENCY_BUCKETS
+        .iter()
+        .fold(CaseStatement::new(), |case, (max_age, weight)| {
+            case.case(timestamp.clone().gte(current_time - max_age), *weight)
+        })
+        .finally(FRECENCY_BASE_WEIGHT);
+
+    let score = Query::select()
+        .expr(Func::coalesce([
+            Func::sum(weight).into(),
+            Expr::val(0).into(),
+        ]))
+        .from(sqlite::CommandUsage::Table)
+        .and_where(
+            Expr::col((sqlite::CommandUsage::Table, sqlite::CommandUsage::CommandId))
+                .equals((sqlite::Command::Table, sqlite::Command::Id)),
+        )
+        .to_owned();
+
+    SimpleExpr::SubQuery(None, Box::new(SubQueryStatement::SelectStatement(score)))
+}
+
+/// Builds an FTS5 query matching the commands described by
+/// [`CommandStore::search_commands`]. Returns `None` if there are no words to
+/// search for
+fn full_text_query(command: Option<&str>, tag: Option<&str>) -> Option<String> {
+    // Words are quoted so that FTS5 operators in the search are taken literally
+    let prefixes = |search: &str| {
+        let words: Vec<String> = search_words(search)
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        (!words.is_empty()).then(|| words.join(" "))
+    };
+
+    let command_query = command
+        .and_then(prefixes)
+        .map(|query| format!("({})", query));
+    let tag_query = tag
+        .and_then(prefixes)
+        .map(|query| format!("tags : ({})", query));
+
+    match (command_query, tag_query) {
+        (Some(command_query), Some(tag_query)) => {
+            Some(format!("{} OR {}", command_query, tag_query))
         }
+        (command_query, tag_query) => command_query.or(tag_query),
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn test_dal(dir: &TempDir) -> SqliteDal {
+        let path = dir.path().join("database.sqlite");
+        SqliteDal {
+            sqlite_conn: SqliteConnectionPool::new(Some(path.to_string_lossy().into_owned()))
+                .await
+                .unwrap(),
+        }
+    }
+
+    /// Strings that would break (or change meaning) if they were ever spliced
+    /// into the SQL text
+    fn nasty_strings() -> Vec<String> {
+        vec![
+            "echo 'single' \"double\" `backtick`".to_string(),
+            "'); DROP TABLE command; --".to_string(),
+            r"C:\path\with\backslashes\\ and \' escapes".to_string(),
+            "nul\u{0}byte and \u{1}\u{2}\u{7f} control characters".to_string(),
+            "emoji 🚀🔥 and combining e\u{301} and 中文".to_string(),
+            "multi\nline\r\nscript\twith tabs".to_string(),
+            format!(
+                "#!/bin/sh\n{}",
+                "echo 'a fairly long line' && \\\n".repeat(4000)
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_nasty_strings_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let dal = test_dal(&dir).await;
+
+        let commands: Vec<InternalCommand> = nasty_strings()
+            .into_iter()
+            .map(|nasty| InternalCommand {
+                command: nasty.clone(),
+                // Tags are trimmed when stored
+                tags: vec![nasty.trim().to_string()],
+                note: Some(nasty),
+                favourite: false,
+            })
+            .collect();
+        assert!(commands.last().unwrap().command.len() > 100_000);
+
+        let inserted = dal
+            .insert_mulitple_commands(commands.clone())
+            .await
+            .unwrap();
+        assert_eq!(inserted, commands.len() as u64);
+
+        let stored = dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        let stored: Vec<InternalCommand> = stored.into_iter().map(|c| c.internal_command).collect();
+        assert_eq!(stored, commands);
+
+        // Updating and recording uses keep the text intact as well
+        let id = dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap()[1]
+            .id;
+        let mut updated = commands[2].clone();
+        updated.tags = vec![commands[4].command.clone()];
+        dal.update_command(id, updated.clone()).await.unwrap();
+
+        let usage = InternalCommandUsage {
+            action: UsageAction::Execute,
+            working_directory: Some(commands[4].command.clone()),
+            resolved_command: commands[3].command.clone(),
+        };
+        dal.record_command_usage(id, usage.clone()).await.unwrap();
+
+        let stored = dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(stored[1].internal_command, updated);
+        let history = dal.get_command_usage(id).await.unwrap();
+        assert_eq!(history[0].internal_usage, usage);
+    }
+
+    #[tokio::test]
+    async fn test_nasty_strings_search() {
+        let dir = TempDir::new().unwrap();
+        let dal = test_dal(&dir).await;
+
+        for nasty in nasty_strings() {
+            dal.insert_command(InternalCommand {
+                command: nasty,
+                tags: Vec::new(),
+                note: None,
+                favourite: false,
+            })
+            .await
+            .unwrap();
+        }
+
+        let found = dal
+            .search_commands(Some("DROP TABLE"), None, &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].internal_command.command, nasty_strings()[1]);
+
+        let found = dal
+            .search_commands(Some("'\"); --"), Some("\\'"), &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert!(found.is_empty());
+
+        let found = dal
+            .search_commands(Some("中文"), None, &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(found.len(), 1);
+
+        // Wildcards in the filters are matched literally
+        for (text, expected) in [("%", 0), ("_", 0), (r"\'", 1)] {
+            let query = CommandQuery {
+                text_contains: Some(text.to_string()),
+                ..Default::default()
+            };
+            let found = dal.query_commands(&query).await.unwrap().commands;
+            assert_eq!(found.len(), expected);
+        }
+
+        // The table was not dropped by any of the above
+        let commands = dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(commands.len(), nasty_strings().len());
     }
 }
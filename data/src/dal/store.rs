@@ -0,0 +1,612 @@
+use async_trait::async_trait;
+use std::path::Path;
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use uuid::Uuid;
+
+use super::{BackupDatabaseError, DeleteCommandError, InsertCommandError, SelectAllCommandsError};
+use super::{MaintenanceError, UndoChangeError, UpdateCommandError, WatchChangesError};
+use super::{
+    RestoreDatabaseError, SelectCommandError, SelectCommandRevisionError, SelectCommandUsageError,
+    SqlTxError,
+};
+use crate::models::*;
+
+/// A store of commands that the business logic can be run against
+#[async_trait]
+pub trait CommandStore: Send + Sync {
+    /// Returns a page of the commands, outside of the trash, that match the query
+    async fn query_commands(
+        &self,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError>;
+
+    /// Returns every command that is not in the trash in the requested order
+    async fn get_all_commands(
+        &self,
+        sort: CommandSort,
+        favourites_only: bool,
+    ) -> Result<Vec<Command>, SelectAllCommandsError> {
+        let query = CommandQuery {
+            sort,
+            favourites_only,
+            ..Default::default()
+        };
+        Ok(self.query_commands(&query).await?.commands)
+    }
+
+    /// Returns a command, whether or not it is in the trash
+    async fn get_command(&self, command_id: i64) -> Result<Command, SelectCommandError>;
+
+    /// Returns the command with the given UUID, whether or not it is in the trash
+    async fn get_command_by_uuid(&self, uuid: Uuid) -> Result<Command, SelectCommandError>;
+
+    /// Returns the oldest command outside of the trash whose text is exactly
+    /// the given text
+    async fn get_command_by_text(
+        &self,
+        command: &str,
+    ) -> Result<Option<Command>, SelectAllCommandsError>;
+
+    /// Returns a page of the commands matching the query where every word of
+    /// `command` starts a word in the command, tags or note, or where every
+    /// word of `tag` starts a word in the tags. Words are split on
+    /// non-alphanumeric characters and compared case-insensitively
+    async fn search_commands(
+        &self,
+        command: Option<&str>,
+        tag: Option<&str>,
+        query: &CommandQuery,
+    ) -> Result<CommandPage, SelectAllCommandsError>;
+
+    /// Inserts a command and returns the ID of the inserted command
+    async fn insert_command(&self, command: InternalCommand) -> Result<i64, InsertCommandError>;
+
+    /// Inserts every command, or none of them if one fails, and returns the
+    /// number of inserted commands
+    async fn insert_mulitple_commands(
+        &self,
+        commands: Vec<InternalCommand>,
+    ) -> Result<u64, InsertCommandError>;
+
+    /// Sets the last used property of a command to the current time
+    async fn update_command_last_used_property(
+        &self,
+        command_id: i64,
+    ) -> Result<(), UpdateCommandError>;
+
+    /// Records a use of a command and updates its last used property
+    async fn record_command_usage(
+        &self,
+        command_id: i64,
+        usage: InternalCommandUsage,
+    ) -> Result<(), UpdateCommandError>;
+
+    /// Returns every recorded use of a command, most recent first
+    async fn get_command_usage(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandUsage>, SelectCommandUsageError>;
+
+    /// Moves a command to the trash
+    async fn delete_command(&self, command_id: i64) -> Result<(), DeleteCommandError>;
+
+    /// Returns every command in the trash, most recently deleted first
+    async fn get_trashed_commands(&self) -> Result<Vec<Command>, SelectAllCommandsError>;
+
+    /// Moves a command out of the trash
+    async fn restore_command(&self, command_id: i64) -> Result<(), UpdateCommandError>;
+
+    /// Permanently deletes the commands that were moved to the trash before the
+    /// given unix timestamp, along with their usage history. Returns the number
+    /// of deleted commands
+    async fn purge_trash(&self, deleted_before: i64) -> Result<u64, DeleteCommandError>;
+
+    /// Replaces the properties of a command
+    async fn update_command(
+        &self,
+        command_id: i64,
+        new_command_props: InternalCommand,
+    ) -> Result<(), UpdateCommandError>;
+
+    /// Returns every change made to a command, most recent first
+    async fn get_command_revisions(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandRevision>, SelectCommandRevisionError>;
+
+    /// Reverts the most recent addition, update, deletion or restoration of any
+    /// command and forgets it, so that the change before it is undone next.
+    /// An addition is reverted by moving the command to the trash. Returns the
+    /// undone change, or `None` if there is nothing to undo
+    async fn undo_last_change(&self) -> Result<Option<CommandRevision>, UndoChangeError>;
+
+    /// Starts a transaction for changes that must either all be applied or
+    /// not at all
+    async fn begin(&self) -> Result<Box<dyn StoreTransaction + '_>, SqlTxError>;
+
+    /// Writes a consistent copy of every command to a new database file
+    async fn backup(&self, destination: &Path) -> Result<(), BackupDatabaseError>;
+
+    /// Replaces every command with the commands in a backup, after checking
+    /// that the backup can be restored
+    async fn restore(&self, backup: &Path) -> Result<(), RestoreDatabaseError>;
+
+    /// Returns the ID and text of every command, including the commands in the
+    /// trash, even if their other properties cannot be read
+    async fn get_command_texts(&self) -> Result<Vec<(i64, String)>, SelectAllCommandsError>;
+
+    /// Checks the store for corruption, orphaned rows and rows that cannot be
+    /// read
+    async fn check_integrity(&self) -> Result<IntegrityReport, MaintenanceError>;
+
+    /// Repairs every problem found by [`CommandStore::check_integrity`] that
+    /// can be repaired without losing commands, and reclaims unused space
+    async fn repair(&self) -> Result<RepairSummary, MaintenanceError>;
+
+    /// Starts watching the store for changes made by anyone, including other
+    /// processes that use the same database
+    async fn watch_changes(&self) -> Result<Box<dyn ChangeWatcher>, WatchChangesError>;
+}
+
+/// A set of changes to a [`CommandStore`] that is applied atomically.
+///
+/// None of the changes are visible outside of the transaction until it is
+/// committed. Dropping a transaction without committing it rolls it back
+#[async_trait]
+pub trait StoreTransaction: Send {
+    /// Inserts a command and returns the ID of the inserted command
+    async fn insert_command(&mut self, command: InternalCommand)
+        -> Result<i64, InsertCommandError>;
+
+    /// Inserts a command that keeps the identity it was given elsewhere, such
+    /// as in another database, and returns the ID of the inserted command
+    async fn insert_command_with_identity(
+        &mut self,
+        command: InternalCommand,
+        identity: CommandIdentity,
+    ) -> Result<i64, InsertCommandError>;
+
+    /// Replaces the properties of a command
+    async fn update_command(
+        &mut self,
+        command_id: i64,
+        new_command_props: InternalCommand,
+    ) -> Result<(), UpdateCommandError>;
+
+    /// Moves a command to the trash
+    async fn delete_command(&mut self, command_id: i64) -> Result<(), DeleteCommandError>;
+
+    /// Applies every change made in the transaction
+    async fn commit(self: Box<Self>) -> Result<(), SqlTxError>;
+
+    /// Discards every change made in the transaction
+    async fn rollback(self: Box<Self>) -> Result<(), SqlTxError>;
+}
+
+/// Detects changes to a [`CommandStore`] since it was last asked
+#[async_trait]
+pub trait ChangeWatcher: Send {
+    /// Returns whether the store has changed since the watcher was started or
+    /// since the last time this was called
+    async fn has_changed(&mut self) -> Result<bool, WatchChangesError>;
+}
+
+/// The weight a use of a command adds to its frecency score, by the maximum
+/// age (in seconds) of the use. Older uses add [`FRECENCY_BASE_WEIGHT`]
+pub(crate) const FRECENCY_BUCKETS: [(i64, i64); 4] = [
+    (4 * SECONDS_PER_DAY, 100),
+    (14 * SECONDS_PER_DAY, 70),
+    (31 * SECONDS_PER_DAY, 50),
+    (90 * SECONDS_PER_DAY, 30),
+];
+pub(crate) const FRECENCY_BASE_WEIGHT: i64 = 10;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Returns the current unix timestamp in seconds
+pub(crate) fn unix_timestamp() -> Result<i64, SystemTimeError> {
+    let duration_since_epoch = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    Ok(duration_since_epoch.as_secs() as i64)
+}
+
+/// Splits a search into the words that are matched by
+/// [`CommandStore::search_commands`]
+pub(crate) fn search_words(search: &str) -> impl Iterator<Item = &str> {
+    search
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dal::memory::MemoryStore;
+    use crate::dal::sqlite::SqliteConnectionPool;
+    use crate::dal::sqlite_dal::SqliteDal;
+    use tempfile::TempDir;
+
+    fn command(command: &str, tags: &[&str], note: Option<&str>) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            note: note.map(str::to_string),
+            favourite: false,
+        }
+    }
+
+    fn commands_of(commands: Vec<Command>) -> Vec<String> {
+        commands
+            .into_iter()
+            .map(|c| c.internal_command.command)
+            .collect()
+    }
+
+    /// Runs the same operations against a store, so that every store behaves
+    /// like the database
+    async fn check_store(store: &dyn CommandStore) {
+        let ls = store
+            .insert_command(command("ls -la", &["files", " files ", ""], None))
+            .await
+            .unwrap();
+        let du = store
+            .insert_command(command("du -sh", &["files"], Some("Disk usage")))
+            .await
+            .unwrap();
+        let inserted = store
+            .insert_mulitple_commands(vec![command("git status", &["git"], None)])
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let commands = store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            commands_of(commands.clone()),
+            vec!["ls -la", "du -sh", "git status"]
+        );
+        assert_eq!(commands[0].internal_command.tags, vec!["files".to_string()]);
+        let found = store.get_command(du).await.unwrap();
+        assert_eq!(found.internal_command.note.as_deref(), Some("Disk usage"));
+        assert!(matches!(
+            store.get_command(-1).await,
+            Err(SelectCommandError::NotFound(-1))
+        ));
+
+        // Every command is given its own UUID, by which it can be found
+        assert_ne!(commands[0].uuid, commands[1].uuid);
+        assert_eq!(commands[0].created_at, commands[0].updated_at);
+        let found = store.get_command_by_uuid(commands[1].uuid).await.unwrap();
+        assert_eq!(found.id, du);
+        let unknown = Uuid::new_v4();
+        assert!(matches!(
+            store.get_command_by_uuid(unknown).await,
+            Err(SelectCommandError::UuidNotFound(uuid)) if uuid == unknown
+        ));
+
+        // Commands can be found by their exact text
+        let found = store.get_command_by_text("du -sh").await.unwrap();
+        assert_eq!(found.map(|c| c.id), Some(du));
+        assert!(store.get_command_by_text("du").await.unwrap().is_none());
+
+        // Searching matches word prefixes in the command, tags and note
+        let found = store
+            .search_commands(Some("DISK us"), None, &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(commands_of(found), vec!["du -sh"]);
+        let found = store
+            .search_commands(None, Some("fil"), &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(commands_of(found), vec!["ls -la", "du -sh"]);
+        let found = store
+            .search_commands(Some("status"), Some("files"), &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(commands_of(found), vec!["ls -la", "du -sh", "git status"]);
+        let found = store
+            .search_commands(Some("--"), None, &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert!(found.is_empty());
+
+        // Frecency favours the most used command
+        let usage = InternalCommandUsage {
+            action: UsageAction::Execute,
+            working_directory: None,
+            resolved_command: "du -sh".to_string(),
+        };
+        for _ in 0..2 {
+            store.record_command_usage(du, usage.clone()).await.unwrap();
+        }
+        store.record_command_usage(ls, usage.clone()).await.unwrap();
+        let commands = store
+            .get_all_commands(CommandSort::Frecency, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            commands_of(commands),
+            vec!["du -sh", "ls -la", "git status"]
+        );
+
+        // Later pages are scored at the time of the first page, at which a
+        // year from now every use only has the base weight
+        let found = store.get_command(du).await.unwrap();
+        let scored_at = unix_timestamp().unwrap() + 365 * SECONDS_PER_DAY;
+        let query = CommandQuery {
+            sort: CommandSort::Frecency,
+            cursor: Some(CommandCursor {
+                id: du,
+                last_used: found.last_used,
+                frecency: 2 * FRECENCY_BASE_WEIGHT,
+                scored_at,
+            }),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = store.query_commands(&query).await.unwrap();
+        assert_eq!(commands_of(page.commands), vec!["ls -la"]);
+        assert_eq!(page.next_cursor.unwrap().scored_at, scored_at);
+        assert_eq!(store.get_command_usage(du).await.unwrap().len(), 2);
+
+        // Queries filter the commands and split them into pages
+        let query = CommandQuery {
+            tag_prefix: Some("FI".to_string()),
+            ..Default::default()
+        };
+        let found = store.query_commands(&query).await.unwrap().commands;
+        assert_eq!(commands_of(found), vec!["ls -la", "du -sh"]);
+        let query = CommandQuery {
+            text_contains: Some("S -".to_string()),
+            ..Default::default()
+        };
+        let found = store.query_commands(&query).await.unwrap().commands;
+        assert_eq!(commands_of(found), vec!["ls -la"]);
+
+        for sort in [
+            CommandSort::Insertion,
+            CommandSort::RecentlyUsed,
+            CommandSort::Frecency,
+        ] {
+            let mut query = CommandQuery {
+                sort,
+                limit: Some(2),
+                ..Default::default()
+            };
+            let mut paged = Vec::new();
+            loop {
+                let page = store.query_commands(&query).await.unwrap();
+                assert!(page.commands.len() <= 2);
+                paged.extend(commands_of(page.commands));
+                match page.next_cursor {
+                    Some(cursor) => query.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            let all = store.get_all_commands(sort, false).await.unwrap();
+            assert_eq!(paged, commands_of(all));
+        }
+
+        let query = CommandQuery {
+            limit: Some(1),
+            offset: 1,
+            ..Default::default()
+        };
+        let page = store.query_commands(&query).await.unwrap();
+        assert_eq!(commands_of(page.commands), vec!["du -sh"]);
+        assert!(page.next_cursor.is_some());
+
+        let mut query = CommandQuery {
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = store
+            .search_commands(None, Some("files"), &query)
+            .await
+            .unwrap();
+        assert_eq!(commands_of(page.commands), vec!["ls -la"]);
+        query.cursor = page.next_cursor;
+        let page = store
+            .search_commands(None, Some("files"), &query)
+            .await
+            .unwrap();
+        assert_eq!(commands_of(page.commands), vec!["du -sh"]);
+        assert!(page.next_cursor.is_none());
+
+        // Updates replace every property of a command
+        let mut updated = command("du -sh .", &["disk"], None);
+        updated.favourite = true;
+        store.update_command(du, updated.clone()).await.unwrap();
+        let favourites = store
+            .get_all_commands(CommandSort::Insertion, true)
+            .await
+            .unwrap();
+        assert_eq!(favourites.len(), 1);
+        assert_eq!(favourites[0].internal_command, updated);
+
+        // Transactions are only visible once committed
+        let mut tx = store.begin().await.unwrap();
+        tx.insert_command(command("whoami", &[], None))
+            .await
+            .unwrap();
+        tx.delete_command(ls).await.unwrap();
+        tx.rollback().await.unwrap();
+        let commands = store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            commands_of(commands),
+            vec!["ls -la", "du -sh .", "git status"]
+        );
+
+        let mut tx = store.begin().await.unwrap();
+        tx.insert_command(command("whoami", &[], None))
+            .await
+            .unwrap();
+        tx.update_command(ls, command("ls", &[], None))
+            .await
+            .unwrap();
+        assert!(matches!(
+            tx.delete_command(-1).await,
+            Err(DeleteCommandError::NoRowsAffected)
+        ));
+        tx.commit().await.unwrap();
+        let commands = store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            commands_of(commands),
+            vec!["ls", "du -sh .", "git status", "whoami"]
+        );
+
+        // Dropping a transaction rolls it back
+        {
+            let mut tx = store.begin().await.unwrap();
+            tx.delete_command(ls).await.unwrap();
+        }
+        let commands = store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(commands.len(), 4);
+
+        // Deleted commands are moved to the trash along with their history
+        store.delete_command(du).await.unwrap();
+        assert!(matches!(
+            store.delete_command(du).await,
+            Err(DeleteCommandError::NoRowsAffected)
+        ));
+        let commands = store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(commands_of(commands), vec!["ls", "git status", "whoami"]);
+        let found = store
+            .search_commands(Some("du"), None, &CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert!(found.is_empty());
+        assert!(store
+            .get_command_by_text("du -sh .")
+            .await
+            .unwrap()
+            .is_none());
+        let trash = store.get_trashed_commands().await.unwrap();
+        assert_eq!(commands_of(trash.clone()), vec!["du -sh ."]);
+        assert!(trash[0].deleted_at.is_some());
+        assert!(store.get_command(du).await.unwrap().deleted_at.is_some());
+        assert_eq!(store.get_command_usage(du).await.unwrap().len(), 2);
+
+        store.restore_command(du).await.unwrap();
+        assert!(matches!(
+            store.restore_command(du).await,
+            Err(UpdateCommandError::NoRowsAffected)
+        ));
+        assert!(store.get_trashed_commands().await.unwrap().is_empty());
+
+        // Only commands deleted before the cutoff are purged, with their history
+        store.delete_command(du).await.unwrap();
+        let deleted_at = store.get_trashed_commands().await.unwrap()[0]
+            .deleted_at
+            .unwrap();
+        assert_eq!(store.purge_trash(deleted_at).await.unwrap(), 0);
+        assert_eq!(store.purge_trash(deleted_at + 1).await.unwrap(), 1);
+        assert!(store.get_trashed_commands().await.unwrap().is_empty());
+        assert!(store.get_command_usage(du).await.unwrap().is_empty());
+        assert!(matches!(
+            store.update_command_last_used_property(du).await,
+            Err(UpdateCommandError::NoRowsAffected)
+        ));
+        assert!(store.get_command_revisions(du).await.unwrap().is_empty());
+
+        // Every change is recorded with the properties the command had before
+        let revisions = store.get_command_revisions(ls).await.unwrap();
+        let actions: Vec<RevisionAction> = revisions.iter().map(|r| r.action).collect();
+        assert_eq!(actions, vec![RevisionAction::Update, RevisionAction::Add]);
+        assert_eq!(
+            revisions[0].internal_command,
+            command("ls -la", &["files"], None)
+        );
+
+        // Changes are undone most recent first
+        let undone = store.undo_last_change().await.unwrap().unwrap();
+        assert_eq!(undone.action, RevisionAction::Update);
+        let undone = store.undo_last_change().await.unwrap().unwrap();
+        assert_eq!(undone.action, RevisionAction::Add);
+        let commands = store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(commands_of(commands), vec!["ls -la", "git status"]);
+        assert_eq!(
+            commands_of(store.get_trashed_commands().await.unwrap()),
+            vec!["whoami"]
+        );
+
+        store.delete_command(ls).await.unwrap();
+        let undone = store.undo_last_change().await.unwrap().unwrap();
+        assert_eq!(undone.action, RevisionAction::Delete);
+        assert_eq!(store.get_trashed_commands().await.unwrap().len(), 1);
+
+        while store.undo_last_change().await.unwrap().is_some() {}
+        assert!(store
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.get_trashed_commands().await.unwrap().len(), 3);
+
+        // An imported command keeps its identity, which must be unique
+        let identity = CommandIdentity {
+            uuid: Uuid::new_v4(),
+            created_at: 100,
+            updated_at: 200,
+        };
+        let mut tx = store.begin().await.unwrap();
+        let imported = tx
+            .insert_command_with_identity(command("uname", &[], None), identity)
+            .await
+            .unwrap();
+        assert!(tx
+            .insert_command_with_identity(command("uname -a", &[], None), identity)
+            .await
+            .is_err());
+        drop(tx);
+        assert!(store.get_command(imported).await.is_err());
+
+        let mut tx = store.begin().await.unwrap();
+        let imported = tx
+            .insert_command_with_identity(command("uname", &[], None), identity)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let found = store.get_command_by_uuid(identity.uuid).await.unwrap();
+        assert_eq!(found.id, imported);
+        assert_eq!((found.created_at, found.updated_at), (100, 200));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        check_store(&MemoryStore::new()).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("database.sqlite");
+        let store = SqliteDal {
+            sqlite_conn: SqliteConnectionPool::new(Some(path.to_string_lossy().into_owned()))
+                .await
+                .unwrap(),
+        };
+        check_store(&store).await;
+    }
+}
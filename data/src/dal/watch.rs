@@ -0,0 +1,99 @@
+//! # Watching for Changes
+//!
+//! SQLite increments the `data_version` of a connection whenever another
+//! connection commits a change to the database, whether that connection is in
+//! the same process or not. A watcher keeps a connection of its own that never
+//! writes, so every change to the database is seen as a new `data_version`.
+
+use async_trait::async_trait;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+use super::store::ChangeWatcher;
+use super::WatchChangesError;
+
+/// Watches a database for changes by polling its `data_version`
+pub(crate) struct SqliteChangeWatcher {
+    conn: SqliteConnection,
+    data_version: i64,
+}
+
+impl SqliteChangeWatcher {
+    /// Starts watching the database that the pool is connected to, on a
+    /// connection that is taken out of the pool
+    pub(crate) async fn new(pool: &SqlitePool) -> Result<Self, WatchChangesError> {
+        let mut conn = pool.acquire().await?.detach();
+        let data_version = data_version(&mut conn).await?;
+
+        Ok(SqliteChangeWatcher { conn, data_version })
+    }
+}
+
+#[async_trait]
+impl ChangeWatcher for SqliteChangeWatcher {
+    async fn has_changed(&mut self) -> Result<bool, WatchChangesError> {
+        let data_version = data_version(&mut self.conn).await?;
+        let changed = data_version != self.data_version;
+        self.data_version = data_version;
+
+        Ok(changed)
+    }
+}
+
+async fn data_version(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    Ok(sqlx::query("PRAGMA data_version")
+        .fetch_one(conn)
+        .await?
+        .get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dal::sqlite_dal::SqliteDal;
+    use crate::dal::store::CommandStore;
+    use crate::models::{CommandSort, InternalCommand};
+    use tempfile::tempdir;
+
+    fn command(command: &str) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: Vec::new(),
+            note: None,
+            favourite: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changes_from_other_connections_are_seen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("database.sqlite");
+        let path = path.to_str().unwrap().to_string();
+        // Like the GUI app and the CLI using the same stack
+        let app = SqliteDal::new_with_custom_path(path.clone()).await.unwrap();
+        let cli = SqliteDal::new_with_custom_path(path).await.unwrap();
+
+        let journal_mode: String = sqlx::query("PRAGMA journal_mode")
+            .fetch_one(&app.sqlite_conn.pool)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(journal_mode, "wal");
+
+        let mut watcher = app.watch_changes().await.unwrap();
+        assert!(!watcher.has_changed().await.unwrap());
+
+        cli.insert_command(command("ls")).await.unwrap();
+        assert!(watcher.has_changed().await.unwrap());
+        assert!(!watcher.has_changed().await.unwrap());
+
+        // Changes made through the store being watched are seen too
+        app.insert_command(command("pwd")).await.unwrap();
+        assert!(watcher.has_changed().await.unwrap());
+
+        // Reads are not changes
+        cli.get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert!(!watcher.has_changed().await.unwrap());
+    }
+}
@@ -1,11 +1,15 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 /// Represents the properties of a command that the user will
 /// have knowledge about
 pub struct InternalCommand {
     pub command: String,
-    pub tag: Option<String>,
+    /// Exports created before commands could have multiple tags store a
+    /// single (optional) `tag` string, which is still accepted here
+    #[serde(default, alias = "tag", deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
     pub note: Option<String>,
     pub favourite: bool,
 }
@@ -14,6 +18,323 @@ pub struct InternalCommand {
 /// Stores all properties of a command in the database
 pub struct Command {
     pub id: i64,
+    /// Identifies the command across databases, unlike the ID
+    pub uuid: Uuid,
     pub last_used: i64,
+    pub created_at: i64,
+    /// When the properties of the command were last changed
+    pub updated_at: i64,
+    /// When the command was moved to the trash, if it has been
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
     pub internal_command: InternalCommand,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// The identity of a command that stays the same when it is moved to another
+/// database, such as by exporting and importing it
+pub struct CommandIdentity {
+    pub uuid: Uuid,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl CommandIdentity {
+    /// The identity of a command that is created at the given time
+    pub fn new(current_time: i64) -> CommandIdentity {
+        CommandIdentity {
+            uuid: Uuid::new_v4(),
+            created_at: current_time,
+            updated_at: current_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// How a command was used
+pub enum UsageAction {
+    Copy,
+    Execute,
+}
+
+impl UsageAction {
+    /// The representation of the action stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageAction::Copy => "Copy",
+            UsageAction::Execute => "Execute",
+        }
+    }
+}
+
+impl std::str::FromStr for UsageAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Copy" => Ok(UsageAction::Copy),
+            "Execute" => Ok(UsageAction::Execute),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Represents a single use of a command
+pub struct InternalCommandUsage {
+    pub action: UsageAction,
+    pub working_directory: Option<String>,
+    /// The command text after its parameters were filled in
+    pub resolved_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Stores all properties of a command use in the database
+pub struct CommandUsage {
+    pub id: i64,
+    pub command_id: i64,
+    pub timestamp: i64,
+    pub internal_usage: InternalCommandUsage,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// A change made to a command
+pub enum RevisionAction {
+    Add,
+    Update,
+    /// The command was moved to the trash
+    Delete,
+    /// The command was moved out of the trash
+    Restore,
+}
+
+impl RevisionAction {
+    /// The representation of the action stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevisionAction::Add => "Add",
+            RevisionAction::Update => "Update",
+            RevisionAction::Delete => "Delete",
+            RevisionAction::Restore => "Restore",
+        }
+    }
+}
+
+impl std::str::FromStr for RevisionAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Add" => Ok(RevisionAction::Add),
+            "Update" => Ok(RevisionAction::Update),
+            "Delete" => Ok(RevisionAction::Delete),
+            "Restore" => Ok(RevisionAction::Restore),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Stores a change made to a command in the database
+pub struct CommandRevision {
+    pub id: i64,
+    pub command_id: i64,
+    pub timestamp: i64,
+    pub action: RevisionAction,
+    /// The properties of the command before the change. A command that was
+    /// added has no previous properties, so the properties it was added with
+    /// are stored instead
+    pub internal_command: InternalCommand,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+/// The order in which commands are returned. Commands that tie are returned
+/// in the order they were added in
+pub enum CommandSort {
+    /// The order the commands were added in
+    #[default]
+    Insertion,
+    /// Most recently used first
+    RecentlyUsed,
+    /// Highest frecency first, where every use of a command adds a score
+    /// that decays with the age of the use
+    Frecency,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+/// Which commands to return, in what order and how many of them.
+///
+/// Only commands outside of the trash are returned
+pub struct CommandQuery {
+    /// Only return commands with a tag that starts with this prefix
+    pub tag_prefix: Option<String>,
+    pub favourites_only: bool,
+    /// Only return commands whose text contains this, ignoring case
+    pub text_contains: Option<String>,
+    pub sort: CommandSort,
+    /// The maximum number of commands to return
+    pub limit: Option<u32>,
+    /// The number of commands to skip, after the cursor if there is one
+    pub offset: u32,
+    /// Only return the commands that come after this position
+    pub cursor: Option<CommandCursor>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// The position of a command in the order of a [`CommandQuery`], which is
+/// used to continue from the end of a page
+pub struct CommandCursor {
+    pub id: i64,
+    pub last_used: i64,
+    /// The frecency score of the command when the page was returned. Only
+    /// used when sorting by frecency
+    pub frecency: i64,
+    /// The time the frecency score was calculated at, which the next pages are
+    /// scored at too so that they continue in the same order
+    pub scored_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// A page of commands returned for a [`CommandQuery`]
+pub struct CommandPage {
+    pub commands: Vec<Command>,
+    /// Where the next page starts, or `None` if this is the last page
+    pub next_cursor: Option<CommandCursor>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+/// The problems found by checking the database of a store
+pub struct IntegrityReport {
+    /// Problems reported by SQLite's integrity check. Empty unless the
+    /// database file is corrupted
+    pub corruption: Vec<String>,
+    /// Rows that refer to a row that does not exist, or tags that no command has
+    pub orphaned_rows: Vec<OrphanedRows>,
+    /// Rows that cannot be read as a command, use or revision
+    pub invalid_rows: Vec<InvalidRow>,
+    /// Commands that are missing from the search index
+    pub unindexed_commands: u64,
+    /// The size of the database file
+    pub size_bytes: u64,
+    /// The part of the database file that is unused and can be reclaimed
+    pub unused_bytes: u64,
+}
+
+impl IntegrityReport {
+    /// Whether no problems were found
+    pub fn is_healthy(&self) -> bool {
+        self.corruption.is_empty()
+            && self.orphaned_rows.is_empty()
+            && self.invalid_rows.is_empty()
+            && self.unindexed_commands == 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// The number of orphaned rows in a table
+pub struct OrphanedRows {
+    pub table: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// A row that cannot be read by the application
+pub struct InvalidRow {
+    pub table: String,
+    pub id: i64,
+    pub problem: RowProblem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Why a row cannot be read by the application
+pub enum RowProblem {
+    /// The text of the command is empty
+    EmptyCommand,
+    /// The UUID of the command is missing or is not a UUID
+    InvalidUuid,
+    /// The action of a use or revision is not one the application knows
+    UnknownAction(String),
+    /// The tags of a revision are not a list of tags
+    InvalidTags,
+    /// The column holds a value that is not text, such as a blob written by
+    /// another tool
+    Unreadable(String),
+}
+
+impl std::fmt::Display for RowProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowProblem::EmptyCommand => write!(f, "The command is empty"),
+            RowProblem::InvalidUuid => write!(f, "The UUID is missing or invalid"),
+            RowProblem::UnknownAction(action) => write!(f, "Unknown action {:?}", action),
+            RowProblem::InvalidTags => write!(f, "The tags are not a list of tags"),
+            RowProblem::Unreadable(column) => write!(f, "The {} cannot be read as text", column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+/// What was changed by repairing the database of a store
+pub struct RepairSummary {
+    /// Orphaned rows and search index entries that were deleted
+    pub orphaned_rows_deleted: u64,
+    /// Uses and revisions that could not be read and were deleted
+    pub invalid_rows_deleted: u64,
+    /// Commands with a missing or invalid UUID that were given a new one
+    pub uuids_replaced: u64,
+    /// How much smaller the database file became
+    pub bytes_reclaimed: u64,
+}
+
+/// Deserializes tags from either a list of tags, a single tag or `null`
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        Single(String),
+        Multiple(Vec<String>),
+    }
+
+    Ok(match Option::<Tags>::deserialize(deserializer)? {
+        Some(Tags::Single(tag)) => vec![tag],
+        Some(Tags::Multiple(tags)) => tags,
+        None => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_tags() {
+        let command: InternalCommand = serde_json::from_str(
+            r#"{"command": "ls", "tags": ["a", "b"], "note": null, "favourite": false}"#,
+        )
+        .unwrap();
+        assert_eq!(command.tags, vec!["a".to_string(), "b".to_string()]);
+
+        let command: InternalCommand =
+            serde_json::from_str(r#"{"command": "ls", "note": null, "favourite": false}"#).unwrap();
+        assert!(command.tags.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_single_tag() {
+        let command: InternalCommand = serde_json::from_str(
+            r#"{"command": "ls", "tag": "a", "note": null, "favourite": false}"#,
+        )
+        .unwrap();
+        assert_eq!(command.tags, vec!["a".to_string()]);
+
+        let command: InternalCommand = serde_json::from_str(
+            r#"{"command": "ls", "tag": null, "note": null, "favourite": false}"#,
+        )
+        .unwrap();
+        assert!(command.tags.is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+//! # Backups
+//!
+//! Backups are complete copies of the database of a stack. They are kept in
+//! the `backups/<stack>` directory in the `cmdstack` config directory, unless
+//! the user chooses another file.
+//!
+//! A backup is also made automatically before any operation that changes many
+//! commands at once, such as importing commands, purging the trash, merging
+//! duplicates or restoring a backup. Only the most recent automatic backups
+//! are kept, as set by `auto_backup_count` in the config.
+
+use chrono::Utc;
+use data::dal::backup::validate_backup;
+use data::dal::{BackupDatabaseError, RestoreDatabaseError};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use thiserror::Error;
+
+use crate::Logic;
+
+/// The start of the file names of automatic backups
+const AUTO_BACKUP_PREFIX: &str = "auto-";
+
+/// The start of the file names of backups made by the user
+const MANUAL_BACKUP_PREFIX: &str = "manual-";
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("There is no backup directory for this store")]
+    NoBackupDirectory,
+    #[error("Failed to access the backup directory: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to back up the database: {0}")]
+    Database(#[from] BackupDatabaseError),
+}
+
+#[derive(Error, Debug)]
+pub enum RestoreError {
+    #[error("Failed to restore the backup: {0}")]
+    Database(#[from] RestoreDatabaseError),
+    #[error("Failed to back up the database before restoring: {0}")]
+    Backup(#[from] BackupError),
+}
+
+impl Logic {
+    /// Handles backing up the database of the stack to a new file, which is a
+    /// timestamped file in the backup directory if no destination is given.
+    /// Returns the path of the backup
+    pub async fn backup(&self, destination: Option<PathBuf>) -> Result<PathBuf, BackupError> {
+        let destination = match destination {
+            Some(destination) => destination,
+            None => self.new_backup_path(MANUAL_BACKUP_PREFIX)?,
+        };
+        self.dal.backup(&destination).await?;
+
+        Ok(destination)
+    }
+
+    /// Handles replacing every command of the stack with the commands in a
+    /// backup. The backup is checked before anything is changed, and the
+    /// current commands are backed up automatically first
+    pub async fn restore_backup(&self, backup: &Path) -> Result<(), RestoreError> {
+        validate_backup(backup).await?;
+        self.auto_backup().await?;
+
+        Ok(self.dal.restore(backup).await?)
+    }
+
+    /// Backs up the database before an operation that changes many commands,
+    /// and deletes the oldest automatic backups beyond the number that are
+    /// kept. Nothing is done if automatic backups are disabled or if there is
+    /// no backup directory, such as for a store that only lives in memory
+    pub(crate) async fn auto_backup(&self) -> Result<(), BackupError> {
+        let keep = self.config.auto_backup_count as usize;
+        if keep == 0 || self.backup_dir.is_none() {
+            return Ok(());
+        }
+
+        let destination = self.new_backup_path(AUTO_BACKUP_PREFIX)?;
+        self.dal.backup(&destination).await?;
+
+        let mut auto_backups = self.auto_backups()?;
+        // The timestamps in the file names sort in the order they were made in
+        auto_backups.sort();
+        let num_expired = auto_backups.len().saturating_sub(keep);
+        for expired in &auto_backups[..num_expired] {
+            fs::remove_file(expired)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the paths of the automatic backups in the backup directory
+    fn auto_backups(&self) -> Result<Vec<PathBuf>, BackupError> {
+        let backup_dir = self
+            .backup_dir
+            .as_ref()
+            .ok_or(BackupError::NoBackupDirectory)?;
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(backup_dir)? {
+            let path = entry?.path();
+            let is_auto_backup =
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(AUTO_BACKUP_PREFIX) && name.ends_with(".sqlite")
+                    });
+            if is_auto_backup {
+                paths.push(path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Returns the path of a new backup in the backup directory, which is
+    /// named after the current time
+    fn new_backup_path(&self, prefix: &str) -> Result<PathBuf, BackupError> {
+        let backup_dir = self
+            .backup_dir
+            .as_ref()
+            .ok_or(BackupError::NoBackupDirectory)?;
+        let timestamp = Utc::now().format("%Y%m%d-%H%M%S%6f");
+
+        Ok(backup_dir.join(format!("{}{}.sqlite", prefix, timestamp)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data::dal::memory::MemoryStore;
+    use data::dal::sqlite_dal::SqliteDal;
+    use data::models::{CommandSort, InternalCommand};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn command(command: &str) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: Vec::new(),
+            note: None,
+            favourite: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_backups_are_rotated() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("database.sqlite");
+        let dal = SqliteDal::new_with_custom_path(db_path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let mut logic = Logic::new(dal).unwrap();
+        let backup_dir = dir.path().join("backups");
+        fs::create_dir(&backup_dir).unwrap();
+        logic.backup_dir = Some(backup_dir);
+        logic.config.auto_backup_count = 2;
+
+        for _ in 0..4 {
+            logic.auto_backup().await.unwrap();
+        }
+        assert_eq!(logic.auto_backups().unwrap().len(), 2);
+
+        // Backups made by the user are never rotated
+        logic.backup(None).await.unwrap();
+        logic.auto_backup().await.unwrap();
+        assert_eq!(logic.auto_backups().unwrap().len(), 2);
+
+        logic.config.auto_backup_count = 0;
+        logic.auto_backup().await.unwrap();
+        assert_eq!(logic.auto_backups().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_trash_is_backed_up_before_it_is_purged() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("database.sqlite");
+        let dal = SqliteDal::new_with_custom_path(db_path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let mut logic = Logic::new(dal).unwrap();
+        let backup_dir = dir.path().join("backups");
+        fs::create_dir(&backup_dir).unwrap();
+        logic.backup_dir = Some(backup_dir);
+        logic.config.trash_retention_days = 0;
+
+        let ls = logic.dal.insert_command(command("ls")).await.unwrap();
+        let pwd = logic.dal.insert_command(command("pwd")).await.unwrap();
+
+        // Nothing has expired yet, so there is nothing to back up
+        logic.delete_command(ls).await.unwrap();
+        assert!(logic.auto_backups().unwrap().is_empty());
+
+        // Deleting a command purges the commands that have expired since
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        logic.delete_command(pwd).await.unwrap();
+        assert_eq!(logic.auto_backups().unwrap().len(), 1);
+        assert_eq!(logic.list_trash().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_succeeds_when_expired_trash_cannot_be_purged() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("database.sqlite");
+        let dal = SqliteDal::new_with_custom_path(db_path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let mut logic = Logic::new(dal).unwrap();
+        // The backup before the purge fails, as the directory does not exist
+        logic.backup_dir = Some(dir.path().join("backups"));
+        logic.config.trash_retention_days = 0;
+
+        let ls = logic.dal.insert_command(command("ls")).await.unwrap();
+        let pwd = logic.dal.insert_command(command("pwd")).await.unwrap();
+        logic.delete_command(ls).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        assert!(logic.delete_command(pwd).await.is_ok());
+        assert_eq!(logic.list_trash().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_restore_backup() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("database.sqlite");
+        let dal = SqliteDal::new_with_custom_path(db_path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let mut logic = Logic::new(dal).unwrap();
+        let backup_dir = dir.path().join("backups");
+        fs::create_dir(&backup_dir).unwrap();
+        logic.backup_dir = Some(backup_dir);
+
+        logic.dal.insert_command(command("ls")).await.unwrap();
+        let backup = logic.backup(None).await.unwrap();
+        logic.dal.insert_command(command("pwd")).await.unwrap();
+
+        logic.restore_backup(&backup).await.unwrap();
+        let commands = logic
+            .dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await
+            .unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].internal_command.command, "ls");
+
+        // The commands from before the restore were backed up automatically
+        assert_eq!(logic.auto_backups().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_has_no_backups() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        assert!(matches!(
+            logic.backup(None).await,
+            Err(BackupError::NoBackupDirectory)
+        ));
+        assert!(logic.auto_backup().await.is_ok());
+    }
+}
@@ -0,0 +1,267 @@
+//! # Blocking
+//!
+//! A synchronous facade over [`Logic`] for callers that do not run inside an
+//! async runtime, such as the CLI. Every call is run to completion on a single
+//! runtime that is created once and shared by all calls.
+//!
+//! The facade dereferences to [`Logic`], so its synchronous methods and the
+//! config can be used directly.
+
+use data::models::{Command, CommandPage, CommandQuery, CommandRevision, CommandUsage};
+use data::models::{InternalCommand, InternalCommandUsage};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::backup::{BackupError, RestoreError};
+use crate::command::{
+    AddCommandError, AddCommandOutcome, CommandUsageHistoryError, DeleteCommandError,
+    GetCommandError, ListCommandError, SearchCommandArgs, SearchCommandError, UpdateCommandError,
+};
+use crate::doctor::{self, DoctorError, DoctorReport};
+use crate::duplicates::{DedupeError, FindDuplicatesError};
+use crate::history::{CommandHistoryError, RevertCommandError, RevisionEntry, UndoError};
+use crate::import_export::{ExportError, ImportError, ImportSummary};
+use crate::stack::{self, StackError};
+use crate::trash::{ListTrashError, PurgeTrashError, RestoreCommandError};
+use crate::{Logic, LogicInitError};
+
+pub struct BlockingLogic {
+    logic: Logic,
+    runtime: Runtime,
+}
+
+impl BlockingLogic {
+    /// Wraps the business logic with a runtime to run its async methods on
+    pub fn new(logic: Logic) -> Result<BlockingLogic, LogicInitError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(LogicInitError::Runtime)?;
+
+        Ok(BlockingLogic { logic, runtime })
+    }
+
+    /// Creates the business logic on top of the database of the stack that is
+    /// selected by the environment or the config
+    pub fn try_default() -> Result<BlockingLogic, LogicInitError> {
+        Self::try_with_stack(None)
+    }
+
+    /// See [`Logic::try_with_stack`]
+    pub fn try_with_stack(stack: Option<String>) -> Result<BlockingLogic, LogicInitError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(LogicInitError::Runtime)?;
+        let logic = runtime.block_on(Logic::try_with_stack(stack))?;
+
+        Ok(BlockingLogic { logic, runtime })
+    }
+
+    /// See [`Logic::add_command`]
+    pub fn add_command(
+        &self,
+        command: InternalCommand,
+    ) -> Result<AddCommandOutcome, AddCommandError> {
+        self.runtime.block_on(self.logic.add_command(command))
+    }
+
+    /// See [`Logic::search_command`]
+    pub fn search_command(
+        &self,
+        params: SearchCommandArgs,
+    ) -> Result<CommandPage, SearchCommandError> {
+        self.runtime.block_on(self.logic.search_command(params))
+    }
+
+    /// See [`Logic::get_command`]
+    pub fn get_command(&self, command_id: i64) -> Result<Command, GetCommandError> {
+        self.runtime.block_on(self.logic.get_command(command_id))
+    }
+
+    /// See [`Logic::list_commands`]
+    pub fn list_commands(&self, query: CommandQuery) -> Result<CommandPage, ListCommandError> {
+        self.runtime.block_on(self.logic.list_commands(query))
+    }
+
+    /// See [`Logic::update_command_last_used_prop`]
+    pub fn update_command_last_used_prop(&self, command_id: i64) -> Result<(), UpdateCommandError> {
+        self.runtime
+            .block_on(self.logic.update_command_last_used_prop(command_id))
+    }
+
+    /// See [`Logic::record_command_usage`]
+    pub fn record_command_usage(
+        &self,
+        command_id: i64,
+        usage: InternalCommandUsage,
+    ) -> Result<(), UpdateCommandError> {
+        self.runtime
+            .block_on(self.logic.record_command_usage(command_id, usage))
+    }
+
+    /// See [`Logic::get_command_usage_history`]
+    pub fn get_command_usage_history(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandUsage>, CommandUsageHistoryError> {
+        self.runtime
+            .block_on(self.logic.get_command_usage_history(command_id))
+    }
+
+    /// See [`Logic::update_command`]
+    pub fn update_command(
+        &self,
+        command_id: i64,
+        new_command_props: InternalCommand,
+    ) -> Result<(), UpdateCommandError> {
+        self.runtime
+            .block_on(self.logic.update_command(command_id, new_command_props))
+    }
+
+    /// See [`Logic::delete_command`]
+    pub fn delete_command(&self, command_id: i64) -> Result<(), DeleteCommandError> {
+        self.runtime.block_on(self.logic.delete_command(command_id))
+    }
+
+    /// See [`Logic::list_trash`]
+    pub fn list_trash(&self) -> Result<Vec<Command>, ListTrashError> {
+        self.runtime.block_on(self.logic.list_trash())
+    }
+
+    /// See [`Logic::restore_command`]
+    pub fn restore_command(&self, command_id: i64) -> Result<(), RestoreCommandError> {
+        self.runtime
+            .block_on(self.logic.restore_command(command_id))
+    }
+
+    /// See [`Logic::purge_trash`]
+    pub fn purge_trash(&self, all: bool) -> Result<u64, PurgeTrashError> {
+        self.runtime.block_on(self.logic.purge_trash(all))
+    }
+
+    /// See [`Logic::get_command_history`]
+    pub fn get_command_history(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<RevisionEntry>, CommandHistoryError> {
+        self.runtime
+            .block_on(self.logic.get_command_history(command_id))
+    }
+
+    /// See [`Logic::revert_command`]
+    pub fn revert_command(
+        &self,
+        command_id: i64,
+        revision_id: i64,
+    ) -> Result<(), RevertCommandError> {
+        self.runtime
+            .block_on(self.logic.revert_command(command_id, revision_id))
+    }
+
+    /// See [`Logic::undo_last_change`]
+    pub fn undo_last_change(&self) -> Result<Option<CommandRevision>, UndoError> {
+        self.runtime.block_on(self.logic.undo_last_change())
+    }
+
+    /// See [`Logic::create_export_json`]
+    pub fn create_export_json(&self, export_file_path: &Path) -> Result<(), ExportError> {
+        self.runtime
+            .block_on(self.logic.create_export_json(export_file_path))
+    }
+
+    /// See [`Logic::import_data`]
+    pub fn import_data(&self, import_file_path: &Path) -> Result<ImportSummary, ImportError> {
+        self.runtime
+            .block_on(self.logic.import_data(import_file_path))
+    }
+
+    /// See [`stack::create_stack`]
+    pub fn create_stack(&self, name: &str) -> Result<(), StackError> {
+        self.runtime.block_on(stack::create_stack(name))
+    }
+
+    /// See [`Logic::find_duplicate_commands`]
+    pub fn find_duplicate_commands(&self) -> Result<Vec<Vec<Command>>, FindDuplicatesError> {
+        self.runtime.block_on(self.logic.find_duplicate_commands())
+    }
+
+    /// See [`Logic::dedupe_commands`]
+    pub fn dedupe_commands(&self) -> Result<u64, DedupeError> {
+        self.runtime.block_on(self.logic.dedupe_commands())
+    }
+
+    /// See [`Logic::backup`]
+    pub fn backup(&self, destination: Option<PathBuf>) -> Result<PathBuf, BackupError> {
+        self.runtime.block_on(self.logic.backup(destination))
+    }
+
+    /// See [`Logic::restore_backup`]
+    pub fn restore_backup(&self, backup: &Path) -> Result<(), RestoreError> {
+        self.runtime.block_on(self.logic.restore_backup(backup))
+    }
+
+    /// See [`Logic::run_doctor`]
+    pub fn run_doctor(&self, repair: bool) -> Result<DoctorReport, DoctorError> {
+        self.runtime.block_on(self.logic.run_doctor(repair))
+    }
+
+    /// See [`doctor::check_unopened_stack`]. Runs on a runtime of its own, as
+    /// there is no business logic when the stack cannot be opened
+    pub fn check_unopened_stack(
+        stack: Option<String>,
+        open_error: String,
+    ) -> Result<DoctorReport, DoctorError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(DoctorError::Runtime)?;
+        runtime.block_on(doctor::check_unopened_stack(stack, open_error))
+    }
+}
+
+impl Deref for BlockingLogic {
+    type Target = Logic;
+
+    fn deref(&self) -> &Logic {
+        &self.logic
+    }
+}
+
+impl DerefMut for BlockingLogic {
+    fn deref_mut(&mut self) -> &mut Logic {
+        &mut self.logic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data::dal::memory::MemoryStore;
+
+    use super::*;
+
+    #[test]
+    fn test_blocking_calls_share_a_runtime() {
+        let logic = BlockingLogic::new(Logic::new(MemoryStore::new()).unwrap()).unwrap();
+
+        let result = logic.add_command(InternalCommand {
+            command: "ls".to_string(),
+            tags: Vec::new(),
+            note: None,
+            favourite: false,
+        });
+        assert!(result.is_ok());
+
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .unwrap()
+            .commands;
+        assert_eq!(commands.len(), 1);
+        assert!(logic.delete_command(commands[0].id).is_ok());
+        assert_eq!(logic.list_trash().unwrap().len(), 1);
+
+        // Synchronous methods are reachable through the facade
+        assert!(logic.parse_parameters("echo @{int}".to_string()).is_ok());
+    }
+}
@@ -1,11 +1,20 @@
-use data::dal::{InsertCommandError, SelectAllCommandsError};
-use data::models::{Command, InternalCommand};
+use data::dal::SelectCommandUsageError;
+use data::dal::{InsertCommandError, SelectAllCommandsError, SelectCommandError};
+use data::models::{
+    Command, CommandPage, CommandQuery, CommandSort, CommandUsage, InternalCommand,
+    InternalCommandUsage,
+};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use itertools::interleave;
+use log::error;
 use regex::Regex;
+use serde::Serialize;
+use std::cmp::Reverse;
 use thiserror::Error;
 
+use crate::config::DuplicatePolicy;
+use crate::duplicates::merge_commands;
 use crate::parameters::ParameterError;
 use crate::Logic;
 
@@ -13,6 +22,12 @@ use crate::Logic;
 pub enum AddCommandError {
     #[error("Empty user input")]
     EmptyInput,
+    #[error("The command is a duplicate of the command with ID {0}")]
+    Duplicate(i64),
+    #[error("Failed to look for duplicates: {0}")]
+    FindDuplicate(#[from] SelectAllCommandsError),
+    #[error("Failed to merge the command into its duplicate: {0}")]
+    Merge(#[from] data::dal::UpdateCommandError),
     #[error("Failed to add command: {0}")]
     Database(#[from] InsertCommandError),
     #[error("Failed to validate parameters: {0}")]
@@ -41,33 +56,85 @@ pub enum ListCommandError {
     Database(#[from] SelectAllCommandsError),
 }
 
+#[derive(Error, Debug)]
+pub enum GetCommandError {
+    #[error("No command with ID {0}")]
+    NotFound(i64),
+    #[error("Failed to fetch command: {0}")]
+    Database(#[source] SelectCommandError),
+}
+
+impl From<SelectCommandError> for GetCommandError {
+    fn from(e: SelectCommandError) -> Self {
+        match e {
+            SelectCommandError::NotFound(id) => GetCommandError::NotFound(id),
+            e => GetCommandError::Database(e),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum DeleteCommandError {
     #[error("Failed to delete command: {0}")]
     Database(#[from] data::dal::DeleteCommandError),
 }
 
-#[derive(Debug)]
+#[derive(Error, Debug)]
+pub enum CommandUsageHistoryError {
+    #[error("Failed to fetch command usage: {0}")]
+    Database(#[from] SelectCommandUsageError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+/// What happened to an added command
+pub enum AddCommandOutcome {
+    /// The command was added with the given ID
+    Added(i64),
+    /// The command was merged into the duplicate with the given ID
+    Merged(i64),
+}
+
+#[derive(Debug, Default)]
 pub struct SearchCommandArgs {
     pub command: Option<String>,
     pub tag: Option<String>,
-    pub order_by_recently_used: bool,
-    pub favourites_only: bool,
+    /// Filters, orders and pages the matching commands
+    pub query: CommandQuery,
 }
 
 impl Logic {
-    #[tokio::main]
-    /// Handles the addition of a command
-    pub async fn add_command(&self, command: InternalCommand) -> Result<(), AddCommandError> {
+    /// Handles the addition of a command.
+    ///
+    /// If the command is a duplicate of an existing command, it is rejected,
+    /// merged into the existing command or added anyway, depending on the
+    /// duplicate policy in the config
+    pub async fn add_command(
+        &self,
+        command: InternalCommand,
+    ) -> Result<AddCommandOutcome, AddCommandError> {
         if command.command.trim().is_empty() {
             return Err(AddCommandError::EmptyInput);
         }
 
         self.parse_parameters(command.command.clone())?;
 
-        self.dal.insert_command(command).await?;
+        if self.config.duplicate_policy != DuplicatePolicy::KeepBoth {
+            if let Some(existing) = self.find_duplicate(&command.command).await? {
+                if self.config.duplicate_policy == DuplicatePolicy::Reject {
+                    return Err(AddCommandError::Duplicate(existing.id));
+                }
 
-        Ok(())
+                let merged = merge_commands(&existing.internal_command, &command);
+                if merged != existing.internal_command {
+                    self.dal.update_command(existing.id, merged).await?;
+                }
+                return Ok(AddCommandOutcome::Merged(existing.id));
+            }
+        }
+
+        let command_id = self.dal.insert_command(command).await?;
+
+        Ok(AddCommandOutcome::Added(command_id))
     }
 
     fn get_search_threshold(&self, search: String) -> i64 {
@@ -79,28 +146,63 @@ impl Logic {
         }
     }
 
-    #[tokio::main]
-    /// Handles the search for a command
+    /// Handles the search for a command.
+    ///
+    /// Commands are found through the full-text index, which matches the start
+    /// of any word in the command, tags or note of a command. If nothing is
+    /// found on the first page, every command that matches the query is fuzzy
+    /// matched instead so that abbreviations and typos still return results.
+    /// These are returned as a single page. Unless another sort is requested,
+    /// results that fit on a single page are ranked by how closely they fuzzy
+    /// match the search
     pub async fn search_command(
         &self,
         params: SearchCommandArgs,
-    ) -> Result<Vec<Command>, SearchCommandError> {
-        // Get all commands from the database
-        let commands = self
+    ) -> Result<CommandPage, SearchCommandError> {
+        // All commands if there is no filter
+        if params.command.is_none() && params.tag.is_none() {
+            return Ok(self.dal.query_commands(&params.query).await?);
+        }
+
+        let mut page = self
             .dal
-            .get_all_commands(params.order_by_recently_used, params.favourites_only)
+            .search_commands(
+                params.command.as_deref(),
+                params.tag.as_deref(),
+                &params.query,
+            )
             .await?;
 
-        // Filter the commands based on the search parameters using fuzzy matching
+        let first_page = params.query.cursor.is_none() && params.query.offset == 0;
+        if first_page && page.commands.is_empty() {
+            let unpaged = CommandQuery {
+                limit: None,
+                ..params.query.clone()
+            };
+            let all_commands = self.dal.query_commands(&unpaged).await?.commands;
+            page = CommandPage {
+                commands: self.fuzzy_filter(all_commands, &params),
+                next_cursor: None,
+            };
+        }
+
+        // Ranking a page on its own would mix up the order across pages
+        if params.query.sort == CommandSort::Insertion && first_page && page.next_cursor.is_none() {
+            let matcher = SkimMatcherV2::default();
+            page.commands.sort_by_cached_key(|command| {
+                Reverse(fuzzy_match_score(&matcher, command, &params))
+            });
+        }
+
+        Ok(page)
+    }
+
+    /// Filters the commands based on the search parameters using fuzzy matching
+    fn fuzzy_filter(&self, commands: Vec<Command>, params: &SearchCommandArgs) -> Vec<Command> {
         let matcher = SkimMatcherV2::default();
-        let filtered_commands: Vec<Command> = commands
+        commands
             .into_iter()
             .filter(|command| {
-                // All commands if there is no filter
-                if params.command.is_none() && params.tag.is_none() {
-                    return true;
-                }
-
                 let command_match = match &params.command {
                     Some(c) => match matcher.fuzzy_match(&command.internal_command.command, c) {
                         Some(r) => r > self.get_search_threshold(c.to_string()),
@@ -109,38 +211,35 @@ impl Logic {
                     None => false,
                 };
 
+                // A command matches the tag filter if any of its tags match
                 let tag_match = match &params.tag {
-                    Some(t) => match &command.internal_command.tag {
-                        Some(tag) => match matcher.fuzzy_match(tag, t) {
+                    Some(t) => command.internal_command.tags.iter().any(|tag| {
+                        match matcher.fuzzy_match(tag, t) {
                             Some(r) => r > self.get_search_threshold(t.to_string()),
                             None => false,
-                        },
-                        None => false,
-                    },
+                        }
+                    }),
                     None => false,
                 };
 
                 command_match || tag_match
             })
-            .collect();
+            .collect()
+    }
 
-        Ok(filtered_commands)
+    /// Handles fetching a command by its ID, whether or not it is in the trash
+    pub async fn get_command(&self, command_id: i64) -> Result<Command, GetCommandError> {
+        Ok(self.dal.get_command(command_id).await?)
     }
 
-    #[tokio::main]
-    /// Handles the listing of all commands
+    /// Handles the listing of a page of commands
     pub async fn list_commands(
         &self,
-        order_by_use: bool,
-        favourite: bool,
-    ) -> Result<Vec<Command>, ListCommandError> {
-        // Get all commands from the database
-        let commands = self.dal.get_all_commands(order_by_use, favourite).await?;
-
-        Ok(commands)
+        query: CommandQuery,
+    ) -> Result<CommandPage, ListCommandError> {
+        Ok(self.dal.query_commands(&query).await?)
     }
 
-    #[tokio::main]
     /// Handles the updating of the last used property of a command
     pub async fn update_command_last_used_prop(
         &self,
@@ -152,7 +251,27 @@ impl Logic {
         Ok(())
     }
 
-    #[tokio::main]
+    /// Handles recording a use (copy or execution) of a command. This also
+    /// updates the last used property of the command. The values of unlocked
+    /// secrets are replaced with their parameters before the use is recorded
+    pub async fn record_command_usage(
+        &self,
+        command_id: i64,
+        mut usage: InternalCommandUsage,
+    ) -> Result<(), UpdateCommandError> {
+        usage.resolved_command = self.mask_secrets(&usage.resolved_command);
+        self.dal.record_command_usage(command_id, usage).await?;
+        Ok(())
+    }
+
+    /// Handles fetching every recorded use of a command, most recent first
+    pub async fn get_command_usage_history(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<CommandUsage>, CommandUsageHistoryError> {
+        Ok(self.dal.get_command_usage(command_id).await?)
+    }
+
     /// Handles the updating of a command
     pub async fn update_command(
         &self,
@@ -172,10 +291,14 @@ impl Logic {
         Ok(())
     }
 
-    #[tokio::main]
-    /// Handles deleting a command
+    /// Handles deleting a command by moving it to the trash. Commands that have
+    /// been in the trash for longer than the retention period are purged, and
+    /// the command is still deleted if that fails
     pub async fn delete_command(&self, command_id: i64) -> Result<(), DeleteCommandError> {
         self.dal.delete_command(command_id).await?;
+        if let Err(e) = self.purge_expired_trash().await {
+            error!("Failed to purge the expired commands in the trash: {:?}", e);
+        }
 
         Ok(())
     }
@@ -187,7 +310,13 @@ impl Logic {
         blank_param_values: Vec<String>,
     ) -> Result<(String, Vec<String>), ParameterError> {
         let (non_parameter_strs, parameters) = self.parse_parameters(command)?;
-        self.populate_parameters(non_parameter_strs, parameters, blank_param_values, None)
+        self.populate_parameters(
+            non_parameter_strs,
+            parameters,
+            blank_param_values,
+            None,
+            None,
+        )
     }
 
     /// Handles the replacement of parameters for a command
@@ -210,20 +339,21 @@ impl Logic {
             .join(""))
     }
 
-    /// Numbers blank parameters in the selected command
+    /// Numbers unnamed blank parameters in the selected command, keeping their
+    /// defaults
     ///
-    /// ex. 'git commit \"@{} @{}\"' becomes 'git commit \"@{1} @{2}\"'
+    /// ex. 'git commit \"@{} @{=wip}\"' becomes 'git commit \"@{1} @{2=wip}\"'
     pub fn index_parameters_for_display(&self, command: &str) -> (Vec<String>, Vec<String>) {
-        let blank_param_regex = Regex::new(r"@\{\s*\}").unwrap();
-        let mut blank_param_num = 1;
+        let blank_param_regex = Regex::new(r"@\{\s*(?P<default>=[^}]*)?\}").unwrap();
 
         let mut indexed_blank_params = Vec::new();
         let mut other_strs = Vec::new();
         let mut last_end = 0;
 
-        for mat in blank_param_regex.find_iter(command) {
-            indexed_blank_params.push(format!("@{{{}}}", blank_param_num));
-            blank_param_num += 1;
+        for (blank_param_num, caps) in (1..).zip(blank_param_regex.captures_iter(command)) {
+            let mat = caps.get(0).unwrap();
+            let default = caps.name("default").map_or("", |default| default.as_str());
+            indexed_blank_params.push(format!("@{{{}{}}}", blank_param_num, default));
 
             other_strs.push(command[last_end..mat.start()].to_string());
             last_end = mat.end();
@@ -239,300 +369,481 @@ impl Logic {
     }
 }
 
+/// Scores how closely a command matches the search parameters. Commands that
+/// only matched through their note score 0
+fn fuzzy_match_score(
+    matcher: &SkimMatcherV2,
+    command: &Command,
+    params: &SearchCommandArgs,
+) -> i64 {
+    let command_score = params
+        .command
+        .as_ref()
+        .and_then(|c| matcher.fuzzy_match(&command.internal_command.command, c));
+    let tag_score = params.tag.as_ref().and_then(|t| {
+        command
+            .internal_command
+            .tags
+            .iter()
+            .filter_map(|tag| matcher.fuzzy_match(tag, t))
+            .max()
+    });
+
+    command_score.max(tag_score).unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{thread, time::Duration};
 
-    use data::dal::sqlite_dal::SqliteDal;
-    use tempfile::TempDir;
+    use data::dal::memory::MemoryStore;
+    use data::models::UsageAction;
+
+    use crate::secrets::{tests::TEST_KDF_PARAMS, SecretStore};
 
     use super::*;
 
-    #[test]
-    fn test_handle_add_command_success() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
-
-        let path = tmp_dir_result
-            .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+    #[tokio::test]
+    async fn test_handle_add_command_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let command = InternalCommand {
             command: "test_command".to_string(),
-            tag: None,
+            tags: Vec::new(),
             note: None,
             favourite: false,
         };
 
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
         assert!(commands.first().unwrap().internal_command == command);
     }
 
-    #[test]
-    fn test_handle_invalid_command() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
+    #[tokio::test]
+    async fn test_handle_add_duplicate_command() {
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+        logic.config.duplicate_ignore_whitespace = true;
 
-        let path = tmp_dir_result
+        let command = InternalCommand {
+            command: "git status".to_string(),
+            tags: vec!["git".to_string()],
+            note: None,
+            favourite: false,
+        };
+        let id = match logic.add_command(command.clone()).await {
+            Ok(AddCommandOutcome::Added(id)) => id,
+            result => panic!("Unexpected result: {:?}", result),
+        };
+
+        let duplicate = InternalCommand {
+            command: "git   status".to_string(),
+            tags: vec!["status".to_string()],
+            note: Some("Show changes".to_string()),
+            favourite: true,
+        };
+
+        logic.config.duplicate_policy = DuplicatePolicy::Reject;
+        assert!(matches!(
+            logic.add_command(duplicate.clone()).await,
+            Err(AddCommandError::Duplicate(existing)) if existing == id
+        ));
+
+        logic.config.duplicate_policy = DuplicatePolicy::Merge;
+        assert_eq!(
+            logic.add_command(duplicate.clone()).await.unwrap(),
+            AddCommandOutcome::Merged(id)
+        );
+        let merged = logic.get_command(id).await.unwrap().internal_command;
+        assert_eq!(merged.command, "git status");
+        assert_eq!(merged.tags, vec!["git".to_string(), "status".to_string()]);
+        assert_eq!(merged.note.as_deref(), Some("Show changes"));
+        assert!(merged.favourite);
+
+        logic.config.duplicate_policy = DuplicatePolicy::KeepBoth;
+        assert!(matches!(
+            logic.add_command(duplicate).await,
+            Ok(AddCommandOutcome::Added(_))
+        ));
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
             .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+            .commands;
+        assert_eq!(commands.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_handle_get_command() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let command = InternalCommand {
+            command: "test_command".to_string(),
+            tags: vec!["test".to_string()],
+            note: None,
+            favourite: false,
+        };
+        let result = logic.add_command(command.clone()).await;
+        assert!(result.is_ok());
+
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        let found = logic.get_command(commands[0].id).await.unwrap();
+        assert_eq!(found.internal_command, command);
+
+        // Commands in the trash can still be fetched
+        assert!(logic.delete_command(found.id).await.is_ok());
+        let found = logic.get_command(found.id).await.unwrap();
+        assert!(found.deleted_at.is_some());
+
+        assert!(matches!(
+            logic.get_command(found.id + 1).await,
+            Err(GetCommandError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_handle_invalid_command() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let mut invalid_command = InternalCommand {
-            command: "@{bad}".to_string(),
-            tag: None,
+            command: "@{bad name}".to_string(),
+            tags: Vec::new(),
             note: None,
             favourite: false,
         };
 
-        let result = logic.add_command(invalid_command.clone());
+        let result = logic.add_command(invalid_command.clone()).await;
         assert!(result.is_err());
 
         // Now a valid command
         invalid_command.command = "asdf".to_string();
 
-        let result = logic.add_command(invalid_command.clone());
+        let result = logic.add_command(invalid_command.clone()).await;
         assert!(result.is_ok());
 
         // Now an invalid command
-        invalid_command.command = "@{what}".to_string();
+        invalid_command.command = "@{what?}".to_string();
 
-        let list_commands_result = logic.list_commands(false, false);
-        let commands = list_commands_result.unwrap();
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
 
-        let result = logic.update_command(commands[0].id, invalid_command.clone());
+        let result = logic
+            .update_command(commands[0].id, invalid_command.clone())
+            .await;
         assert!(result.is_err());
 
         // Now a valid command
         invalid_command.command = "@{int}".to_string();
 
-        let result = logic.update_command(commands[0].id, invalid_command.clone());
+        let result = logic
+            .update_command(commands[0].id, invalid_command.clone())
+            .await;
         assert!(result.is_ok());
     }
 
-    #[test]
-    fn test_handle_update_command_success() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
-
-        let path = tmp_dir_result
-            .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+    #[tokio::test]
+    async fn test_handle_update_command_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let command = InternalCommand {
             command: "test_command".to_string(),
-            tag: None,
+            tags: Vec::new(),
             note: None,
             favourite: false,
         };
 
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
         assert!(commands.first().unwrap().internal_command == command);
 
         let new_command = InternalCommand {
             command: "new_test_command".to_string(),
-            tag: Some("green".to_string()),
+            tags: vec!["green".to_string()],
             note: Some("new note".to_string()),
             favourite: true,
         };
 
-        let update_command_result =
-            logic.update_command(commands.first().unwrap().id, new_command.clone());
+        let update_command_result = logic
+            .update_command(commands.first().unwrap().id, new_command.clone())
+            .await;
         assert!(update_command_result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
 
         assert!(commands.first().unwrap().internal_command == new_command);
     }
 
-    #[test]
-    fn test_handle_search_command_success() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
-
-        let path = tmp_dir_result
-            .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+    #[tokio::test]
+    async fn test_handle_search_command_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let command = InternalCommand {
             command: "abcd".to_string(),
-            tag: Some("green".to_string()),
+            tags: vec!["green".to_string()],
             note: None,
             favourite: false,
         };
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
         let command = InternalCommand {
             command: "abce".to_string(),
-            tag: Some("greet".to_string()),
+            tags: vec!["greet".to_string()],
             note: None,
             favourite: false,
         };
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
         // search by tag starts with gree
-        let search_command_result = logic.search_command(SearchCommandArgs {
-            command: None,
-            tag: Some("gree".to_string()),
-            order_by_recently_used: false,
-            favourites_only: false,
-        });
+        let search_command_result = logic
+            .search_command(SearchCommandArgs {
+                command: None,
+                tag: Some("gree".to_string()),
+                query: CommandQuery::default(),
+            })
+            .await;
         assert!(search_command_result.is_ok());
-        let commands = search_command_result.unwrap();
+        let commands = search_command_result.unwrap().commands;
         assert!(commands.len() == 2);
 
         // search by tag starts with green
-        let search_command_result = logic.search_command(SearchCommandArgs {
-            command: None,
-            tag: Some("green".to_string()),
-            order_by_recently_used: false,
-            favourites_only: false,
-        });
+        let search_command_result = logic
+            .search_command(SearchCommandArgs {
+                command: None,
+                tag: Some("green".to_string()),
+                query: CommandQuery::default(),
+            })
+            .await;
         assert!(search_command_result.is_ok());
-        let commands = search_command_result.unwrap();
+        let commands = search_command_result.unwrap().commands;
         assert!(commands.len() == 1);
 
         // search by command starts with abc
-        let search_command_result = logic.search_command(SearchCommandArgs {
-            command: Some("abc".to_string()),
-            tag: None,
-            order_by_recently_used: false,
-            favourites_only: false,
-        });
+        let search_command_result = logic
+            .search_command(SearchCommandArgs {
+                command: Some("abc".to_string()),
+                tag: None,
+                query: CommandQuery::default(),
+            })
+            .await;
         assert!(search_command_result.is_ok());
-        let commands = search_command_result.unwrap();
+        let commands = search_command_result.unwrap().commands;
         assert!(commands.len() == 2);
 
         // search by command starts with abcd
-        let search_command_result = logic.search_command(SearchCommandArgs {
-            command: Some("abcd".to_string()),
-            tag: None,
-            order_by_recently_used: false,
-            favourites_only: false,
-        });
+        let search_command_result = logic
+            .search_command(SearchCommandArgs {
+                command: Some("abcd".to_string()),
+                tag: None,
+                query: CommandQuery::default(),
+            })
+            .await;
         assert!(search_command_result.is_ok());
-        let commands = search_command_result.unwrap();
+        let commands = search_command_result.unwrap().commands;
         assert!(commands.len() == 1);
 
         // No filter should return all commands
-        let search_command_result = logic.search_command(SearchCommandArgs {
-            command: None,
-            tag: None,
-            order_by_recently_used: false,
-            favourites_only: false,
-        });
+        let search_command_result = logic
+            .search_command(SearchCommandArgs {
+                command: None,
+                tag: None,
+                query: CommandQuery::default(),
+            })
+            .await;
         assert!(search_command_result.is_ok());
-        let commands = search_command_result.unwrap();
+        let commands = search_command_result.unwrap().commands;
         assert!(commands.len() == 2);
     }
 
-    #[test]
-    fn test_handle_delete_command_success() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
+    #[tokio::test]
+    async fn test_handle_search_command_multiple_tags() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let command = InternalCommand {
+            command: "kubectl rollout restart".to_string(),
+            tags: vec!["k8s/deploy".to_string(), "oncall/runbook".to_string()],
+            note: None,
+            favourite: false,
+        };
+        let result = logic.add_command(command.clone()).await;
+        assert!(result.is_ok());
+
+        // The command can be found through either of its tags
+        for tag in ["k8s/deploy", "oncall/runbook"] {
+            let search_command_result = logic
+                .search_command(SearchCommandArgs {
+                    command: None,
+                    tag: Some(tag.to_string()),
+                    query: CommandQuery::default(),
+                })
+                .await;
+            assert!(search_command_result.is_ok());
+            let commands = search_command_result.unwrap().commands;
+            assert!(commands.len() == 1);
+            assert!(commands.first().unwrap().internal_command == command);
+        }
+
+        // Removing a tag keeps the others
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        let mut new_command = command.clone();
+        new_command.tags = vec!["oncall/runbook".to_string()];
+        let result = logic
+            .update_command(commands.first().unwrap().id, new_command.clone())
+            .await;
+        assert!(result.is_ok());
 
-        let path = tmp_dir_result
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
             .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+            .commands;
+        assert!(commands.first().unwrap().internal_command == new_command);
+    }
+
+    #[tokio::test]
+    async fn test_handle_search_command_full_text() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let commands = [
+            (
+                "docker system prune -af",
+                Some("Reclaim disk space"),
+                "docker",
+            ),
+            ("git log --oneline --graph", None, "git/history"),
+            (
+                "du -sh * | sort -h",
+                Some("Find what is using disk space"),
+                "files",
+            ),
+        ];
+        for (command, note, tag) in commands {
+            let result = logic
+                .add_command(InternalCommand {
+                    command: command.to_string(),
+                    tags: vec![tag.to_string()],
+                    note: note.map(str::to_string),
+                    favourite: false,
+                })
+                .await;
+            assert!(result.is_ok());
+        }
+
+        let search = async |command: Option<&str>, tag: Option<&str>| -> Vec<String> {
+            logic
+                .search_command(SearchCommandArgs {
+                    command: command.map(str::to_string),
+                    tag: tag.map(str::to_string),
+                    query: CommandQuery::default(),
+                })
+                .await
+                .unwrap()
+                .commands
+                .into_iter()
+                .map(|c| c.internal_command.command)
+                .collect()
+        };
+
+        // Notes are searched
+        assert_eq!(
+            search(Some("disk space"), None).await,
+            vec!["docker system prune -af", "du -sh * | sort -h"]
+        );
+
+        // Words are matched by prefix in any order
+        assert_eq!(
+            search(Some("graph onel"), None).await,
+            vec!["git log --oneline --graph"]
+        );
+
+        // Tags are matched when searching by tag
+        assert_eq!(
+            search(None, Some("git")).await,
+            vec!["git log --oneline --graph"]
+        );
+
+        // FTS5 syntax in the search is taken literally
+        assert!(search(Some("\"prune\" OR NEAR(*"), None).await.is_empty());
+
+        // Falls back to fuzzy matching when no word matches
+        assert_eq!(
+            search(Some("dckr"), None).await,
+            vec!["docker system prune -af"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_delete_command_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let command = InternalCommand {
             command: "test_command".to_string(),
-            tag: None,
+            tags: Vec::new(),
             note: None,
             favourite: false,
         };
 
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
         let command_id = commands.first().unwrap().id;
 
-        let delete_command_result = logic.delete_command(command_id);
+        let delete_command_result = logic.delete_command(command_id).await;
         assert!(delete_command_result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.is_empty());
 
         // delete can be called multiple times
-        let delete_command_result = logic.delete_command(command_id);
+        let delete_command_result = logic.delete_command(command_id).await;
         assert!(delete_command_result.is_err());
     }
 
-    #[test]
-    fn test_handle_update_command_last_used_prop_success() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
-
-        let path = tmp_dir_result
-            .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+    #[tokio::test]
+    async fn test_handle_update_command_last_used_prop_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let command = InternalCommand {
             command: "test_command".to_string(),
-            tag: None,
+            tags: Vec::new(),
             note: None,
             favourite: false,
         };
 
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
         let command_id = commands.first().unwrap().id;
         let last_used = commands.first().unwrap().last_used;
@@ -540,46 +851,228 @@ mod tests {
         // a second gone past so the timestamp will update
         thread::sleep(Duration::from_millis(1000));
 
-        let update_last_used_result = logic.update_command_last_used_prop(command_id);
+        let update_last_used_result = logic.update_command_last_used_prop(command_id).await;
         assert!(update_last_used_result.is_ok());
 
         // Verify that the last used property has been updated
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
 
         // last_used has been updated
         assert!(commands.first().unwrap().last_used > last_used);
     }
 
-    #[test]
-    fn test_handle_generate_param_success() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
+    #[tokio::test]
+    async fn test_handle_record_command_usage_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let command = InternalCommand {
+            command: "echo @{}".to_string(),
+            tags: Vec::new(),
+            note: None,
+            favourite: false,
+        };
+
+        let result = logic.add_command(command.clone()).await;
+        assert!(result.is_ok());
 
-        let path = tmp_dir_result
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
             .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+            .commands;
+        let command_id = commands.first().unwrap().id;
+
+        let copy_usage = InternalCommandUsage {
+            action: UsageAction::Copy,
+            working_directory: None,
+            resolved_command: "echo hello".to_string(),
+        };
+        let execute_usage = InternalCommandUsage {
+            action: UsageAction::Execute,
+            working_directory: Some("/tmp".to_string()),
+            resolved_command: "echo world".to_string(),
+        };
+
+        let result = logic
+            .record_command_usage(command_id, copy_usage.clone())
+            .await;
+        assert!(result.is_ok());
+        let result = logic
+            .record_command_usage(command_id, execute_usage.clone())
+            .await;
+        assert!(result.is_ok());
+
+        // Every use is kept, most recent first
+        let history_result = logic.get_command_usage_history(command_id).await;
+        assert!(history_result.is_ok());
+        let history = history_result.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].internal_usage, execute_usage);
+        assert_eq!(history[1].internal_usage, copy_usage);
+        assert!(history.iter().all(|usage| usage.command_id == command_id));
+
+        // Recording a use of a command that does not exist fails
+        let result = logic.record_command_usage(command_id + 1, copy_usage).await;
+        assert!(result.is_err());
+
+        // The history is kept while the command is in the trash
+        assert!(logic.delete_command(command_id).await.is_ok());
+        let history = logic.get_command_usage_history(command_id).await.unwrap();
+        assert_eq!(history.len(), 2);
+
+        // The history is removed once the command is purged
+        assert!(logic.purge_trash(true).await.is_ok());
+        let history = logic.get_command_usage_history(command_id).await.unwrap();
+        assert!(history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_record_command_usage_masks_secrets() {
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SecretStore::open_with_params(
+            &dir.path().join("secrets.json"),
+            "hunter2",
+            TEST_KDF_PARAMS,
+        )
+        .unwrap();
+        store.set("api_token", "s3cr3t").unwrap();
+        logic.secrets = Some(store);
+
+        let command = "curl -H 'Token: @{secret:api_token}' example.com";
+        let command_id = match logic
+            .add_command(InternalCommand {
+                command: command.to_string(),
+                tags: Vec::new(),
+                note: None,
+                favourite: false,
+            })
+            .await
+        {
+            Ok(AddCommandOutcome::Added(id)) => id,
+            other => panic!("Unexpected result: {:?}", other),
+        };
+
+        let (resolved_command, _) = logic
+            .generate_parameters(command.to_string(), vec![])
+            .unwrap();
+        assert_eq!(resolved_command, "curl -H 'Token: s3cr3t' example.com");
+        let result = logic
+            .record_command_usage(
+                command_id,
+                InternalCommandUsage {
+                    action: UsageAction::Copy,
+                    working_directory: None,
+                    resolved_command,
+                },
+            )
+            .await;
+        assert!(result.is_ok());
+
+        // The value of the secret is never written to the history
+        let history = logic.get_command_usage_history(command_id).await.unwrap();
+        assert_eq!(history[0].internal_usage.resolved_command, command);
+    }
+
+    #[tokio::test]
+    async fn test_handle_list_commands_by_frecency() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        for command in ["never_used", "used_once", "used_daily"] {
+            let result = logic
+                .add_command(InternalCommand {
+                    command: command.to_string(),
+                    tags: Vec::new(),
+                    note: None,
+                    favourite: false,
+                })
+                .await;
+            assert!(result.is_ok());
+        }
+
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        let id_of = |command: &str| {
+            commands
+                .iter()
+                .find(|c| c.internal_command.command == command)
+                .unwrap()
+                .id
+        };
+        let usage = InternalCommandUsage {
+            action: UsageAction::Copy,
+            working_directory: None,
+            resolved_command: String::new(),
+        };
+
+        for _ in 0..3 {
+            let result = logic
+                .record_command_usage(id_of("used_daily"), usage.clone())
+                .await;
+            assert!(result.is_ok());
+        }
+        let result = logic
+            .record_command_usage(id_of("used_once"), usage.clone())
+            .await;
+        assert!(result.is_ok());
+
+        // Frequently used commands come first, even if another command was used more recently
+        let commands = logic
+            .list_commands(CommandQuery {
+                sort: CommandSort::Frecency,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .commands;
+        let order: Vec<&str> = commands
+            .iter()
+            .map(|c| c.internal_command.command.as_str())
+            .collect();
+        assert_eq!(order, vec!["used_daily", "used_once", "never_used"]);
+
+        // Searching keeps the requested order
+        let commands = logic
+            .search_command(SearchCommandArgs {
+                command: Some("used".to_string()),
+                tag: None,
+                query: CommandQuery {
+                    sort: CommandSort::Frecency,
+                    ..Default::default()
+                },
+            })
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(
+            commands.first().unwrap().internal_command.command,
+            "used_daily"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_generate_param_success() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let command = InternalCommand {
             command: "echo @{int}".to_string(),
-            tag: None,
+            tags: Vec::new(),
             note: None,
             favourite: false,
         };
 
-        let result = logic.add_command(command.clone());
+        let result = logic.add_command(command.clone()).await;
         assert!(result.is_ok());
 
-        let list_commands_result = logic.list_commands(false, false);
+        let list_commands_result = logic.list_commands(CommandQuery::default()).await;
         assert!(list_commands_result.is_ok());
-        let commands = list_commands_result.unwrap();
+        let commands = list_commands_result.unwrap().commands;
         assert!(commands.len() == 1);
 
         let generated_param_result = logic.generate_parameters(
@@ -594,17 +1087,7 @@ mod tests {
 
     #[test]
     fn test_replace_parameters() {
-        let tmp_dir_result = TempDir::new();
-        assert!(tmp_dir_result.is_ok());
-
-        let path = tmp_dir_result
-            .unwrap()
-            .path()
-            .to_string_lossy()
-            .into_owned();
-        let dal = SqliteDal::new_with_custom_path(path);
-        assert!(dal.is_ok());
-        let logic = Logic::new(dal.unwrap()).unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.replace_parameters("echo @{} @{int}".to_string(), vec!["a".to_string()]);
         assert!(ret.is_err());
@@ -616,4 +1099,18 @@ mod tests {
         assert!(ret.is_ok());
         assert_eq!("echo a b", ret.unwrap());
     }
+
+    #[test]
+    fn test_index_parameters_for_display() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let (other_strs, indexed_blank_params) =
+            logic.index_parameters_for_display("git commit -m \"@{} @{=wip}\" @{branch}");
+        assert_eq!(
+            interleave(other_strs, indexed_blank_params)
+                .collect::<Vec<String>>()
+                .join(""),
+            "git commit -m \"@{1} @{2=wip}\" @{branch}"
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use data::dal::sqlite::DEFAULT_STACK;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::{fs, io};
@@ -28,7 +29,7 @@ pub enum ConfigReadError {
 }
 
 /// Configuration structure for reading/writing JSON
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct Config {
     pub cli_print_style: CliPrintStyle,
@@ -39,6 +40,16 @@ pub struct Config {
     pub param_int_range_max: i32,
     pub application_theme: ApplicationTheme,
     pub default_terminal: UiDefaultTerminal,
+    /// Number of days deleted commands are kept in the trash for
+    pub trash_retention_days: u32,
+    /// What happens when an added or imported command is a duplicate
+    pub duplicate_policy: DuplicatePolicy,
+    /// Whether commands that only differ in whitespace are duplicates
+    pub duplicate_ignore_whitespace: bool,
+    /// The stack that is used when no other stack is requested
+    pub active_stack: String,
+    /// Number of automatic backups that are kept, where 0 disables them
+    pub auto_backup_count: u32,
 }
 
 impl Default for Config {
@@ -52,6 +63,11 @@ impl Default for Config {
             param_int_range_max: 10,
             application_theme: ApplicationTheme::default(),
             default_terminal: UiDefaultTerminal::default(),
+            trash_retention_days: 30,
+            duplicate_policy: DuplicatePolicy::default(),
+            duplicate_ignore_whitespace: false,
+            active_stack: DEFAULT_STACK.to_string(),
+            auto_backup_count: 5,
         }
     }
 }
@@ -78,6 +94,19 @@ pub enum UiDefaultTerminal {
     Terminal,
 }
 
+/// How a command that is a duplicate of an existing command is handled
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Copy, PartialEq)]
+pub enum DuplicatePolicy {
+    /// The duplicate is not added, and is skipped when importing
+    Reject,
+    /// The tags, note and favourite status of the duplicate are merged into
+    /// the existing command
+    Merge,
+    /// The duplicate is added as a separate command
+    #[default]
+    KeepBoth,
+}
+
 impl Config {
     pub fn read() -> Result<Config, ConfigReadError> {
         let config_path =
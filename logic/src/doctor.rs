@@ -0,0 +1,170 @@
+//! # Doctor
+//!
+//! Checks the health of the database of a stack: whether the file is
+//! corrupted, whether it has orphaned or unreadable rows and whether every
+//! command can still be parsed with the current config. Problems that can be
+//! repaired without losing commands are repaired on request, after the
+//! database has been backed up automatically.
+
+use data::dal::maintenance::check_database_file;
+use data::dal::sqlite::{stack_db_path, SqliteDbConnectionError};
+use data::dal::{MaintenanceError, SelectAllCommandsError};
+use data::models::{IntegrityReport, RepairSummary};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::backup::BackupError;
+use crate::config::Config;
+use crate::stack::resolve_stack;
+use crate::Logic;
+
+#[derive(Error, Debug)]
+pub enum DoctorError {
+    #[error("Failed to check the database: {0}")]
+    Maintenance(#[from] MaintenanceError),
+    #[error("Failed to fetch the commands: {0}")]
+    Database(#[from] SelectAllCommandsError),
+    #[error("Failed to back up the database before repairing it: {0}")]
+    Backup(#[from] BackupError),
+    #[error("Failed to locate the database: {0}")]
+    DbPath(#[from] SqliteDbConnectionError),
+    #[error("Failed to start the async runtime: {0}")]
+    Runtime(#[source] std::io::Error),
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// The health of the database of a stack
+pub struct DoctorReport {
+    pub stack: String,
+    /// Why the database could not be opened by the application, in which case
+    /// only SQLite's integrity check was run
+    pub open_error: Option<String>,
+    #[serde(flatten)]
+    pub integrity: IntegrityReport,
+    /// Commands whose parameters cannot be parsed with the current config
+    pub unparsable_commands: Vec<UnparsableCommand>,
+    /// What was repaired, if a repair was requested
+    pub repair: Option<RepairSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A command whose parameters cannot be parsed
+pub struct UnparsableCommand {
+    pub id: i64,
+    pub command: String,
+    pub error: String,
+}
+
+impl DoctorReport {
+    /// The number of problems that were found. Orphaned rows count once per
+    /// table
+    pub fn num_problems(&self) -> usize {
+        self.open_error.iter().count()
+            + self.integrity.corruption.len()
+            + self.integrity.orphaned_rows.len()
+            + self.integrity.invalid_rows.len()
+            + (self.integrity.unindexed_commands > 0) as usize
+            + self.unparsable_commands.len()
+    }
+}
+
+impl Logic {
+    /// Handles checking the health of the database of the stack, after
+    /// repairing it if requested
+    pub async fn run_doctor(&self, repair: bool) -> Result<DoctorReport, DoctorError> {
+        let repair = if repair {
+            self.auto_backup().await?;
+            Some(self.dal.repair().await?)
+        } else {
+            None
+        };
+
+        let unparsable_commands = self
+            .dal
+            .get_command_texts()
+            .await?
+            .into_iter()
+            .filter_map(|(id, command)| {
+                self.parse_parameters(command.clone())
+                    .err()
+                    .map(|e| UnparsableCommand {
+                        id,
+                        command,
+                        error: e.to_string(),
+                    })
+            })
+            .collect();
+
+        Ok(DoctorReport {
+            stack: self.stack.clone(),
+            open_error: None,
+            integrity: self.dal.check_integrity().await?,
+            unparsable_commands,
+            repair,
+        })
+    }
+}
+
+/// Runs SQLite's integrity check on the database of a stack that the
+/// application failed to open, which is the requested stack or the stack
+/// selected by the environment or the config
+pub async fn check_unopened_stack(
+    stack: Option<String>,
+    open_error: String,
+) -> Result<DoctorReport, DoctorError> {
+    // The config may be what failed to open
+    let config = Config::read().unwrap_or_default();
+    let stack = resolve_stack(stack, &config);
+    let corruption = check_database_file(&stack_db_path(&stack)?).await?;
+
+    Ok(DoctorReport {
+        stack,
+        open_error: Some(open_error),
+        integrity: IntegrityReport {
+            corruption,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use data::dal::memory::MemoryStore;
+    use data::models::InternalCommand;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_doctor_reports_unparsable_commands() {
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+        logic.config.param_int_range_min = 0;
+        logic.config.param_int_range_max = 100;
+
+        for command in ["ls @{int}", "echo @{int[5, 50]}"] {
+            logic
+                .add_command(InternalCommand {
+                    command: command.to_string(),
+                    tags: Vec::new(),
+                    note: None,
+                    favourite: false,
+                })
+                .await
+                .unwrap();
+        }
+
+        let report = logic.run_doctor(false).await.unwrap();
+        assert_eq!(report.num_problems(), 0);
+        assert!(report.repair.is_none());
+
+        // A command can stop parsing when the config changes
+        logic.config.param_int_range_min = 50;
+        logic.config.param_int_range_max = 10;
+
+        let report = logic.run_doctor(true).await.unwrap();
+        assert_eq!(report.num_problems(), 1);
+        assert_eq!(report.unparsable_commands[0].id, 1);
+        assert_eq!(report.unparsable_commands[0].command, "ls @{int}");
+        assert!(report.repair.is_some());
+    }
+}
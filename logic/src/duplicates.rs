@@ -0,0 +1,238 @@
+use data::dal::{DeleteCommandError, SelectAllCommandsError, SqlTxError, UpdateCommandError};
+use data::models::{Command, CommandSort, InternalCommand};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::backup::BackupError;
+use crate::Logic;
+
+#[derive(Error, Debug)]
+pub enum FindDuplicatesError {
+    #[error("Failed to fetch commands: {0}")]
+    Database(#[from] SelectAllCommandsError),
+}
+
+#[derive(Error, Debug)]
+pub enum DedupeError {
+    #[error("Failed to find duplicates: {0}")]
+    Find(#[from] FindDuplicatesError),
+    #[error("Failed to merge command: {0}")]
+    Update(#[from] UpdateCommandError),
+    #[error("Failed to delete duplicate: {0}")]
+    Delete(#[from] DeleteCommandError),
+    #[error("Failed to run the dedupe transaction: {0}")]
+    Tx(#[from] SqlTxError),
+    #[error("Failed to back up the database before merging duplicates: {0}")]
+    Backup(#[from] BackupError),
+}
+
+impl Logic {
+    /// The text that two commands share if they are duplicates of each other.
+    /// Commands are duplicates if their text is identical or, if the config
+    /// allows it, if they only differ in whitespace
+    pub(crate) fn duplicate_key(&self, command: &str) -> String {
+        if self.config.duplicate_ignore_whitespace {
+            command.split_whitespace().collect::<Vec<&str>>().join(" ")
+        } else {
+            command.to_string()
+        }
+    }
+
+    /// Returns the oldest command outside of the trash that the given command
+    /// is a duplicate of. Identical commands are looked up by their text, and
+    /// every command is only compared when whitespace is ignored
+    pub(crate) async fn find_duplicate(
+        &self,
+        command: &str,
+    ) -> Result<Option<Command>, SelectAllCommandsError> {
+        if !self.config.duplicate_ignore_whitespace {
+            return self.dal.get_command_by_text(command).await;
+        }
+
+        let key = self.duplicate_key(command);
+        Ok(self
+            .dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await?
+            .into_iter()
+            .find(|c| self.duplicate_key(&c.internal_command.command) == key))
+    }
+
+    /// Handles finding the groups of commands outside of the trash that are
+    /// duplicates of each other. The most recently used command of a group
+    /// comes first, and is the one the others are merged into
+    pub async fn find_duplicate_commands(&self) -> Result<Vec<Vec<Command>>, FindDuplicatesError> {
+        let commands = self
+            .dal
+            .get_all_commands(CommandSort::RecentlyUsed, false)
+            .await?;
+
+        let mut groups: Vec<Vec<Command>> = Vec::new();
+        let mut group_of_key: HashMap<String, usize> = HashMap::new();
+        for command in commands {
+            let key = self.duplicate_key(&command.internal_command.command);
+            match group_of_key.get(&key) {
+                Some(&i) => groups[i].push(command),
+                None => {
+                    group_of_key.insert(key, groups.len());
+                    groups.push(vec![command]);
+                }
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+
+        Ok(groups)
+    }
+
+    /// Handles merging every group of duplicate commands into the most recently
+    /// used command of the group, which keeps its last used time. The others
+    /// are moved to the trash. Returns the number of commands moved to the trash.
+    ///
+    /// The database is backed up automatically first, if there are duplicates
+    pub async fn dedupe_commands(&self) -> Result<u64, DedupeError> {
+        let groups = self.find_duplicate_commands().await?;
+        if !groups.is_empty() {
+            self.auto_backup().await?;
+        }
+
+        let mut tx = self.dal.begin().await?;
+        let mut num_removed = 0;
+        for group in groups {
+            let Some((kept, duplicates)) = group.split_first() else {
+                continue;
+            };
+
+            let merged = duplicates
+                .iter()
+                .fold(kept.internal_command.clone(), |merged, duplicate| {
+                    merge_commands(&merged, &duplicate.internal_command)
+                });
+            if merged != kept.internal_command {
+                tx.update_command(kept.id, merged).await?;
+            }
+
+            for duplicate in duplicates {
+                tx.delete_command(duplicate.id).await?;
+                num_removed += 1;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(num_removed)
+    }
+}
+
+/// Merges a duplicate into a command. The text of the command is kept, the
+/// tags of both are combined, a differing note is appended and the command is
+/// a favourite if either of them is
+pub fn merge_commands(command: &InternalCommand, duplicate: &InternalCommand) -> InternalCommand {
+    let mut merged = command.clone();
+
+    for tag in &duplicate.tags {
+        if !merged.tags.contains(tag) {
+            merged.tags.push(tag.clone());
+        }
+    }
+
+    merged.note = match (&command.note, &duplicate.note) {
+        (Some(note), Some(other)) if !note.contains(other.as_str()) => {
+            Some(format!("{}\n{}", note, other))
+        }
+        (None, other) => other.clone(),
+        (note, _) => note.clone(),
+    };
+
+    merged.favourite |= duplicate.favourite;
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use data::dal::memory::MemoryStore;
+    use data::models::CommandQuery;
+
+    use super::*;
+    use crate::config::DuplicatePolicy;
+
+    fn command(command: &str, tags: &[&str], note: Option<&str>) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            note: note.map(str::to_string),
+            favourite: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_commands() {
+        let mut duplicate = command("ls", &["files", "list"], Some("List files"));
+        duplicate.favourite = true;
+
+        let merged = merge_commands(&command("ls", &["files"], None), &duplicate);
+        assert_eq!(merged.tags, vec!["files".to_string(), "list".to_string()]);
+        assert_eq!(merged.note.as_deref(), Some("List files"));
+        assert!(merged.favourite);
+
+        let merged = merge_commands(&command("ls", &[], Some("Lists")), &duplicate);
+        assert_eq!(merged.note.as_deref(), Some("Lists\nList files"));
+        let merged = merge_commands(&merged, &duplicate);
+        assert_eq!(merged.note.as_deref(), Some("Lists\nList files"));
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_commands() {
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+        logic.config.duplicate_policy = DuplicatePolicy::KeepBoth;
+        logic.config.duplicate_ignore_whitespace = true;
+
+        for added in [
+            command("git  status", &["git"], None),
+            command("ls", &[], None),
+            command("git status", &["status"], Some("Show changes")),
+        ] {
+            assert!(logic.add_command(added).await.is_ok());
+        }
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        let mut favourite = commands[0].internal_command.clone();
+        favourite.favourite = true;
+        assert!(logic
+            .update_command(commands[0].id, favourite)
+            .await
+            .is_ok());
+
+        // The last command to be used is the one that is kept
+        thread::sleep(Duration::from_millis(1000));
+        assert!(logic
+            .update_command_last_used_prop(commands[2].id)
+            .await
+            .is_ok());
+
+        let groups = logic.find_duplicate_commands().await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 2);
+
+        assert_eq!(logic.dedupe_commands().await.unwrap(), 1);
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(commands.len(), 2);
+        let kept = &commands[1].internal_command;
+        assert_eq!(kept.command, "git status");
+        assert_eq!(kept.tags, vec!["status".to_string(), "git".to_string()]);
+        assert_eq!(kept.note.as_deref(), Some("Show changes"));
+        assert!(kept.favourite);
+        assert_eq!(logic.list_trash().await.unwrap().len(), 1);
+
+        assert!(logic.find_duplicate_commands().await.unwrap().is_empty());
+        assert_eq!(logic.dedupe_commands().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,271 @@
+use data::dal::{SelectCommandRevisionError, UndoChangeError};
+use data::models::{CommandRevision, InternalCommand, RevisionAction};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::command::{GetCommandError, UpdateCommandError};
+use crate::Logic;
+
+#[derive(Error, Debug)]
+pub enum CommandHistoryError {
+    #[error("Failed to fetch command revisions: {0}")]
+    Revision(#[from] SelectCommandRevisionError),
+    #[error("Failed to fetch command: {0}")]
+    Command(#[from] GetCommandError),
+}
+
+#[derive(Error, Debug)]
+pub enum RevertCommandError {
+    #[error("Failed to fetch command revisions: {0}")]
+    Revision(#[from] SelectCommandRevisionError),
+    #[error("Command has no revision with ID {0}")]
+    RevisionNotFound(i64),
+    #[error("Failed to revert command: {0}")]
+    Update(#[from] UpdateCommandError),
+}
+
+#[derive(Error, Debug)]
+pub enum UndoError {
+    #[error("Failed to undo the last change: {0}")]
+    Database(#[from] UndoChangeError),
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+/// A property of a command that was changed
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A change made to a command along with the properties it changed
+pub struct RevisionEntry {
+    pub revision: CommandRevision,
+    /// Only updates change properties, so this is empty for other changes
+    pub changes: Vec<FieldChange>,
+}
+
+impl Logic {
+    /// Handles fetching every change made to a command, most recent first
+    pub async fn get_command_history(
+        &self,
+        command_id: i64,
+    ) -> Result<Vec<RevisionEntry>, CommandHistoryError> {
+        let revisions = self.dal.get_command_revisions(command_id).await?;
+        let current = match self.get_command(command_id).await {
+            Ok(command) => Some(command.internal_command),
+            Err(GetCommandError::NotFound(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        // Every revision stores the properties from before its change, so the
+        // properties after a change are the ones stored by the next change
+        let mut after = current;
+        let mut entries = Vec::with_capacity(revisions.len());
+        for revision in revisions {
+            let changes = match (&revision.action, &after) {
+                (RevisionAction::Update, Some(after)) => {
+                    command_changes(&revision.internal_command, after)
+                }
+                _ => Vec::new(),
+            };
+            after = Some(revision.internal_command.clone());
+            entries.push(RevisionEntry { revision, changes });
+        }
+
+        Ok(entries)
+    }
+
+    /// Handles restoring the properties a command had before one of its
+    /// revisions. The revert is validated and recorded like any other update,
+    /// so it can be undone
+    pub async fn revert_command(
+        &self,
+        command_id: i64,
+        revision_id: i64,
+    ) -> Result<(), RevertCommandError> {
+        let revision = self
+            .dal
+            .get_command_revisions(command_id)
+            .await?
+            .into_iter()
+            .find(|r| r.id == revision_id)
+            .ok_or(RevertCommandError::RevisionNotFound(revision_id))?;
+
+        self.update_command(command_id, revision.internal_command)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handles undoing the most recent addition, update, deletion or restoration
+    /// of a command. Returns the undone change, or `None` if there was nothing
+    /// to undo
+    pub async fn undo_last_change(&self) -> Result<Option<CommandRevision>, UndoError> {
+        Ok(self.dal.undo_last_change().await?)
+    }
+}
+
+/// Lists the properties that differ between two versions of a command
+pub fn command_changes(before: &InternalCommand, after: &InternalCommand) -> Vec<FieldChange> {
+    let favourite = |c: &InternalCommand| if c.favourite { "Yes" } else { "No" }.to_string();
+    let fields = [
+        ("command", before.command.clone(), after.command.clone()),
+        ("tags", before.tags.join(", "), after.tags.join(", ")),
+        (
+            "note",
+            before.note.clone().unwrap_or_default(),
+            after.note.clone().unwrap_or_default(),
+        ),
+        ("favourite", favourite(before), favourite(after)),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange {
+            field,
+            before,
+            after,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use data::dal::memory::MemoryStore;
+    use data::models::CommandQuery;
+
+    use super::*;
+
+    fn command(command: &str, tags: &[&str]) -> InternalCommand {
+        InternalCommand {
+            command: command.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            note: None,
+            favourite: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_revert_and_undo() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        assert!(logic.add_command(command("ls", &["files"])).await.is_ok());
+        let command_id = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands[0]
+            .id;
+        assert!(logic
+            .update_command(command_id, command("ls -la", &["files"]))
+            .await
+            .is_ok());
+        assert!(logic
+            .update_command(command_id, command("ls -la", &["files", "list"]))
+            .await
+            .is_ok());
+
+        // Every update lists the properties it changed
+        let history = logic.get_command_history(command_id).await.unwrap();
+        let actions: Vec<RevisionAction> = history.iter().map(|e| e.revision.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                RevisionAction::Update,
+                RevisionAction::Update,
+                RevisionAction::Add
+            ]
+        );
+        assert_eq!(
+            history[0].changes,
+            vec![FieldChange {
+                field: "tags",
+                before: "files".to_string(),
+                after: "files, list".to_string(),
+            }]
+        );
+        assert_eq!(history[1].changes[0].field, "command");
+        assert!(history[2].changes.is_empty());
+
+        // Reverting to the first version is itself undoable
+        let first_update = history[1].revision.id;
+        assert!(logic.revert_command(command_id, first_update).await.is_ok());
+        let current = &logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands[0];
+        assert_eq!(current.internal_command, command("ls", &["files"]));
+        assert!(matches!(
+            logic.revert_command(command_id, -1).await,
+            Err(RevertCommandError::RevisionNotFound(-1))
+        ));
+
+        let undone = logic.undo_last_change().await.unwrap().unwrap();
+        assert_eq!(undone.action, RevisionAction::Update);
+        let current = &logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands[0];
+        assert_eq!(
+            current.internal_command,
+            command("ls -la", &["files", "list"])
+        );
+
+        // Undoing a deletion restores the command
+        assert!(logic.delete_command(command_id).await.is_ok());
+        let undone = logic.undo_last_change().await.unwrap().unwrap();
+        assert_eq!(undone.action, RevisionAction::Delete);
+        assert_eq!(
+            logic
+                .list_commands(CommandQuery::default())
+                .await
+                .unwrap()
+                .commands
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revert_is_validated() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        assert!(logic.add_command(command("ls", &[])).await.is_ok());
+        let command_id = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands[0]
+            .id;
+        // A version that does not parse, e.g. one saved by an older version
+        // of CmdStack
+        logic
+            .dal
+            .update_command(command_id, command("echo @{bad name}", &[]))
+            .await
+            .unwrap();
+        assert!(logic
+            .update_command(command_id, command("ls -a", &[]))
+            .await
+            .is_ok());
+
+        let history = logic.get_command_history(command_id).await.unwrap();
+        assert!(matches!(
+            logic
+                .revert_command(command_id, history[0].revision.id)
+                .await,
+            Err(RevertCommandError::Update(UpdateCommandError::Parameter(_)))
+        ));
+        let current = &logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands[0];
+        assert_eq!(current.internal_command, command("ls -a", &[]));
+    }
+}
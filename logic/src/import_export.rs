@@ -1,20 +1,34 @@
 use data::{
-    dal::{InsertCommandError, SelectAllCommandsError},
-    models::InternalCommand,
+    dal::{InsertCommandError, SelectAllCommandsError, SqlTxError, UpdateCommandError},
+    models::{CommandIdentity, CommandSort, InternalCommand},
 };
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::{
+    collections::{HashMap, HashSet},
     fs::{self},
     path::Path,
 };
 use thiserror::Error;
+use uuid::Uuid;
 
-use crate::Logic;
+use crate::{
+    backup::BackupError, config::DuplicatePolicy, duplicates::merge_commands,
+    parameters::ParameterError, Logic,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ImportExportFormat {
-    commands: Vec<InternalCommand>,
+    commands: Vec<ExportedCommand>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedCommand {
+    #[serde(flatten)]
+    internal_command: InternalCommand,
+    /// Exports created before commands had UUIDs do not have an identity
+    #[serde(flatten)]
+    identity: Option<CommandIdentity>,
 }
 
 #[derive(Error, Debug)]
@@ -33,19 +47,50 @@ pub enum ImportError {
     Serialize(#[from] serde_json::Error),
     #[error("Failed to read commands from file: {0}")]
     Read(String),
+    #[error("Command {0} in the file is empty")]
+    EmptyCommand(usize),
+    #[error("Command {0} in the file has invalid parameters: {1}")]
+    Parameter(usize, #[source] ParameterError),
+    #[error("Failed to look for duplicates: {0}")]
+    FindDuplicates(#[from] SelectAllCommandsError),
     #[error("Failed to insert commands to the database: {0}")]
     Database(#[from] InsertCommandError),
+    #[error("Failed to merge a command into its duplicate: {0}")]
+    Merge(#[from] UpdateCommandError),
+    #[error("Failed to run the import transaction: {0}")]
+    Tx(#[from] SqlTxError),
+    #[error("Failed to back up the database before importing: {0}")]
+    Backup(#[from] BackupError),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+/// The number of commands in an import file by what happened to them
+pub struct ImportSummary {
+    pub imported: u64,
+    /// Duplicates that were merged into another command
+    pub merged: u64,
+    /// Duplicates that were rejected
+    pub skipped: u64,
 }
 
 impl Logic {
-    #[tokio::main]
     /// Handle the export request by writing all data in the database to the requested JSON file
     pub async fn create_export_json(&self, export_file_path: &Path) -> Result<(), ExportError> {
-        let commands = self.dal.get_all_commands(false, false).await?;
+        let commands = self
+            .dal
+            .get_all_commands(CommandSort::Insertion, false)
+            .await?;
         let export_data = ImportExportFormat {
             commands: commands
                 .into_iter()
-                .map(|command| command.internal_command)
+                .map(|command| ExportedCommand {
+                    identity: Some(CommandIdentity {
+                        uuid: command.uuid,
+                        created_at: command.created_at,
+                        updated_at: command.updated_at,
+                    }),
+                    internal_command: command.internal_command,
+                })
                 .collect(),
         };
         let json_string = serde_json::to_string(&export_data)?;
@@ -54,18 +99,312 @@ impl Logic {
         Ok(())
     }
 
-    #[tokio::main]
-    /// Handle the import request by importing all data in the given JSON file
-    pub async fn import_data(&self, import_file_path: &Path) -> Result<u64, ImportError> {
+    /// Handle the import request by importing all data in the given JSON file.
+    ///
+    /// Every command is validated before anything is written, and the commands
+    /// are inserted in a single transaction, so either all of them are imported
+    /// or none are. Commands that are duplicates of an existing command, or of
+    /// a command earlier in the file, are handled by the duplicate policy.
+    /// Commands with the same UUID are always merged, whatever the policy.
+    ///
+    /// Imported commands keep their UUID and creation and update times, unless
+    /// the UUID is already taken, in which case they are given a new one.
+    ///
+    /// The database is backed up automatically before anything is written
+    pub async fn import_data(&self, import_file_path: &Path) -> Result<ImportSummary, ImportError> {
         let json_string =
             fs::read_to_string(import_file_path).map_err(|e| ImportError::Read(e.to_string()))?;
         let import_data: ImportExportFormat = serde_json::from_str(&json_string)?;
 
-        let num_commands = self
+        // Commands are numbered from 1 in errors, in the order they appear in the file
+        for (i, exported) in import_data.commands.iter().enumerate() {
+            let command = &exported.internal_command;
+            if command.command.trim().is_empty() {
+                return Err(ImportError::EmptyCommand(i + 1));
+            }
+            self.parse_parameters(command.command.clone())
+                .map_err(|e| ImportError::Parameter(i + 1, e))?;
+        }
+
+        let policy = self.config.duplicate_policy;
+        let existing = self
             .dal
-            .insert_mulitple_commands(import_data.commands)
+            .get_all_commands(CommandSort::Insertion, false)
             .await?;
+        // Commands in the trash are not duplicates, but keep their UUID
+        let trashed_uuids: HashSet<Uuid> = self
+            .dal
+            .get_trashed_commands()
+            .await?
+            .iter()
+            .map(|command| command.uuid)
+            .collect();
+
+        // The commands as they will be after the import, along with whether
+        // they are new or have to be updated
+        let mut summary = ImportSummary::default();
+        let mut entries: Vec<ImportEntry> = Vec::new();
+        let mut entry_of_key: HashMap<String, usize> = HashMap::new();
+        let mut entry_of_uuid: HashMap<Uuid, usize> = HashMap::new();
+        for command in existing {
+            entry_of_key
+                .entry(self.duplicate_key(&command.internal_command.command))
+                .or_insert(entries.len());
+            entry_of_uuid.insert(command.uuid, entries.len());
+            entries.push(ImportEntry {
+                id: Some(command.id),
+                command: command.internal_command,
+                identity: None,
+                changed: false,
+            });
+        }
+
+        for exported in import_data.commands {
+            let command = exported.internal_command;
+            let key = self.duplicate_key(&command.command);
+            let same_uuid = exported
+                .identity
+                .and_then(|identity| entry_of_uuid.get(&identity.uuid).copied());
+            let duplicate = match policy {
+                _ if same_uuid.is_some() => same_uuid,
+                DuplicatePolicy::KeepBoth => None,
+                _ => entry_of_key.get(&key).copied(),
+            };
+
+            match duplicate {
+                Some(_) if same_uuid.is_none() && policy == DuplicatePolicy::Reject => {
+                    summary.skipped += 1
+                }
+                Some(i) => {
+                    let merged = merge_commands(&entries[i].command, &command);
+                    if merged != entries[i].command {
+                        entries[i].command = merged;
+                        entries[i].changed = true;
+                    }
+                    summary.merged += 1;
+                }
+                None => {
+                    let identity = exported
+                        .identity
+                        .filter(|identity| !trashed_uuids.contains(&identity.uuid));
+                    if let Some(identity) = identity {
+                        entry_of_uuid.insert(identity.uuid, entries.len());
+                    }
+                    entry_of_key.entry(key).or_insert(entries.len());
+                    entries.push(ImportEntry {
+                        id: None,
+                        command,
+                        identity,
+                        changed: true,
+                    });
+                }
+            }
+        }
+
+        self.auto_backup().await?;
+        let mut tx = self.dal.begin().await?;
+        for entry in entries.into_iter().filter(|entry| entry.changed) {
+            match (entry.id, entry.identity) {
+                (Some(id), _) => tx.update_command(id, entry.command).await?,
+                (None, Some(identity)) => {
+                    tx.insert_command_with_identity(entry.command, identity)
+                        .await?;
+                    summary.imported += 1;
+                }
+                (None, None) => {
+                    tx.insert_command(entry.command).await?;
+                    summary.imported += 1;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        Ok(summary)
+    }
+}
+
+/// A command that is written by an import
+struct ImportEntry {
+    /// The ID of the command, unless it is new
+    id: Option<i64>,
+    command: InternalCommand,
+    /// The identity a new command keeps from the import file
+    identity: Option<CommandIdentity>,
+    changed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use data::dal::memory::MemoryStore;
+    use data::models::CommandQuery;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn write_import_file(dir: &TempDir, commands: &[&str]) -> std::path::PathBuf {
+        let import_data = ImportExportFormat {
+            commands: commands
+                .iter()
+                .map(|command| ExportedCommand {
+                    internal_command: InternalCommand {
+                        command: command.to_string(),
+                        tags: Vec::new(),
+                        note: None,
+                        favourite: false,
+                    },
+                    identity: None,
+                })
+                .collect(),
+        };
+        let path = dir.path().join("import.json");
+        fs::write(&path, serde_json::to_string(&import_data).unwrap()).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_import_data_success() {
+        let dir = TempDir::new().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let path = write_import_file(&dir, &["ls", "echo @{int}", "cat @{}"]);
+        let result = logic.import_data(&path).await;
+        assert!(matches!(
+            result,
+            Ok(ImportSummary {
+                imported: 3,
+                merged: 0,
+                skipped: 0
+            })
+        ));
+
+        let export_path = dir.path().join("export.json");
+        assert!(logic.create_export_json(&export_path).await.is_ok());
+        let export: ImportExportFormat =
+            serde_json::from_str(&fs::read_to_string(export_path).unwrap()).unwrap();
+        let commands: Vec<String> = export
+            .commands
+            .into_iter()
+            .map(|c| c.internal_command.command)
+            .collect();
+        assert_eq!(commands, vec!["ls", "echo @{int}", "cat @{}"]);
+    }
+
+    #[tokio::test]
+    async fn test_import_keeps_identity() {
+        let dir = TempDir::new().unwrap();
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+        logic.config.duplicate_policy = DuplicatePolicy::Merge;
+        logic.config.duplicate_ignore_whitespace = false;
+
+        let export = r#"{"commands": [
+            {"command": "ls", "tags": ["files"], "note": null, "favourite": false,
+             "uuid": "6f7c6a4e-1c52-4a5e-9a8e-2b0b7c1d9f10", "created_at": 100, "updated_at": 200},
+            {"command": "pwd", "tag": "dirs", "note": null, "favourite": false}
+        ]}"#;
+        let path = dir.path().join("import.json");
+        fs::write(&path, export).unwrap();
+        assert_eq!(logic.import_data(&path).await.unwrap().imported, 2);
+
+        let uuid = Uuid::parse_str("6f7c6a4e-1c52-4a5e-9a8e-2b0b7c1d9f10").unwrap();
+        let imported = logic.dal.get_command_by_uuid(uuid).await.unwrap();
+        assert_eq!(imported.internal_command.command, "ls");
+        assert_eq!((imported.created_at, imported.updated_at), (100, 200));
+
+        // A command with the same UUID is a duplicate, even if it was changed
+        let export = export.replace(r#""command": "ls""#, r#""command": "ls -la""#);
+        fs::write(&path, export).unwrap();
+        let summary = logic.import_data(&path).await.unwrap();
+        assert_eq!((summary.imported, summary.merged), (0, 2));
+
+        // Exporting and importing into another stack keeps the identities
+        let export_path = dir.path().join("export.json");
+        assert!(logic.create_export_json(&export_path).await.is_ok());
+        let mut other = Logic::new(MemoryStore::new()).unwrap();
+        other.config.duplicate_policy = DuplicatePolicy::KeepBoth;
+        assert_eq!(other.import_data(&export_path).await.unwrap().imported, 2);
+        let commands = other
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        let originals = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        for (command, original) in commands.iter().zip(&originals) {
+            assert_eq!(command.uuid, original.uuid);
+            assert_eq!(command.created_at, original.created_at);
+        }
+
+        // Commands with the same UUID are merged even when duplicates are
+        // kept, so importing the same export again does not double the stack
+        let summary = other.import_data(&export_path).await.unwrap();
+        assert_eq!((summary.imported, summary.merged), (0, 2));
+        other.config.duplicate_policy = DuplicatePolicy::Reject;
+        let summary = other.import_data(&export_path).await.unwrap();
+        assert_eq!((summary.merged, summary.skipped), (2, 0));
+        let commands = other
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(commands.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_data_is_atomic() {
+        let dir = TempDir::new().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        // The invalid parameter is found before any command is written
+        let path = write_import_file(&dir, &["ls", "pwd", "echo @{bad name}"]);
+        let result = logic.import_data(&path).await;
+        assert!(matches!(result, Err(ImportError::Parameter(3, _))));
+
+        let path = write_import_file(&dir, &["ls", "  "]);
+        let result = logic.import_data(&path).await;
+        assert!(matches!(result, Err(ImportError::EmptyCommand(2))));
+
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert!(commands.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_data_duplicates() {
+        let dir = TempDir::new().unwrap();
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+        logic.config.duplicate_ignore_whitespace = false;
+
+        // Importing the same file twice does not double the stack
+        logic.config.duplicate_policy = DuplicatePolicy::Merge;
+        let path = write_import_file(&dir, &["ls", "pwd", "ls"]);
+        let summary = logic.import_data(&path).await.unwrap();
+        assert_eq!((summary.imported, summary.merged), (2, 1));
+        let summary = logic.import_data(&path).await.unwrap();
+        assert_eq!((summary.imported, summary.merged), (0, 3));
+
+        logic.config.duplicate_policy = DuplicatePolicy::Reject;
+        let path = write_import_file(&dir, &["ls", "whoami"]);
+        let summary = logic.import_data(&path).await.unwrap();
+        assert_eq!((summary.imported, summary.skipped), (1, 1));
+
+        logic.config.duplicate_policy = DuplicatePolicy::KeepBoth;
+        let summary = logic.import_data(&path).await.unwrap();
+        assert_eq!(summary.imported, 2);
 
-        Ok(num_commands)
+        let commands: Vec<String> = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands
+            .into_iter()
+            .map(|c| c.internal_command.command)
+            .collect();
+        assert_eq!(commands, vec!["ls", "pwd", "whoami", "ls", "whoami"]);
     }
 }
@@ -1,14 +1,31 @@
 //! # Logic
 //!
-//! This crate handles the business logic of the application
+//! This crate handles the business logic of the application.
+//!
+//! Every method that accesses the command store is async and can be called
+//! from any runtime. Callers without a runtime can use
+//! [`blocking::BlockingLogic`] instead.
 
+pub mod backup;
+pub mod blocking;
 pub mod command;
 pub mod config;
+pub mod doctor;
+pub mod duplicates;
+pub mod history;
 pub mod import_export;
 pub mod parameters;
+pub mod secrets;
+pub mod stack;
+pub mod trash;
 
 use config::{Config, ConfigReadError};
-use data::dal::{sqlite::SqliteDbConnectionError, sqlite_dal::SqliteDal};
+use data::dal::sqlite::{stack_backup_dir, SqliteDbConnectionError};
+use data::dal::store::{ChangeWatcher, CommandStore};
+use data::dal::{sqlite_dal::SqliteDal, WatchChangesError};
+use secrets::SecretStore;
+use stack::{check_stack_exists, resolve_stack, StackError};
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -17,25 +34,62 @@ pub enum LogicInitError {
     Database(#[from] SqliteDbConnectionError),
     #[error("Failed to read from config file: {0}")]
     Config(#[from] ConfigReadError),
+    #[error("Failed to open the stack: {0}")]
+    Stack(#[from] StackError),
+    #[error("Failed to start the async runtime: {0}")]
+    Runtime(#[source] std::io::Error),
 }
 
 pub struct Logic {
-    dal: SqliteDal,
+    dal: Box<dyn CommandStore>,
     pub config: Config,
+    stack: String,
+    /// The secrets, once they have been unlocked
+    secrets: Option<SecretStore>,
+    /// The directory that backups of the stack are kept in, if the store can
+    /// be backed up
+    backup_dir: Option<PathBuf>,
 }
 
 impl Logic {
-    pub fn new(dal: SqliteDal) -> Result<Logic, LogicInitError> {
+    /// Creates the business logic on top of any command store
+    pub fn new(dal: impl CommandStore + 'static) -> Result<Logic, LogicInitError> {
+        let config = Config::read()?;
         Ok(Logic {
-            dal,
-            config: Config::read()?,
+            dal: Box::new(dal),
+            stack: config.active_stack.clone(),
+            config,
+            secrets: None,
+            backup_dir: None,
         })
     }
 
-    pub fn try_default() -> Result<Logic, LogicInitError> {
+    /// Creates the business logic on top of the database of the stack that is
+    /// selected by the environment or the config
+    pub async fn try_default() -> Result<Logic, LogicInitError> {
+        Self::try_with_stack(None).await
+    }
+
+    /// Creates the business logic on top of the database of the requested
+    /// stack, or the stack selected by the environment or the config if none
+    /// is requested
+    pub async fn try_with_stack(stack: Option<String>) -> Result<Logic, LogicInitError> {
+        let config = Config::read()?;
+        let stack = resolve_stack(stack, &config);
+        check_stack_exists(&stack)?;
+
         Ok(Self {
-            dal: SqliteDal::new()?,
-            config: Config::read()?,
+            dal: Box::new(SqliteDal::new_for_stack(&stack).await?),
+            config,
+            backup_dir: Some(stack_backup_dir(&stack)?),
+            stack,
+            secrets: None,
         })
     }
+
+    /// Starts watching the stack for changes to its commands, such as those
+    /// made by another instance of the application
+    pub async fn watch_changes(&self) -> Result<Box<dyn ChangeWatcher>, WatchChangesError> {
+        self.dal.watch_changes().await
+    }
 }
@@ -4,25 +4,43 @@ use super::ParameterError;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlankParameter;
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BlankParameter {
+    /// The value that is used when nothing is filled in, e.g. `main` for
+    /// `@{=main}`
+    pub default: Option<String>,
+}
 
 impl FromStr for BlankParameter {
     type Err = ParameterError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let blank_param_regex = r"@\{\s*\}";
+        let blank_param_regex = r"^@\{\s*(?:=(?P<default>[^}]*))?\}$";
         let re = Regex::new(blank_param_regex).map_err(|e| {
             ParameterError::InvalidRegex(blank_param_regex.to_string(), e.to_string())
         })?;
 
-        if re.is_match(s) {
-            return Ok(BlankParameter);
+        if let Some(caps) = re.captures(s) {
+            let default = caps
+                .name("default")
+                .map(|default| default.as_str().to_string())
+                .filter(|default| !default.is_empty());
+            return Ok(BlankParameter { default });
         }
         Err(ParameterError::InvalidParameter)
     }
 }
 
+impl BlankParameter {
+    /// Returns the value that was filled in, or the default if nothing was
+    pub fn value_or_default(&self, value: String) -> String {
+        match &self.default {
+            Some(default) if value.is_empty() => default.clone(),
+            _ => value,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parameters::blank::BlankParameter;
@@ -32,15 +50,42 @@ mod tests {
     fn test_from_str() {
         let ret = BlankParameter::from_str("@{}");
         assert!(ret.is_ok());
+        assert_eq!(ret.unwrap().default, None);
 
         let ret = BlankParameter::from_str("@{         }");
         assert!(ret.is_ok());
     }
 
+    #[test]
+    fn test_from_str_default() {
+        let ret = BlankParameter::from_str("@{=main}");
+        assert_eq!(ret.unwrap().default, Some("main".to_string()));
+
+        // The default is kept as it is written
+        let ret = BlankParameter::from_str("@{=us-east-1 }");
+        assert_eq!(ret.unwrap().default, Some("us-east-1 ".to_string()));
+
+        let ret = BlankParameter::from_str("@{=}");
+        assert_eq!(ret.unwrap().default, None);
+    }
+
+    #[test]
+    fn test_value_or_default() {
+        let param = BlankParameter::from_str("@{=main}").unwrap();
+        assert_eq!(param.value_or_default(String::new()), "main");
+        assert_eq!(param.value_or_default("dev".to_string()), "dev");
+
+        let param = BlankParameter::from_str("@{}").unwrap();
+        assert_eq!(param.value_or_default(String::new()), "");
+    }
+
     #[test]
     fn test_from_str_errors() {
         // Wrong type
         let ret = BlankParameter::from_str("@{int}");
         assert!(ret.is_err());
+
+        let ret = BlankParameter::from_str("@{main}");
+        assert!(ret.is_err());
     }
 }
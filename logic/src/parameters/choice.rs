@@ -0,0 +1,87 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::{populator::RandomNumberGenerator, GenerateRandomValues, ParameterError};
+
+/// A parameter that is one of a fixed set of values, e.g.
+/// `@{choice[dev,staging,prod]}`. The value is chosen when the command is
+/// used, or at random if no choice is made
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChoiceParameter {
+    pub choices: Vec<String>,
+}
+
+impl FromStr for ChoiceParameter {
+    type Err = ParameterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let choice_param_regex = r"^@\{choice\[(?P<choices>[^\]]*)\]\}$";
+        let re = Regex::new(choice_param_regex).map_err(|e| {
+            ParameterError::InvalidRegex(choice_param_regex.to_string(), e.to_string())
+        })?;
+
+        if let Some(caps) = re.captures(s) {
+            let choices: Vec<String> = caps["choices"]
+                .split(',')
+                .map(|choice| choice.trim().to_string())
+                .collect();
+            if choices.iter().any(|choice| choice.is_empty()) {
+                return Err(ParameterError::InvalidParameter);
+            }
+
+            return Ok(ChoiceParameter { choices });
+        }
+        Err(ParameterError::InvalidParameter)
+    }
+}
+
+impl GenerateRandomValues for ChoiceParameter {
+    fn generate_random_value(&self, rng: &mut dyn RandomNumberGenerator) -> String {
+        let idx = rng.generate_range(0, (self.choices.len() - 1) as i32);
+        self.choices[idx as usize].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::populator::tests::MockRng;
+
+    #[test]
+    fn test_from_str() {
+        let ret = ChoiceParameter::from_str("@{choice[dev,staging,prod]}");
+        assert_eq!(ret.unwrap().choices, vec!["dev", "staging", "prod"]);
+
+        let ret = ChoiceParameter::from_str("@{choice[json, yaml]}");
+        assert_eq!(ret.unwrap().choices, vec!["json", "yaml"]);
+
+        let ret = ChoiceParameter::from_str("@{choice[only]}");
+        assert_eq!(ret.unwrap().choices, vec!["only"]);
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        for s in [
+            "@{choice}",
+            "@{choice[]}",
+            "@{choice[dev,,prod]}",
+            "@{choice[dev,]}",
+            "@{choice[dev}",
+            "@{int[0, 1]}",
+        ] {
+            assert!(ChoiceParameter::from_str(s).is_err());
+        }
+    }
+
+    #[test]
+    fn test_generate_random_value() {
+        let param = ChoiceParameter::from_str("@{choice[dev,staging,prod]}").unwrap();
+        let mut rng = MockRng::new(vec![0, 1, 2, 3]);
+
+        let values: Vec<String> = (0..4)
+            .map(|_| param.generate_random_value(&mut rng))
+            .collect();
+        assert_eq!(values, vec!["dev", "staging", "prod", "dev"]);
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::Duration;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::{populator::Clock, GenerateTimeValues, ParameterError};
+
+/// The format of `@{now}`, e.g. `2024-05-01T09:30:00`
+const NOW_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+/// The format of `@{date}`, e.g. `2024-05-01`
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A parameter that is the current time in the local time zone, moved by an
+/// offset and formatted with a `strftime` format, e.g. `@{now[%H:%M]}` or
+/// `@{date[-1d,%F]}` for the date yesterday
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DateParameter {
+    /// The number of seconds the time is moved by, which is negative for times
+    /// in the past
+    pub offset: i64,
+    pub format: String,
+}
+
+impl FromStr for DateParameter {
+    type Err = ParameterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let date_param_regex = r"^@\{(?P<type>now|date)(?:\[(?P<args>[^\]]*)\])?\}$";
+        let re = Regex::new(date_param_regex).map_err(|e| {
+            ParameterError::InvalidRegex(date_param_regex.to_string(), e.to_string())
+        })?;
+
+        if let Some(caps) = re.captures(s) {
+            let default_format = if &caps["type"] == "now" {
+                NOW_FORMAT
+            } else {
+                DATE_FORMAT
+            };
+
+            // The arguments are an offset, a format, or an offset and a format.
+            // A format is told apart from an offset by its `%`s, so a comma
+            // only ends the offset when there are none before it
+            let (offset, format) = match caps.name("args").map(|args| args.as_str()) {
+                None => (0, default_format),
+                Some(args) => match args.split_once(',') {
+                    Some((offset, format)) if !offset.contains('%') => {
+                        (parse_offset(offset)?, format.trim())
+                    }
+                    _ if args.contains('%') => (0, args.trim()),
+                    _ => (parse_offset(args)?, default_format),
+                },
+            };
+
+            if format.is_empty() {
+                return Err(ParameterError::InvalidParameter);
+            }
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return Err(ParameterError::InvalidDateFormat(format.to_string()));
+            }
+
+            return Ok(DateParameter {
+                offset,
+                format: format.to_string(),
+            });
+        }
+        Err(ParameterError::InvalidParameter)
+    }
+}
+
+/// Parses an offset such as `-1d` or `+2h` into a number of seconds. The units
+/// are `s`, `m`, `h`, `d` and `w`
+fn parse_offset(s: &str) -> Result<i64, ParameterError> {
+    let offset_regex = r"^(?P<sign>[+-])?(?P<amount>\d{1,6})(?P<unit>[smhdw])$";
+    let re = Regex::new(offset_regex)
+        .map_err(|e| ParameterError::InvalidRegex(offset_regex.to_string(), e.to_string()))?;
+
+    let caps = re
+        .captures(s.trim())
+        .ok_or(ParameterError::InvalidParameter)?;
+    let amount: i64 = caps["amount"].parse().map_err(|_| {
+        ParameterError::TypeParsing(
+            std::any::type_name::<i64>().to_string(),
+            caps["amount"].to_owned(),
+        )
+    })?;
+    let unit = match &caps["unit"] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => 7 * 24 * 60 * 60,
+    };
+    let sign = if caps.name("sign").map(|sign| sign.as_str()) == Some("-") {
+        -1
+    } else {
+        1
+    };
+
+    Ok(sign * amount * unit)
+}
+
+impl GenerateTimeValues for DateParameter {
+    fn generate_time_value(&self, clock: &dyn Clock) -> String {
+        let time = clock.now() + Duration::seconds(self.offset);
+        time.format(&self.format).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::populator::tests::MockClock;
+
+    #[test]
+    fn test_from_str() {
+        let ret = DateParameter::from_str("@{now}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (0, NOW_FORMAT));
+
+        let ret = DateParameter::from_str("@{now[%Y-%m-%d]}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (0, "%Y-%m-%d"));
+
+        let ret = DateParameter::from_str("@{date}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (0, DATE_FORMAT));
+
+        let ret = DateParameter::from_str("@{date[-1d]}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (-86400, DATE_FORMAT));
+
+        let ret = DateParameter::from_str("@{date[-1d,%F]}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (-86400, "%F"));
+
+        let ret = DateParameter::from_str("@{now[+2h, %H:%M]}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (7200, "%H:%M"));
+
+        // Only the first comma separates the offset from the format
+        let ret = DateParameter::from_str("@{date[1w,%b %d, %Y]}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (604800, "%b %d, %Y"));
+
+        // A comma in a format without an offset is part of the format
+        let ret = DateParameter::from_str("@{now[%b %d, %Y]}").unwrap();
+        assert_eq!((ret.offset, ret.format.as_str()), (0, "%b %d, %Y"));
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        for s in [
+            "@{date[]}",
+            "@{date[-1d,]}",
+            "@{date[-1y]}",
+            "@{date[-1y,%F]}",
+            "@{date[1.5d,%F]}",
+            "@{today}",
+            "@{timestamp}",
+        ] {
+            assert!(matches!(
+                DateParameter::from_str(s),
+                Err(ParameterError::InvalidParameter)
+            ));
+        }
+
+        for s in ["@{now[%Q]}", "@{date[-1d,%Y-%]}"] {
+            assert!(matches!(
+                DateParameter::from_str(s),
+                Err(ParameterError::InvalidDateFormat(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_generate_time_value() {
+        // 2024-05-01T09:30:15Z
+        let clock = MockClock::new(1714555815);
+
+        let param = DateParameter::from_str("@{now}").unwrap();
+        assert_eq!(param.generate_time_value(&clock), "2024-05-01T09:30:15");
+
+        let param = DateParameter::from_str("@{date[-1d]}").unwrap();
+        assert_eq!(param.generate_time_value(&clock), "2024-04-30");
+
+        let param = DateParameter::from_str("@{now[+90m,%H:%M]}").unwrap();
+        assert_eq!(param.generate_time_value(&clock), "11:00");
+
+        let param = DateParameter::from_str("@{date[%Y%m%d]}").unwrap();
+        assert_eq!(param.generate_time_value(&clock), "20240501");
+    }
+}
@@ -1,14 +1,20 @@
-use populator::RandomNumberGenerator;
+use populator::{Clock, RandomNumberGenerator};
 use thiserror::Error;
 
-use crate::config::Config;
+use crate::{config::Config, secrets::SecretError};
 
 pub mod blank;
 pub mod boolean;
+pub mod choice;
+pub mod date;
 pub mod int;
+pub mod named;
 pub mod parser;
 pub mod populator;
+pub mod regex;
+pub mod secret;
 pub mod string;
+pub mod timestamp;
 pub mod uuid;
 
 pub trait FromStrWithConfig: Sized {
@@ -19,6 +25,10 @@ pub trait GenerateRandomValues {
     fn generate_random_value(&self, rng: &mut dyn RandomNumberGenerator) -> String;
 }
 
+pub trait GenerateTimeValues {
+    fn generate_time_value(&self, clock: &dyn Clock) -> String;
+}
+
 #[derive(Error, Debug)]
 pub enum ParameterError {
     #[error("Failed to parse into {0} type from string value {1}")]
@@ -29,8 +39,16 @@ pub enum ParameterError {
     InvalidRegex(String, String),
     #[error("Invalid (min,max): ({0},{1}) provided")]
     InvalidMinMax(String, String),
+    #[error("Invalid date format: {0}")]
+    InvalidDateFormat(String),
     #[error("Failed to fill in blank parameters: {0} value(s) provided, needed {1} value(s)")]
     MissingBlankParamValues(String, String),
     #[error("Failed to fill in parameters: {0} value(s) provided, needed {1} value(s)")]
     MissingParamValues(String, String),
+    #[error("Invalid choice {0}: expected one of {1}")]
+    InvalidChoice(String, String),
+    #[error("The parameter @{{{0}}} is used with different types")]
+    ConflictingNamedParameter(String),
+    #[error("Failed to fill in secret parameters: {0}")]
+    Secret(#[from] SecretError),
 }
@@ -0,0 +1,94 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{parser::SerializableParameter, ParameterError};
+
+/// The types of parameters, which cannot be used as names
+const RESERVED_NAMES: [&str; 10] = [
+    "int",
+    "string",
+    "boolean",
+    "uuid",
+    "secret",
+    "choice",
+    "regex",
+    "now",
+    "date",
+    "timestamp",
+];
+
+/// A parameter with a name, such as `@{branch}` or `@{id:uuid}`. Every
+/// parameter with the same name in a command is filled in with the same value
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NamedParameter {
+    pub name: String,
+    pub parameter: Box<SerializableParameter>,
+}
+
+impl NamedParameter {
+    /// Splits a named parameter into its name and the parameter it names,
+    /// e.g. `@{id:uuid}` into `id` and `@{uuid}`. A name on its own or with a
+    /// default names a blank parameter, e.g. `@{branch}` is split into
+    /// `branch` and `@{}`, and `@{branch=main}` into `branch` and `@{=main}`
+    pub fn split(s: &str) -> Result<(String, String), ParameterError> {
+        let named_param_regex =
+            r"^@\{(?P<name>[A-Za-z_][A-Za-z0-9_-]*)(?::(?P<param>.*)|(?P<default>=[^}]*))?\}$";
+        let re = Regex::new(named_param_regex).map_err(|e| {
+            ParameterError::InvalidRegex(named_param_regex.to_string(), e.to_string())
+        })?;
+
+        if let Some(caps) = re.captures(s) {
+            let name = &caps["name"];
+            if RESERVED_NAMES.contains(&name) {
+                return Err(ParameterError::InvalidParameter);
+            }
+            let param = caps
+                .name("param")
+                .or(caps.name("default"))
+                .map_or("", |param| param.as_str());
+
+            return Ok((name.to_string(), format!("@{{{}}}", param)));
+        }
+        Err(ParameterError::InvalidParameter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let ret = NamedParameter::split("@{branch}");
+        assert_eq!(ret.unwrap(), ("branch".to_string(), "@{}".to_string()));
+
+        let ret = NamedParameter::split("@{id:uuid}");
+        assert_eq!(ret.unwrap(), ("id".to_string(), "@{uuid}".to_string()));
+
+        let ret = NamedParameter::split("@{region=us-east-1}");
+        assert_eq!(
+            ret.unwrap(),
+            ("region".to_string(), "@{=us-east-1}".to_string())
+        );
+
+        let ret = NamedParameter::split("@{port_2:int[8000, 8999]}");
+        assert_eq!(
+            ret.unwrap(),
+            ("port_2".to_string(), "@{int[8000, 8999]}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_split_errors() {
+        for s in [
+            "@{}",
+            "@{my branch}",
+            "@{2fa}",
+            "@{:uuid}",
+            "@{uuid}",
+            "@{secret}",
+        ] {
+            assert!(NamedParameter::split(s).is_err());
+        }
+    }
+}
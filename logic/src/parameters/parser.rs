@@ -1,11 +1,14 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
 
 use super::{
-    blank::BlankParameter, boolean::BooleanParameter, int::IntParameter,
-    populator::RandomNumberGenerator, string::StringParameter, uuid::UuidParameter,
-    FromStrWithConfig, GenerateRandomValues, ParameterError,
+    blank::BlankParameter, boolean::BooleanParameter, choice::ChoiceParameter, date::DateParameter,
+    int::IntParameter, named::NamedParameter, populator::RandomNumberGenerator,
+    regex::RegexParameter, secret::SecretParameter, string::StringParameter,
+    timestamp::TimestampParameter, uuid::UuidParameter, FromStrWithConfig, GenerateRandomValues,
+    ParameterError,
 };
 use crate::Logic;
 
@@ -15,8 +18,71 @@ pub enum SerializableParameter {
     Int(IntParameter),
     String(StringParameter),
     Boolean(BooleanParameter),
-    Blank,
+    Blank(BlankParameter),
     Uuid(UuidParameter),
+    Secret(SecretParameter),
+    Named(NamedParameter),
+    Choice(ChoiceParameter),
+    Regex(RegexParameter),
+    Date(DateParameter),
+    Timestamp(TimestampParameter),
+}
+
+impl SerializableParameter {
+    /// The name of the parameter, if it has one
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            SerializableParameter::Named(param) => Some(&param.name),
+            _ => None,
+        }
+    }
+
+    /// The parameter without its name
+    pub fn unnamed(&self) -> &SerializableParameter {
+        match self {
+            SerializableParameter::Named(param) => &param.parameter,
+            _ => self,
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        matches!(self.unnamed(), SerializableParameter::Blank(_))
+    }
+
+    /// Whether the value of the parameter is given when the command is used,
+    /// rather than generated
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self.unnamed(),
+            SerializableParameter::Blank(_) | SerializableParameter::Choice(_)
+        )
+    }
+
+    pub fn is_secret(&self) -> bool {
+        matches!(self.unnamed(), SerializableParameter::Secret(_))
+    }
+}
+
+/// Returns the blank and choice parameters that a value is given for, in the
+/// order the values are given in: every named parameter the first time it is
+/// used, and every unnamed one
+pub fn input_parameters(parameters: &[SerializableParameter]) -> Vec<&SerializableParameter> {
+    let mut inputs = Vec::new();
+    for (i, param) in parameters.iter().enumerate() {
+        if !param.is_input() {
+            continue;
+        }
+        match param.name() {
+            Some(name) => {
+                let is_first_use = !parameters[..i].iter().any(|p| p.name() == Some(name));
+                if is_first_use {
+                    inputs.push(param);
+                }
+            }
+            None => inputs.push(param),
+        }
+    }
+    inputs
 }
 
 impl GenerateRandomValues for SerializableParameter {
@@ -26,7 +92,14 @@ impl GenerateRandomValues for SerializableParameter {
             SerializableParameter::String(param) => param.generate_random_value(rng),
             SerializableParameter::Boolean(param) => param.generate_random_value(rng),
             SerializableParameter::Uuid(param) => param.generate_random_value(),
-            SerializableParameter::Blank => String::new(),
+            SerializableParameter::Choice(param) => param.generate_random_value(rng),
+            SerializableParameter::Regex(param) => param.generate_random_value(rng),
+            SerializableParameter::Named(param) => param.parameter.generate_random_value(rng),
+            // Blank, secret and time parameters are filled in when populating
+            SerializableParameter::Blank(_)
+            | SerializableParameter::Secret(_)
+            | SerializableParameter::Date(_)
+            | SerializableParameter::Timestamp(_) => String::new(),
         }
     }
 }
@@ -36,16 +109,37 @@ impl Logic {
         &self,
         command: String,
     ) -> Result<(Vec<String>, Vec<SerializableParameter>), ParameterError> {
-        let regex_string = r"\@\{(?P<param>([^}]*))\}";
+        // The pattern of a regex parameter can contain `}`, so it ends at the
+        // first `]}` instead
+        let regex_string = r"\@\{(?P<param>([^}\[]*regex\[.*?\]|[^}]*))\}";
         let re = Regex::new(regex_string)
             .map_err(|e| ParameterError::InvalidRegex(regex_string.to_string(), e.to_string()))?;
 
         let mut parameters = Vec::new();
         let mut non_parameter_strs = Vec::new();
         let mut last_end = 0;
+        // The parameter that each name is given to, which has to be the same
+        // wherever it is given
+        let mut named_params: HashMap<String, String> = HashMap::new();
+        // The uses of names on their own, which refer to the parameter the
+        // name is given to elsewhere in the command, if any
+        let mut bare_names = Vec::new();
 
         for mat in re.find_iter(&command) {
             let param = self.parse_parameter(mat.as_str().to_owned())?;
+            if let Some(name) = param.name() {
+                let (_, unnamed) = NamedParameter::split(mat.as_str())?;
+                if unnamed == "@{}" {
+                    bare_names.push(parameters.len());
+                } else {
+                    let named_param = named_params
+                        .entry(name.to_string())
+                        .or_insert(unnamed.clone());
+                    if *named_param != unnamed {
+                        return Err(ParameterError::ConflictingNamedParameter(name.to_string()));
+                    }
+                }
+            }
             parameters.push(param);
 
             non_parameter_strs.push(command[last_end..mat.start()].to_string());
@@ -58,6 +152,14 @@ impl Logic {
             non_parameter_strs.push("".to_string());
         }
 
+        for i in bare_names {
+            if let SerializableParameter::Named(param) = &mut parameters[i] {
+                if let Some(unnamed) = named_params.get(&param.name) {
+                    *param.parameter = self.parse_unnamed_parameter(unnamed)?;
+                }
+            }
+        }
+
         // There should be a parameter for each "gap" between strings
         assert_eq!(non_parameter_strs.len() - 1, parameters.len());
 
@@ -65,37 +167,84 @@ impl Logic {
     }
 
     fn parse_parameter(&self, s: String) -> Result<SerializableParameter, ParameterError> {
-        if BlankParameter::from_str(&s).is_ok() {
-            return Ok(SerializableParameter::Blank);
+        match self.parse_unnamed_parameter(&s) {
+            Err(ParameterError::InvalidParameter) => {}
+            ret => return ret,
+        }
+
+        let (name, unnamed) = NamedParameter::split(&s)?;
+        Ok(SerializableParameter::Named(NamedParameter {
+            name,
+            parameter: Box::new(self.parse_unnamed_parameter(&unnamed)?),
+        }))
+    }
+
+    fn parse_unnamed_parameter(&self, s: &str) -> Result<SerializableParameter, ParameterError> {
+        if let Ok(blank_param) = BlankParameter::from_str(s) {
+            return Ok(SerializableParameter::Blank(blank_param));
         }
 
-        if let Ok(string_param) = StringParameter::from_str(&s, &self.config) {
+        if let Ok(string_param) = StringParameter::from_str(s, &self.config) {
             return Ok(SerializableParameter::String(string_param));
         }
 
-        if let Ok(int_param) = IntParameter::from_str(&s, &self.config) {
+        if let Ok(int_param) = IntParameter::from_str(s, &self.config) {
             return Ok(SerializableParameter::Int(int_param));
         }
 
-        if let Ok(bool_param) = BooleanParameter::from_str(&s) {
+        if let Ok(bool_param) = BooleanParameter::from_str(s) {
             return Ok(SerializableParameter::Boolean(bool_param));
         }
 
-        if let Ok(uuid_param) = UuidParameter::from_str(&s) {
+        if let Ok(uuid_param) = UuidParameter::from_str(s) {
             return Ok(SerializableParameter::Uuid(uuid_param));
         }
 
+        if let Ok(secret_param) = SecretParameter::from_str(s) {
+            return Ok(SerializableParameter::Secret(secret_param));
+        }
+
+        if let Ok(choice_param) = ChoiceParameter::from_str(s) {
+            return Ok(SerializableParameter::Choice(choice_param));
+        }
+
+        // A regex parameter with a pattern that values cannot be generated for
+        // is an error of its own, so that the pattern can be fixed
+        match RegexParameter::from_str(s) {
+            Ok(regex_param) => return Ok(SerializableParameter::Regex(regex_param)),
+            Err(ParameterError::InvalidParameter) => {}
+            Err(e) => return Err(e),
+        }
+
+        // Likewise for a date parameter with a format that cannot be used
+        match DateParameter::from_str(s) {
+            Ok(date_param) => return Ok(SerializableParameter::Date(date_param)),
+            Err(ParameterError::InvalidParameter) => {}
+            Err(e) => return Err(e),
+        }
+
+        if let Ok(timestamp_param) = TimestampParameter::from_str(s) {
+            return Ok(SerializableParameter::Timestamp(timestamp_param));
+        }
+
         Err(ParameterError::InvalidParameter)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{parameters::parser::SerializableParameter, Logic};
+    use crate::{
+        parameters::{
+            parser::{input_parameters, SerializableParameter},
+            ParameterError,
+        },
+        Logic,
+    };
+    use data::dal::memory::MemoryStore;
 
     #[test]
     fn test_parse_parameters_no_parameter() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.parse_parameters("cmd @ @email @wadsf @test {} @".to_string());
         assert!(ret.is_ok());
@@ -109,19 +258,19 @@ mod tests {
 
     #[test]
     fn test_parse_parameters() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.parse_parameters("cmd @{boolean} @{int} @{string} @{}".to_string());
         assert!(ret.is_ok());
         let (non_parameter_strings, parameters) = ret.unwrap();
         assert_eq!(parameters.len(), 4);
         matches!(
-            parameters.get(0).unwrap(),
+            parameters.first().unwrap(),
             SerializableParameter::Boolean(_)
         );
         matches!(parameters.get(1).unwrap(), SerializableParameter::Int(_));
         matches!(parameters.get(2).unwrap(), SerializableParameter::String(_));
-        matches!(parameters.get(3).unwrap(), SerializableParameter::Blank);
+        matches!(parameters.get(3).unwrap(), SerializableParameter::Blank(_));
         assert_eq!(
             non_parameter_strings,
             vec![
@@ -136,16 +285,27 @@ mod tests {
 
     #[test]
     fn test_parse_parameter_blank() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.parse_parameter("@{}".to_string());
         assert!(ret.is_ok());
-        matches!(ret.unwrap(), SerializableParameter::Blank);
+        matches!(ret.unwrap(), SerializableParameter::Blank(_));
+    }
+
+    #[test]
+    fn test_parse_parameter_blank_default() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let ret = logic.parse_parameter("@{=main}".to_string());
+        assert!(matches!(
+            ret,
+            Ok(SerializableParameter::Blank(param)) if param.default.as_deref() == Some("main")
+        ));
     }
 
     #[test]
     fn test_parse_parameter_int() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.parse_parameter("@{int}".to_string());
         assert!(ret.is_ok());
@@ -154,7 +314,7 @@ mod tests {
 
     #[test]
     fn test_parse_parameter_string() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.parse_parameter("@{string}".to_string());
         assert!(ret.is_ok());
@@ -163,10 +323,151 @@ mod tests {
 
     #[test]
     fn test_parse_parameter_boolean() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let ret = logic.parse_parameter("@{boolean}".to_string());
         assert!(ret.is_ok());
         matches!(ret.unwrap(), SerializableParameter::Boolean(_));
     }
+
+    #[test]
+    fn test_parse_parameters_named() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let (_, parameters) = logic
+            .parse_parameters(
+                "git checkout -b @{branch} && git push -u origin @{branch} @{id:uuid} @{}"
+                    .to_string(),
+            )
+            .unwrap();
+        assert_eq!(parameters.len(), 4);
+        assert_eq!(parameters[0].name(), Some("branch"));
+        assert!(parameters[1].is_blank());
+        assert!(matches!(
+            parameters[2].unnamed(),
+            SerializableParameter::Uuid(_)
+        ));
+        assert_eq!(parameters[3].name(), None);
+        let inputs = input_parameters(&parameters);
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].name(), Some("branch"));
+        assert_eq!(inputs[1].name(), None);
+
+        // A name on its own refers to the parameter it is given to elsewhere
+        let (_, parameters) = logic
+            .parse_parameters("echo @{region} @{region=us-east-1} @{id} @{id:uuid}".to_string())
+            .unwrap();
+        assert!(matches!(
+            parameters[0].unnamed(),
+            SerializableParameter::Blank(param) if param.default.as_deref() == Some("us-east-1")
+        ));
+        assert!(matches!(
+            parameters[2].unnamed(),
+            SerializableParameter::Uuid(_)
+        ));
+        assert_eq!(input_parameters(&parameters).len(), 1);
+
+        // A name cannot be given to different parameters
+        let ret = logic.parse_parameters("echo @{port:int} @{port:string}".to_string());
+        assert!(matches!(
+            ret,
+            Err(ParameterError::ConflictingNamedParameter(name)) if name == "port"
+        ));
+
+        // The parameter that is named has to be valid
+        let ret = logic.parse_parameters("echo @{port:int[9, 1]}".to_string());
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_parse_parameter_choice() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let ret = logic.parse_parameter("@{choice[dev,staging,prod]}".to_string());
+        assert!(matches!(
+            ret,
+            Ok(SerializableParameter::Choice(param)) if param.choices.len() == 3
+        ));
+
+        let (_, parameters) = logic
+            .parse_parameters("deploy @{env:choice[dev,prod]} @{} @{env}".to_string())
+            .unwrap();
+        assert!(parameters.iter().all(|param| param.is_input()));
+        assert_eq!(input_parameters(&parameters).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_parameter_regex() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let (non_parameter_strs, parameters) = logic
+            .parse_parameters(
+                r"jira @{regex[[A-Z]{3}-\d{4}]} @{mac:regex[([0-9a-f]{2}:){5}[0-9a-f]{2}]} @{}"
+                    .to_string(),
+            )
+            .unwrap();
+        assert_eq!(non_parameter_strs, vec!["jira ", " ", " ", ""]);
+        assert!(matches!(
+            &parameters[0],
+            SerializableParameter::Regex(param) if param.pattern == r"[A-Z]{3}-\d{4}"
+        ));
+        assert!(matches!(
+            parameters[1].unnamed(),
+            SerializableParameter::Regex(param) if param.pattern == "([0-9a-f]{2}:){5}[0-9a-f]{2}"
+        ));
+        assert!(parameters[2].is_blank());
+
+        // Patterns that values cannot be generated for are reported
+        for command in ["echo @{regex[(ab]}", "echo @{id:regex[^a$]}"] {
+            assert!(matches!(
+                logic.parse_parameters(command.to_string()),
+                Err(ParameterError::InvalidRegex(_, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_parameter_time() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let (_, parameters) = logic
+            .parse_parameters(
+                "tar czf backup-@{now[%Y%m%d]}.tgz @{since:date[-1d,%F]} @{since} @{timestamp[ms]}"
+                    .to_string(),
+            )
+            .unwrap();
+        assert!(matches!(
+            &parameters[0],
+            SerializableParameter::Date(param) if param.offset == 0 && param.format == "%Y%m%d"
+        ));
+        assert!(matches!(
+            parameters[1].unnamed(),
+            SerializableParameter::Date(param) if param.offset == -86400 && param.format == "%F"
+        ));
+        assert!(matches!(
+            parameters[2].unnamed(),
+            SerializableParameter::Date(_)
+        ));
+        assert!(matches!(
+            &parameters[3],
+            SerializableParameter::Timestamp(param) if param.millis
+        ));
+
+        // Formats that cannot be used are reported
+        assert!(matches!(
+            logic.parse_parameters("echo @{now[%Q]}".to_string()),
+            Err(ParameterError::InvalidDateFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_parameter_secret() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let ret = logic.parse_parameter("@{secret:api_token}".to_string());
+        assert!(matches!(
+            ret,
+            Ok(SerializableParameter::Secret(param)) if param.name == "api_token"
+        ));
+    }
 }
@@ -1,6 +1,9 @@
+use chrono::{DateTime, FixedOffset, Local};
 use rand::{rngs::ThreadRng, Rng};
+use std::collections::HashMap;
 
-use super::{parser::SerializableParameter, GenerateRandomValues, ParameterError};
+use super::parser::{input_parameters, SerializableParameter};
+use super::{GenerateRandomValues, GenerateTimeValues, ParameterError};
 use crate::Logic;
 
 pub trait RandomNumberGenerator {
@@ -13,6 +16,19 @@ impl RandomNumberGenerator for ThreadRng {
     }
 }
 
+pub trait Clock {
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// The time on the system clock, in the local time zone
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().into()
+    }
+}
+
 impl Logic {
     pub fn populate_parameters(
         &self,
@@ -20,43 +36,82 @@ impl Logic {
         parameters: Vec<SerializableParameter>,
         blank_parameter_values: Vec<String>,
         rng: Option<Box<dyn RandomNumberGenerator>>,
+        clock: Option<Box<dyn Clock>>,
     ) -> Result<(String, Vec<String>), ParameterError> {
         let mut rng = if let Some(rng) = rng {
             rng
         } else {
             Box::new(ThreadRng::default())
         };
+        let clock = clock.unwrap_or_else(|| Box::new(SystemClock));
 
         // Build the string by generating parameters
         let mut generated_result = String::new();
         let mut generated_parameters = Vec::new();
         let mut blank_parameters_used_index = 0;
+        // The values of named parameters, which are reused wherever the name is
+        let mut named_values: HashMap<&str, String> = HashMap::new();
 
         for (i, other_string) in non_parameter_strs.iter().enumerate() {
             generated_result.push_str(other_string);
 
             if i < non_parameter_strs.len() - 1 {
-                let generated_value = match &parameters[i] {
-                    SerializableParameter::Blank => {
+                let parameter = &parameters[i];
+                if let Some(value) = parameter.name().and_then(|name| named_values.get(name)) {
+                    generated_result.push_str(value);
+                    generated_parameters.push(value.clone());
+                    continue;
+                }
+
+                let generated_value = match parameter.unnamed() {
+                    SerializableParameter::Blank(param) => {
                         if blank_parameters_used_index < blank_parameter_values.len() {
                             let user_val =
                                 blank_parameter_values[blank_parameters_used_index].clone();
                             blank_parameters_used_index += 1;
-                            user_val
+                            param.value_or_default(user_val)
                         } else {
-                            let total_blank_params_needed = parameters
-                                .iter()
-                                .filter(|p| matches!(p, SerializableParameter::Blank))
-                                .count();
                             return Err(ParameterError::MissingBlankParamValues(
                                 blank_parameters_used_index.to_string(),
-                                total_blank_params_needed.to_string(),
+                                input_parameters(&parameters).len().to_string(),
                             ));
                         }
                     }
-                    _ => parameters[i].generate_random_value(rng.as_mut()),
+                    // Values for choice parameters are given along with the values
+                    // for blank parameters, and must be one of the choices. A
+                    // choice that is not made is made at random
+                    SerializableParameter::Choice(param) => {
+                        let choice = blank_parameter_values
+                            .get(blank_parameters_used_index)
+                            .filter(|choice| !choice.is_empty())
+                            .cloned();
+                        if blank_parameters_used_index < blank_parameter_values.len() {
+                            blank_parameters_used_index += 1;
+                        }
+                        match choice {
+                            Some(choice) if !param.choices.contains(&choice) => {
+                                return Err(ParameterError::InvalidChoice(
+                                    choice,
+                                    param.choices.join(", "),
+                                ));
+                            }
+                            Some(choice) => choice,
+                            None => param.generate_random_value(rng.as_mut()),
+                        }
+                    }
+                    SerializableParameter::Secret(param) => {
+                        self.secret_value(&param.name)?.to_string()
+                    }
+                    SerializableParameter::Date(param) => param.generate_time_value(clock.as_ref()),
+                    SerializableParameter::Timestamp(param) => {
+                        param.generate_time_value(clock.as_ref())
+                    }
+                    unnamed => unnamed.generate_random_value(rng.as_mut()),
                 };
 
+                if let Some(name) = parameter.name() {
+                    named_values.insert(name, generated_value.clone());
+                }
                 generated_result.push_str(&generated_value);
                 generated_parameters.push(generated_value);
             }
@@ -67,14 +122,21 @@ impl Logic {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use crate::{
         parameters::{
-            boolean::BooleanParameter, int::IntParameter, parser::SerializableParameter,
-            string::StringParameter, RandomNumberGenerator,
+            blank::BlankParameter, boolean::BooleanParameter, choice::ChoiceParameter,
+            date::DateParameter, int::IntParameter, named::NamedParameter,
+            parser::SerializableParameter, secret::SecretParameter, string::StringParameter,
+            timestamp::TimestampParameter, uuid::UuidParameter, Clock, ParameterError,
+            RandomNumberGenerator,
         },
+        secrets::{tests::TEST_KDF_PARAMS, SecretError, SecretStore},
         Logic,
     };
+    use chrono::{DateTime, FixedOffset, Utc};
+    use data::dal::memory::MemoryStore;
+    use tempfile::tempdir;
 
     pub struct MockRng {
         values: Vec<u32>,
@@ -98,9 +160,27 @@ mod tests {
         }
     }
 
+    pub struct MockClock {
+        now: DateTime<FixedOffset>,
+    }
+
+    impl MockClock {
+        /// A clock that is always at the Unix time given, in UTC
+        pub fn new(timestamp: i64) -> Self {
+            let now = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap();
+            Self { now: now.into() }
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> DateTime<FixedOffset> {
+            self.now
+        }
+    }
+
     #[test]
     fn test_populate_parameters_1() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![0, 1, 2, 4]));
 
@@ -116,7 +196,8 @@ mod tests {
             SerializableParameter::Int(IntParameter::default()),
         ];
 
-        let ret = logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng));
+        let ret =
+            logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng), None);
         assert!(ret.is_ok());
 
         let (generated_string, generated_parameters) = ret.unwrap();
@@ -129,7 +210,7 @@ mod tests {
 
     #[test]
     fn test_populate_parameters_2() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![2, 3, 4, 7]));
 
@@ -147,7 +228,8 @@ mod tests {
             SerializableParameter::Boolean(BooleanParameter::default()),
         ];
 
-        let ret = logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng));
+        let ret =
+            logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng), None);
         assert!(ret.is_ok());
 
         let (generated_string, generated_parameters) = ret.unwrap();
@@ -165,7 +247,7 @@ mod tests {
 
     #[test]
     fn test_populate_parameters_blank_1() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![2, 0]));
 
@@ -178,11 +260,11 @@ mod tests {
             "".to_string(),
         ];
         let parameters = vec![
-            SerializableParameter::Blank,
+            SerializableParameter::Blank(BlankParameter::default()),
             SerializableParameter::Int(IntParameter::default()),
-            SerializableParameter::Blank,
+            SerializableParameter::Blank(BlankParameter::default()),
             SerializableParameter::Boolean(BooleanParameter::default()),
-            SerializableParameter::Blank,
+            SerializableParameter::Blank(BlankParameter::default()),
         ];
         let blank_params_values = vec![
             "value1".to_string(),
@@ -195,6 +277,7 @@ mod tests {
             parameters,
             blank_params_values,
             Some(rng),
+            None,
         );
         assert!(ret.is_ok());
 
@@ -212,16 +295,200 @@ mod tests {
         assert_eq!("ls value1 7 value2 false value3", generated_string);
     }
 
+    #[test]
+    fn test_populate_parameters_named() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let rng = Box::new(MockRng::new(vec![2, 0]));
+
+        let non_parameter_strs = vec![
+            "git checkout -b ".to_string(),
+            " && git push -u origin ".to_string(),
+            " # ".to_string(),
+            " ".to_string(),
+            " ".to_string(),
+            " ".to_string(),
+            "".to_string(),
+        ];
+        let named = |name: &str, parameter: SerializableParameter| {
+            SerializableParameter::Named(NamedParameter {
+                name: name.to_string(),
+                parameter: Box::new(parameter),
+            })
+        };
+        let parameters = vec![
+            named(
+                "branch",
+                SerializableParameter::Blank(BlankParameter::default()),
+            ),
+            named(
+                "branch",
+                SerializableParameter::Blank(BlankParameter::default()),
+            ),
+            named("id", SerializableParameter::Uuid(UuidParameter)),
+            SerializableParameter::Blank(BlankParameter::default()),
+            named("id", SerializableParameter::Uuid(UuidParameter)),
+            named("n", SerializableParameter::Int(IntParameter::default())),
+        ];
+        // One value for the named blank parameter and one for the unnamed one
+        let blank_params_values = vec!["feature".to_string(), "note".to_string()];
+
+        let (generated_string, generated_parameters) = logic
+            .populate_parameters(
+                non_parameter_strs,
+                parameters,
+                blank_params_values,
+                Some(rng),
+                None,
+            )
+            .unwrap();
+        assert_eq!(generated_parameters[0], "feature");
+        assert_eq!(generated_parameters[1], "feature");
+        assert_eq!(generated_parameters[2], generated_parameters[4]);
+        assert_eq!(generated_parameters[3], "note");
+        assert_eq!(generated_parameters[5], "7");
+        assert!(generated_string
+            .starts_with("git checkout -b feature && git push -u origin feature # "));
+    }
+
+    #[test]
+    fn test_populate_parameters_blank_default() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let (generated_string, _) = logic
+            .populate_parameters(
+                vec!["git push ".to_string(), " ".to_string(), "".to_string()],
+                vec![
+                    SerializableParameter::Blank(BlankParameter {
+                        default: Some("origin".to_string()),
+                    }),
+                    SerializableParameter::Blank(BlankParameter {
+                        default: Some("main".to_string()),
+                    }),
+                ],
+                // An empty value falls back to the default
+                vec![String::new(), "dev".to_string()],
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!("git push origin dev", generated_string);
+    }
+
+    #[test]
+    fn test_populate_parameters_choice() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let non_parameter_strs = || {
+            vec![
+                "deploy --env ".to_string(),
+                " --output ".to_string(),
+                " ".to_string(),
+                "".to_string(),
+            ]
+        };
+        let parameters = || {
+            vec![
+                SerializableParameter::Choice(ChoiceParameter {
+                    choices: vec!["dev".to_string(), "prod".to_string()],
+                }),
+                SerializableParameter::Choice(ChoiceParameter {
+                    choices: vec!["json".to_string(), "yaml".to_string()],
+                }),
+                SerializableParameter::Blank(BlankParameter::default()),
+            ]
+        };
+
+        // The values of choice parameters are given with the blank ones
+        let (generated_string, _) = logic
+            .populate_parameters(
+                non_parameter_strs(),
+                parameters(),
+                vec!["prod".to_string(), "yaml".to_string(), "app".to_string()],
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!("deploy --env prod --output yaml app", generated_string);
+
+        // Choices that are not made are made at random
+        let rng = Box::new(MockRng::new(vec![1]));
+        let (generated_string, _) = logic
+            .populate_parameters(
+                non_parameter_strs(),
+                parameters(),
+                vec![String::new(), String::new(), "app".to_string()],
+                Some(rng),
+                None,
+            )
+            .unwrap();
+        assert_eq!("deploy --env prod --output yaml app", generated_string);
+
+        // Values that are not one of the choices are rejected
+        let result = logic.populate_parameters(
+            non_parameter_strs(),
+            parameters(),
+            vec!["staging".to_string(), "yaml".to_string(), "app".to_string()],
+            None,
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(ParameterError::InvalidChoice(choice, choices))
+                if choice == "staging" && choices == "dev, prod"
+        ));
+    }
+
+    #[test]
+    fn test_populate_parameters_time() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let non_parameter_strs = vec![
+            "pg_dump > backup-".to_string(),
+            ".sql && echo ".to_string(),
+            " ".to_string(),
+            "".to_string(),
+        ];
+        let parameters = vec![
+            SerializableParameter::Named(NamedParameter {
+                name: "day".to_string(),
+                parameter: Box::new(SerializableParameter::Date(DateParameter {
+                    offset: -86400,
+                    format: "%F".to_string(),
+                })),
+            }),
+            SerializableParameter::Named(NamedParameter {
+                name: "day".to_string(),
+                parameter: Box::new(SerializableParameter::Blank(BlankParameter::default())),
+            }),
+            SerializableParameter::Timestamp(TimestampParameter { millis: false }),
+        ];
+
+        let clock = Box::new(MockClock::new(1714555815));
+        let (generated_string, generated_parameters) = logic
+            .populate_parameters(non_parameter_strs, parameters, vec![], None, Some(clock))
+            .unwrap();
+        assert_eq!(
+            generated_string,
+            "pg_dump > backup-2024-04-30.sql && echo 2024-04-30 1714555815"
+        );
+        assert_eq!(
+            generated_parameters,
+            vec!["2024-04-30", "2024-04-30", "1714555815"]
+        );
+    }
+
     #[test]
     fn test_populate_parameters_no_parameters() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![0, 1, 2, 4]));
 
         let non_parameter_strs = vec!["some string".to_string()];
         let parameters = vec![];
 
-        let ret = logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng));
+        let ret =
+            logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng), None);
         assert!(ret.is_ok());
 
         let (generated_string, generated_parameters) = ret.unwrap();
@@ -231,14 +498,15 @@ mod tests {
 
     #[test]
     fn test_populate_parameters_empty() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![0, 1, 2, 4]));
 
         let non_parameter_strs = vec![];
         let parameters = vec![];
 
-        let ret = logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng));
+        let ret =
+            logic.populate_parameters(non_parameter_strs, parameters, vec![], Some(rng), None);
         assert!(ret.is_ok());
 
         let (generated_string, generated_parameters) = ret.unwrap();
@@ -248,12 +516,12 @@ mod tests {
 
     #[test]
     fn test_populate_parameters_too_many_blank_param_values() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![2, 0]));
 
         let non_parameter_strs = vec!["ls ".to_string(), "".to_string()];
-        let parameters = vec![SerializableParameter::Blank];
+        let parameters = vec![SerializableParameter::Blank(BlankParameter::default())];
         let blank_params_values = vec!["value1".to_string(), "value2".to_string()];
 
         let ret = logic.populate_parameters(
@@ -261,6 +529,7 @@ mod tests {
             parameters,
             blank_params_values,
             Some(rng),
+            None,
         );
         assert!(ret.is_ok());
 
@@ -271,12 +540,12 @@ mod tests {
 
     #[test]
     fn test_populate_parameters_too_few_blank_param_values() {
-        let logic = Logic::try_default().unwrap();
+        let logic = Logic::new(MemoryStore::new()).unwrap();
 
         let rng = Box::new(MockRng::new(vec![2, 0]));
 
         let non_parameter_strs = vec!["ls ".to_string(), "".to_string()];
-        let parameters = vec![SerializableParameter::Blank];
+        let parameters = vec![SerializableParameter::Blank(BlankParameter::default())];
         let blank_params_values = vec![];
 
         let ret = logic.populate_parameters(
@@ -284,7 +553,58 @@ mod tests {
             parameters,
             blank_params_values,
             Some(rng),
+            None,
         );
         assert!(ret.is_err());
     }
+
+    #[test]
+    fn test_populate_parameters_secret() {
+        let mut logic = Logic::new(MemoryStore::new()).unwrap();
+
+        let non_parameter_strs = vec!["curl -H 'Token: ".to_string(), "'".to_string()];
+        let parameters = || {
+            vec![SerializableParameter::Secret(SecretParameter {
+                name: "api_token".to_string(),
+            })]
+        };
+
+        // The secrets have to be unlocked first
+        let ret =
+            logic.populate_parameters(non_parameter_strs.clone(), parameters(), vec![], None, None);
+        assert!(matches!(
+            ret,
+            Err(ParameterError::Secret(SecretError::Locked))
+        ));
+
+        let dir = tempdir().unwrap();
+        let mut store = SecretStore::open_with_params(
+            &dir.path().join("secrets.json"),
+            "hunter2",
+            TEST_KDF_PARAMS,
+        )
+        .unwrap();
+        store.set("api_token", "s3cr3t").unwrap();
+        logic.secrets = Some(store);
+
+        let (generated_string, generated_parameters) = logic
+            .populate_parameters(non_parameter_strs, parameters(), vec![], None, None)
+            .unwrap();
+        assert_eq!(generated_parameters, vec!["s3cr3t".to_string()]);
+        assert_eq!("curl -H 'Token: s3cr3t'", generated_string);
+
+        let ret = logic.populate_parameters(
+            vec!["echo ".to_string(), "".to_string()],
+            vec![SerializableParameter::Secret(SecretParameter {
+                name: "missing".to_string(),
+            })],
+            vec![],
+            None,
+            None,
+        );
+        assert!(matches!(
+            ret,
+            Err(ParameterError::Secret(SecretError::NotFound(_)))
+        ));
+    }
 }
@@ -0,0 +1,224 @@
+use rand::{Rng, RngCore};
+use regex::Regex;
+use regex_syntax::hir::{
+    Capture, Class, ClassBytes, ClassBytesRange, ClassUnicode, ClassUnicodeRange, Hir, HirKind,
+    Repetition,
+};
+use regex_syntax::ParserBuilder;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::{populator::RandomNumberGenerator, GenerateRandomValues, ParameterError};
+
+/// The most times that an unbounded repetition such as `*` or `+` is repeated
+/// beyond its minimum
+const MAX_REPEAT: u32 = 10;
+
+/// A parameter whose value matches a regular expression, e.g.
+/// `@{regex[[a-z]{3}-\d{4}]}`. Classes such as `\d` and `\w` only match ASCII
+/// characters, and `.` and negated classes only match printable ones
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RegexParameter {
+    pub pattern: String,
+}
+
+impl FromStr for RegexParameter {
+    type Err = ParameterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let regex_param_regex = r"^@\{regex\[(?P<pattern>.+)\]\}$";
+        let re = Regex::new(regex_param_regex).map_err(|e| {
+            ParameterError::InvalidRegex(regex_param_regex.to_string(), e.to_string())
+        })?;
+
+        if let Some(caps) = re.captures(s) {
+            let pattern = caps["pattern"].to_string();
+            // Patterns that values cannot be generated for are rejected when
+            // the parameter is parsed, rather than when a value is generated
+            generator(&pattern)?;
+
+            return Ok(RegexParameter { pattern });
+        }
+        Err(ParameterError::InvalidParameter)
+    }
+}
+
+impl GenerateRandomValues for RegexParameter {
+    fn generate_random_value(&self, rng: &mut dyn RandomNumberGenerator) -> String {
+        let generator =
+            generator(&self.pattern).expect("the pattern is checked when the parameter is parsed");
+        RngAdapter(rng).sample(&generator)
+    }
+}
+
+/// Compiles the pattern into a generator of strings that match it
+fn generator(pattern: &str) -> Result<rand_regex::Regex, ParameterError> {
+    let invalid = |e: String| ParameterError::InvalidRegex(pattern.to_string(), e);
+
+    // Classes such as `.` match any byte without UTF-8 checks, and are then
+    // narrowed to printable characters
+    let hir = ParserBuilder::new()
+        .unicode(false)
+        .utf8(false)
+        .build()
+        .parse(pattern)
+        .map_err(|e| invalid(e.to_string()))?;
+    let hir = printable(hir).map_err(invalid)?;
+    if !hir.properties().is_utf8() {
+        return Err(invalid("the pattern can match invalid UTF-8".to_string()));
+    }
+
+    rand_regex::Regex::with_hir(hir, MAX_REPEAT).map_err(|e| invalid(e.to_string()))
+}
+
+/// Narrows the classes that match characters outside of ASCII, such as `.`
+/// and `[^a-z]`, to printable ASCII characters
+fn printable(hir: Hir) -> Result<Hir, String> {
+    let hir = match hir.into_kind() {
+        HirKind::Class(class) => {
+            let class = match class {
+                Class::Bytes(mut class) if !class.is_ascii() => {
+                    class.intersect(&ClassBytes::new([ClassBytesRange::new(b' ', b'~')]));
+                    Class::Bytes(class)
+                }
+                Class::Unicode(mut class) if !class.is_ascii() => {
+                    class.intersect(&ClassUnicode::new([ClassUnicodeRange::new(' ', '~')]));
+                    Class::Unicode(class)
+                }
+                class => class,
+            };
+            if class.is_empty() {
+                return Err("a class matches no printable characters".to_string());
+            }
+            Hir::class(class)
+        }
+        HirKind::Empty => Hir::empty(),
+        HirKind::Literal(literal) => Hir::literal(literal.0),
+        HirKind::Look(look) => Hir::look(look),
+        HirKind::Repetition(repetition) => Hir::repetition(Repetition {
+            sub: Box::new(printable(*repetition.sub)?),
+            ..repetition
+        }),
+        HirKind::Capture(capture) => Hir::capture(Capture {
+            sub: Box::new(printable(*capture.sub)?),
+            ..capture
+        }),
+        HirKind::Concat(subs) => {
+            Hir::concat(subs.into_iter().map(printable).collect::<Result<_, _>>()?)
+        }
+        HirKind::Alternation(subs) => {
+            Hir::alternation(subs.into_iter().map(printable).collect::<Result<_, _>>()?)
+        }
+    };
+    Ok(hir)
+}
+
+/// Lets the generator draw its random numbers from a `RandomNumberGenerator`,
+/// so that the values generated are the same whenever the numbers are
+struct RngAdapter<'a>(&'a mut dyn RandomNumberGenerator);
+
+impl RngCore for RngAdapter<'_> {
+    fn next_u32(&mut self) -> u32 {
+        let high = self.0.generate_range(0, u16::MAX as i32) as u32;
+        let low = self.0.generate_range(0, u16::MAX as i32) as u32;
+        (high << 16) | low
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::populator::tests::MockRng;
+    use rand::rngs::ThreadRng;
+
+    #[test]
+    fn test_from_str() {
+        let ret = RegexParameter::from_str(r"@{regex[[a-z]{3}-\d{4}]}");
+        assert_eq!(ret.unwrap().pattern, r"[a-z]{3}-\d{4}");
+
+        let ret = RegexParameter::from_str("@{regex[([0-9a-f]{2}:){5}[0-9a-f]{2}]}");
+        assert_eq!(ret.unwrap().pattern, "([0-9a-f]{2}:){5}[0-9a-f]{2}");
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        for s in ["@{regex}", "@{regex[]}", "@{regex[a-z}", "@{choice[a,b]}"] {
+            assert!(matches!(
+                RegexParameter::from_str(s),
+                Err(ParameterError::InvalidParameter)
+            ));
+        }
+
+        // Patterns that values cannot be generated for
+        for s in [
+            "@{regex[[a-z]{3]}",
+            "@{regex[(ab]}",
+            "@{regex[^[a-z]+$]}",
+            r"@{regex[\bword]}",
+            "@{regex[[^ -~]]}",
+            r"@{regex[(?-u:\xFF)]}",
+        ] {
+            assert!(matches!(
+                RegexParameter::from_str(s),
+                Err(ParameterError::InvalidRegex(_, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_generate_random_value() {
+        let param = RegexParameter::from_str(r"@{regex[[a-z]{3}-\d{4}]}").unwrap();
+
+        let mut rng = MockRng::new(vec![0]);
+        assert_eq!(param.generate_random_value(&mut rng), "aaa-0000");
+
+        let re = Regex::new(r"^[a-z]{3}-[0-9]{4}$").unwrap();
+        let mut rng = ThreadRng::default();
+        for _ in 0..100 {
+            assert!(re.is_match(&param.generate_random_value(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn test_generate_random_value_printable() {
+        // `.` and negated classes only generate printable ASCII characters
+        let param = RegexParameter::from_str(r"@{regex[.[^a-z]\D]}").unwrap();
+
+        let mut rng = MockRng::new(vec![0]);
+        assert_eq!(param.generate_random_value(&mut rng), "   ");
+
+        let re = Regex::new(r"^[ -~][ -`{-~][ -/:-~]$").unwrap();
+        let mut rng = ThreadRng::default();
+        for _ in 0..100 {
+            assert!(re.is_match(&param.generate_random_value(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn test_generate_random_value_repetition() {
+        let param = RegexParameter::from_str("@{regex[a*]}").unwrap();
+
+        let mut rng = ThreadRng::default();
+        for _ in 0..100 {
+            let value = param.generate_random_value(&mut rng);
+            assert!(value.chars().all(|c| c == 'a'));
+            assert!(value.len() <= MAX_REPEAT as usize);
+        }
+    }
+}
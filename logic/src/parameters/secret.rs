@@ -0,0 +1,50 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::ParameterError;
+
+/// A parameter that is filled in with the value of a secret
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SecretParameter {
+    pub name: String,
+}
+
+impl FromStr for SecretParameter {
+    type Err = ParameterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let secret_param_regex = r"@\{secret:(?P<name>[A-Za-z0-9_-]+)\}";
+        let re = Regex::new(secret_param_regex).map_err(|e| {
+            ParameterError::InvalidRegex(secret_param_regex.to_string(), e.to_string())
+        })?;
+
+        if let Some(caps) = re.captures(s) {
+            return Ok(SecretParameter {
+                name: caps["name"].to_string(),
+            });
+        }
+        Err(ParameterError::InvalidParameter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let ret = SecretParameter::from_str("@{secret:api_token}");
+        assert_eq!(ret.unwrap().name, "api_token");
+
+        let ret = SecretParameter::from_str("@{secret:db-password-2}");
+        assert_eq!(ret.unwrap().name, "db-password-2");
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        for s in ["@{secret}", "@{secret:}", "@{secret:my token}", "@{string}"] {
+            assert!(SecretParameter::from_str(s).is_err());
+        }
+    }
+}
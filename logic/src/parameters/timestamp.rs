@@ -0,0 +1,76 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::{populator::Clock, GenerateTimeValues, ParameterError};
+
+/// A parameter that is the current Unix time, in seconds for `@{timestamp}`
+/// or `@{timestamp[s]}` and in milliseconds for `@{timestamp[ms]}`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TimestampParameter {
+    pub millis: bool,
+}
+
+impl FromStr for TimestampParameter {
+    type Err = ParameterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let timestamp_param_regex = r"^@\{timestamp(?:\[(?P<unit>s|ms)\])?\}$";
+        let re = Regex::new(timestamp_param_regex).map_err(|e| {
+            ParameterError::InvalidRegex(timestamp_param_regex.to_string(), e.to_string())
+        })?;
+
+        if let Some(caps) = re.captures(s) {
+            let millis = caps.name("unit").map(|unit| unit.as_str()) == Some("ms");
+            return Ok(TimestampParameter { millis });
+        }
+        Err(ParameterError::InvalidParameter)
+    }
+}
+
+impl GenerateTimeValues for TimestampParameter {
+    fn generate_time_value(&self, clock: &dyn Clock) -> String {
+        let now = clock.now();
+        if self.millis {
+            now.timestamp_millis().to_string()
+        } else {
+            now.timestamp().to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::populator::tests::MockClock;
+
+    #[test]
+    fn test_from_str() {
+        let ret = TimestampParameter::from_str("@{timestamp}");
+        assert!(!ret.unwrap().millis);
+
+        let ret = TimestampParameter::from_str("@{timestamp[s]}");
+        assert!(!ret.unwrap().millis);
+
+        let ret = TimestampParameter::from_str("@{timestamp[ms]}");
+        assert!(ret.unwrap().millis);
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        for s in ["@{timestamp[]}", "@{timestamp[ns]}", "@{time}", "@{now}"] {
+            assert!(TimestampParameter::from_str(s).is_err());
+        }
+    }
+
+    #[test]
+    fn test_generate_time_value() {
+        let clock = MockClock::new(1714555815);
+
+        let param = TimestampParameter::from_str("@{timestamp}").unwrap();
+        assert_eq!(param.generate_time_value(&clock), "1714555815");
+
+        let param = TimestampParameter::from_str("@{timestamp[ms]}").unwrap();
+        assert_eq!(param.generate_time_value(&clock), "1714555815000");
+    }
+}
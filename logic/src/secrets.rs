@@ -0,0 +1,518 @@
+//! # Secrets
+//!
+//! Secret values, such as API tokens and passwords, are kept in an encrypted
+//! file next to the config and used in commands through the `@{secret:name}`
+//! parameter. The file is encrypted with XChaCha20-Poly1305 using a key that
+//! is derived from a passphrase with Argon2id, so the secrets have to be
+//! unlocked with the passphrase before they can be used or changed.
+//!
+//! The secrets are shared by every stack. Their values are never written to
+//! the database, so they are not part of the usage history or of exports.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+use thiserror::Error;
+
+use crate::Logic;
+
+/// The version of the format of the secrets file
+const SECRETS_FILE_VERSION: u32 = 1;
+
+/// The longest name a secret can have
+const MAX_SECRET_NAME_LENGTH: usize = 64;
+
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+
+/// Authenticated along with the secrets, which ties the ciphertext to the
+/// version of the format it was written in
+const ASSOCIATED_DATA: &[u8] = b"cmdstack-secrets-v1";
+
+#[derive(Error, Debug)]
+pub enum SecretError {
+    #[error("Invalid secret name {0:?}: only letters, digits, '-' and '_' are allowed")]
+    InvalidName(String),
+    #[error("The secret {0} does not exist")]
+    NotFound(String),
+    #[error("The secrets are locked")]
+    Locked,
+    #[error("Incorrect passphrase")]
+    IncorrectPassphrase,
+    #[error("The secrets file is corrupted")]
+    Corrupted,
+    #[error("Unsupported secrets file version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Failed to derive the key from the passphrase: {0}")]
+    KeyDerivation(String),
+    #[error("Failed to encrypt the secrets")]
+    Encrypt,
+    #[error("Failed to locate default config directory")]
+    DefaultConfigDirectory,
+    #[error("Failed to access the secrets file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to parse the secrets file: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
+/// The cost of deriving the key from the passphrase. It is kept in the
+/// secrets file so that it can be raised without breaking existing files
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// The secrets file as it is stored on disk
+#[derive(Serialize, Deserialize)]
+struct SecretsFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Unlocked secrets. Every change is encrypted and written to the secrets
+/// file straight away
+pub struct SecretStore {
+    path: PathBuf,
+    kdf: KdfParams,
+    salt: [u8; SALT_LENGTH],
+    key: [u8; KEY_LENGTH],
+    secrets: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for SecretStore {
+    // Only the names are shown so that the values never end up in logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretStore")
+            .field("path", &self.path)
+            .field("names", &self.secrets.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecretStore {
+    /// Unlocks the secrets in a file. If there is no file yet, an empty store
+    /// is returned and the file is created when the first secret is set
+    pub fn open(path: &Path, passphrase: &str) -> Result<SecretStore, SecretError> {
+        Self::open_with_params(path, passphrase, KdfParams::default())
+    }
+
+    /// Same as [`SecretStore::open`], but a new file is created with the given
+    /// key derivation cost
+    pub(crate) fn open_with_params(
+        path: &Path,
+        passphrase: &str,
+        kdf: KdfParams,
+    ) -> Result<SecretStore, SecretError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut salt = [0u8; SALT_LENGTH];
+                OsRng.fill_bytes(&mut salt);
+                return Ok(SecretStore {
+                    path: path.to_path_buf(),
+                    kdf,
+                    salt,
+                    key: derive_key(passphrase, &salt, kdf)?,
+                    secrets: BTreeMap::new(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let file: SecretsFile = serde_json::from_str(&content)?;
+        if file.version != SECRETS_FILE_VERSION {
+            return Err(SecretError::UnsupportedVersion(file.version));
+        }
+        let salt = decode::<SALT_LENGTH>(&file.salt)?;
+        let nonce = decode::<NONCE_LENGTH>(&file.nonce)?;
+        let ciphertext = STANDARD
+            .decode(&file.ciphertext)
+            .map_err(|_| SecretError::Corrupted)?;
+
+        let key = derive_key(passphrase, &salt, file.kdf)?;
+        let plaintext = XChaCha20Poly1305::new(&key.into())
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: ASSOCIATED_DATA,
+                },
+            )
+            .map_err(|_| SecretError::IncorrectPassphrase)?;
+        let secrets = serde_json::from_slice(&plaintext).map_err(|_| SecretError::Corrupted)?;
+
+        Ok(SecretStore {
+            path: path.to_path_buf(),
+            kdf: file.kdf,
+            salt,
+            key,
+            secrets,
+        })
+    }
+
+    /// The names of the secrets in alphabetical order
+    pub fn names(&self) -> Vec<String> {
+        self.secrets.keys().cloned().collect()
+    }
+
+    /// The value of a secret
+    pub fn get(&self, name: &str) -> Result<&str, SecretError> {
+        self.secrets
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| SecretError::NotFound(name.to_string()))
+    }
+
+    /// Adds a secret, or replaces the value of an existing one
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), SecretError> {
+        validate_secret_name(name)?;
+        self.secrets.insert(name.to_string(), value.to_string());
+        self.save()
+    }
+
+    /// Deletes a secret
+    pub fn remove(&mut self, name: &str) -> Result<(), SecretError> {
+        if self.secrets.remove(name).is_none() {
+            return Err(SecretError::NotFound(name.to_string()));
+        }
+        self.save()
+    }
+
+    /// Replaces every secret value in the text with the parameter of the
+    /// secret. Longer values are replaced first so that a value that contains
+    /// another value is replaced as a whole
+    pub fn mask(&self, text: &str) -> String {
+        let mut secrets: Vec<(&String, &String)> = self
+            .secrets
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        if secrets.is_empty() {
+            return text.to_string();
+        }
+        secrets.sort_by_key(|(_, value)| Reverse(value.len()));
+
+        let pattern = secrets
+            .iter()
+            .map(|(_, value)| regex::escape(value))
+            .collect::<Vec<String>>()
+            .join("|");
+        let re = match Regex::new(&pattern) {
+            Ok(re) => re,
+            // The pattern only fails to compile if it is too big, in which case
+            // the values are replaced one at a time
+            Err(_) => {
+                return secrets
+                    .iter()
+                    .fold(text.to_string(), |masked, (name, value)| {
+                        masked.replace(value.as_str(), &secret_parameter(name))
+                    })
+            }
+        };
+
+        re.replace_all(text, |caps: &Captures| {
+            let matched = &caps[0];
+            secrets
+                .iter()
+                .find(|(_, value)| value.as_str() == matched)
+                .map(|(name, _)| secret_parameter(name))
+                .unwrap_or_default()
+        })
+        .into_owned()
+    }
+
+    /// Encrypts the secrets and replaces the secrets file with them
+    fn save(&self) -> Result<(), SecretError> {
+        let plaintext = serde_json::to_vec(&self.secrets)?;
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = XChaCha20Poly1305::new(&self.key.into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: ASSOCIATED_DATA,
+                },
+            )
+            .map_err(|_| SecretError::Encrypt)?;
+
+        let file = SecretsFile {
+            version: SECRETS_FILE_VERSION,
+            kdf: self.kdf,
+            salt: STANDARD.encode(self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        write_private_file(&self.path, serde_json::to_string_pretty(&file)?.as_bytes())
+    }
+}
+
+/// Returns the path of the secrets file, creating its directory if needed
+pub fn secrets_file_path() -> Result<PathBuf, SecretError> {
+    let mut path = dirs::config_dir().ok_or(SecretError::DefaultConfigDirectory)?;
+    path.push("cmdstack");
+    fs::create_dir_all(&path)?;
+    path.push("secrets.json");
+    Ok(path)
+}
+
+/// Whether any secrets have been stored yet
+pub fn secrets_exist() -> Result<bool, SecretError> {
+    Ok(secrets_file_path()?.exists())
+}
+
+/// Checks that a secret name can be used in a `@{secret:name}` parameter
+pub fn validate_secret_name(name: &str) -> Result<(), SecretError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SECRET_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(SecretError::InvalidName(name.to_string()))
+    }
+}
+
+fn secret_parameter(name: &str) -> String {
+    format!("@{{secret:{}}}", name)
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    kdf: KdfParams,
+) -> Result<[u8; KEY_LENGTH], SecretError> {
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(KEY_LENGTH))
+        .map_err(|e| SecretError::KeyDerivation(e.to_string()))?;
+    let mut key = [0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| SecretError::KeyDerivation(e.to_string()))?;
+    Ok(key)
+}
+
+fn decode<const N: usize>(encoded: &str) -> Result<[u8; N], SecretError> {
+    STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(SecretError::Corrupted)
+}
+
+/// Writes a file that only the user can read, replacing it in one step so that
+/// an interrupted write never leaves a partial file behind
+fn write_private_file(path: &Path, content: &[u8]) -> Result<(), SecretError> {
+    let temp_path = path.with_extension("json.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    io::Write::write_all(&mut options.open(&temp_path)?, content)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+impl Logic {
+    /// Unlocks the secrets with the passphrase. The first passphrase used
+    /// before any secret is stored becomes the passphrase of the secrets
+    pub fn unlock_secrets(&mut self, passphrase: &str) -> Result<(), SecretError> {
+        self.secrets = Some(SecretStore::open(&secrets_file_path()?, passphrase)?);
+        Ok(())
+    }
+
+    /// Forgets the unlocked secrets
+    pub fn lock_secrets(&mut self) {
+        self.secrets = None;
+    }
+
+    /// Takes over the secrets unlocked by another `Logic`, such as the one of
+    /// the stack that is being switched away from. The secrets are shared by
+    /// every stack, so they stay unlocked
+    pub fn take_secrets_from(&mut self, other: &mut Logic) {
+        self.secrets = other.secrets.take();
+    }
+
+    /// Whether the secrets are unlocked
+    pub fn secrets_unlocked(&self) -> bool {
+        self.secrets.is_some()
+    }
+
+    /// The names of the secrets in alphabetical order
+    pub fn secret_names(&self) -> Result<Vec<String>, SecretError> {
+        Ok(self.unlocked_secrets()?.names())
+    }
+
+    /// The value of a secret
+    pub fn secret_value(&self, name: &str) -> Result<&str, SecretError> {
+        self.unlocked_secrets()?.get(name)
+    }
+
+    /// Adds a secret, or replaces the value of an existing one
+    pub fn set_secret(&mut self, name: &str, value: &str) -> Result<(), SecretError> {
+        self.secrets
+            .as_mut()
+            .ok_or(SecretError::Locked)?
+            .set(name, value)
+    }
+
+    /// Deletes a secret
+    pub fn delete_secret(&mut self, name: &str) -> Result<(), SecretError> {
+        self.secrets
+            .as_mut()
+            .ok_or(SecretError::Locked)?
+            .remove(name)
+    }
+
+    /// Replaces the values of the unlocked secrets in the text with their
+    /// parameters
+    pub fn mask_secrets(&self, text: &str) -> String {
+        match &self.secrets {
+            Some(secrets) => secrets.mask(text),
+            None => text.to_string(),
+        }
+    }
+
+    fn unlocked_secrets(&self) -> Result<&SecretStore, SecretError> {
+        self.secrets.as_ref().ok_or(SecretError::Locked)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use data::dal::memory::MemoryStore;
+    use tempfile::tempdir;
+
+    /// Keeps the key derivation fast in tests
+    pub(crate) const TEST_KDF_PARAMS: KdfParams = KdfParams {
+        m_cost: 8,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_secrets_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+
+        let mut store = SecretStore::open_with_params(&path, "hunter2", TEST_KDF_PARAMS).unwrap();
+        assert!(store.names().is_empty());
+        assert!(!path.exists());
+
+        store.set("api_token", "s3cr3t-value").unwrap();
+        store.set("db-password", "pa55").unwrap();
+        store.remove("db-password").unwrap();
+        assert!(matches!(
+            store.remove("db-password"),
+            Err(SecretError::NotFound(_))
+        ));
+
+        // The file never contains the plaintext
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cr3t-value"));
+        assert!(!content.contains("api_token"));
+
+        let store = SecretStore::open(&path, "hunter2").unwrap();
+        assert_eq!(store.names(), vec!["api_token".to_string()]);
+        assert_eq!(store.get("api_token").unwrap(), "s3cr3t-value");
+        assert!(matches!(
+            store.get("missing"),
+            Err(SecretError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_secrets_wrong_passphrase() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+
+        let mut store = SecretStore::open_with_params(&path, "hunter2", TEST_KDF_PARAMS).unwrap();
+        store.set("api_token", "s3cr3t-value").unwrap();
+
+        assert!(matches!(
+            SecretStore::open(&path, "hunter3"),
+            Err(SecretError::IncorrectPassphrase)
+        ));
+    }
+
+    #[test]
+    fn test_secret_names() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+        let mut store = SecretStore::open_with_params(&path, "hunter2", TEST_KDF_PARAMS).unwrap();
+
+        for name in ["", "my token", "token}", "a:b", &"a".repeat(65)] {
+            assert!(matches!(
+                store.set(name, "value"),
+                Err(SecretError::InvalidName(_))
+            ));
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+        let mut store = SecretStore::open_with_params(&path, "hunter2", TEST_KDF_PARAMS).unwrap();
+        store.set("short", "abc").unwrap();
+        store.set("long", "abcdef").unwrap();
+        store.set("empty", "").unwrap();
+
+        assert_eq!(
+            store.mask("curl -u abc -H 'Token: abcdef' secret"),
+            "curl -u @{secret:short} -H 'Token: @{secret:long}' secret"
+        );
+        assert_eq!(store.mask("no secrets here"), "no secrets here");
+    }
+
+    #[test]
+    fn test_take_secrets_from() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+        let mut store = SecretStore::open_with_params(&path, "hunter2", TEST_KDF_PARAMS).unwrap();
+        store.set("api_token", "s3cr3t").unwrap();
+
+        let mut old_stack = Logic::new(MemoryStore::new()).unwrap();
+        old_stack.secrets = Some(store);
+        let mut new_stack = Logic::new(MemoryStore::new()).unwrap();
+        assert!(!new_stack.secrets_unlocked());
+
+        new_stack.take_secrets_from(&mut old_stack);
+        assert!(!old_stack.secrets_unlocked());
+        assert_eq!(new_stack.secret_value("api_token").unwrap(), "s3cr3t");
+    }
+}
@@ -0,0 +1,189 @@
+//! # Stacks
+//!
+//! Commands can be kept in separate stacks, each of which has its own
+//! database. The stack that is used is, in order of precedence, the one that
+//! was explicitly requested (e.g. with the `--stack` flag of the CLI), the one
+//! in the `CMDSTACK_STACK` environment variable or the active stack in the
+//! config.
+
+use data::dal::sqlite::{remove_database, stack_backup_dir, stack_db_path, stack_names};
+use data::dal::sqlite::{SqliteDbConnectionError, DEFAULT_STACK};
+use data::dal::sqlite_dal::SqliteDal;
+use std::path::Path;
+use std::{env, fs, io};
+use thiserror::Error;
+
+use crate::config::{Config, ConfigWriteError};
+use crate::Logic;
+
+/// The environment variable that selects the stack to use
+pub const STACK_ENV_VAR: &str = "CMDSTACK_STACK";
+
+/// The longest name a stack can have
+const MAX_STACK_NAME_LENGTH: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum StackError {
+    #[error("Invalid stack name {0:?}: only letters, digits, '-' and '_' are allowed")]
+    InvalidName(String),
+    #[error("The stack {0} already exists")]
+    AlreadyExists(String),
+    #[error("The stack {0} does not exist")]
+    NotFound(String),
+    #[error("The stack {0} is in use")]
+    InUse(String),
+    #[error("The default stack cannot be deleted")]
+    DeleteDefault,
+    #[error("Failed to access the stack database: {0}")]
+    Database(#[from] SqliteDbConnectionError),
+    #[error("Failed to delete the stack database: {0}")]
+    Delete(#[from] io::Error),
+    #[error("Failed to write config: {0}")]
+    Config(#[from] ConfigWriteError),
+}
+
+/// Returns the stack to use when the given stack is requested, which is
+/// `None` if no stack was requested
+pub fn resolve_stack(requested: Option<String>, config: &Config) -> String {
+    requested
+        .or_else(|| env::var(STACK_ENV_VAR).ok())
+        .filter(|stack| !stack.is_empty())
+        .unwrap_or_else(|| config.active_stack.clone())
+}
+
+/// Checks that a stack name can be used as the name of its database file
+pub fn validate_stack_name(name: &str) -> Result<(), StackError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_STACK_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(StackError::InvalidName(name.to_string()))
+    }
+}
+
+/// Returns the names of every stack in alphabetical order
+pub fn list_stacks() -> Result<Vec<String>, StackError> {
+    Ok(stack_names()?)
+}
+
+/// Returns an error if the stack does not exist
+pub fn check_stack_exists(name: &str) -> Result<(), StackError> {
+    validate_stack_name(name)?;
+    if list_stacks()?.iter().any(|stack| stack == name) {
+        Ok(())
+    } else {
+        Err(StackError::NotFound(name.to_string()))
+    }
+}
+
+/// Creates a stack without any commands
+pub async fn create_stack(name: &str) -> Result<(), StackError> {
+    validate_stack_name(name)?;
+    if list_stacks()?.iter().any(|stack| stack == name) {
+        return Err(StackError::AlreadyExists(name.to_string()));
+    }
+
+    // Connecting to the database of the stack creates it
+    SqliteDal::new_for_stack(name).await?;
+
+    Ok(())
+}
+
+impl Logic {
+    /// The name of the stack the commands are kept in
+    pub fn stack(&self) -> &str {
+        &self.stack
+    }
+
+    /// Makes a stack the active stack in the config, which is used whenever no
+    /// other stack is requested
+    pub fn use_stack(&mut self, name: &str) -> Result<(), StackError> {
+        check_stack_exists(name)?;
+        self.config.active_stack = name.to_string();
+        Ok(self.config.write()?)
+    }
+
+    /// Permanently deletes a stack along with all of its commands and backups.
+    /// Neither the default stack nor the stack that is in use can be deleted
+    pub fn delete_stack(&self, name: &str) -> Result<(), StackError> {
+        if name == DEFAULT_STACK {
+            return Err(StackError::DeleteDefault);
+        }
+        if name == self.stack || name == self.config.active_stack {
+            return Err(StackError::InUse(name.to_string()));
+        }
+        check_stack_exists(name)?;
+
+        remove_stack_files(&stack_db_path(name)?, &stack_backup_dir(name)?)
+    }
+}
+
+/// Deletes the database of a stack and the directory of its backups, which
+/// would otherwise be picked up by a new stack with the same name
+fn remove_stack_files(db_path: &Path, backup_dir: &Path) -> Result<(), StackError> {
+    remove_database(db_path)?;
+    match fs::remove_dir_all(backup_dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_stack_name() {
+        assert!(validate_stack_name("work").is_ok());
+        assert!(validate_stack_name("client-x_2").is_ok());
+
+        for name in ["", "../work", "my stack", "work.sqlite", &"a".repeat(65)] {
+            assert!(matches!(
+                validate_stack_name(name),
+                Err(StackError::InvalidName(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_stack_files() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("work.sqlite");
+        SqliteDal::new_with_custom_path(db_path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let backup_dir = dir.path().join("backups").join("work");
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(backup_dir.join("auto-backup.sqlite"), "").unwrap();
+
+        remove_stack_files(&db_path, &backup_dir).unwrap();
+        assert!(!db_path.exists());
+        assert!(!backup_dir.exists());
+
+        // A stack without any backups can be deleted too
+        SqliteDal::new_with_custom_path(db_path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        remove_stack_files(&db_path, &backup_dir).unwrap();
+        assert!(!db_path.exists());
+    }
+
+    #[test]
+    fn test_resolve_stack() {
+        let config = Config {
+            active_stack: "personal".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(resolve_stack(Some("work".to_string()), &config), "work");
+        if env::var(STACK_ENV_VAR).is_err() {
+            assert_eq!(resolve_stack(None, &config), "personal");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use data::dal::SelectAllCommandsError;
+use data::models::Command;
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use thiserror::Error;
+
+use crate::backup::BackupError;
+use crate::Logic;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Error, Debug)]
+pub enum ListTrashError {
+    #[error("Failed to list the trash: {0}")]
+    Database(#[from] SelectAllCommandsError),
+}
+
+#[derive(Error, Debug)]
+pub enum RestoreCommandError {
+    #[error("Failed to restore command: {0}")]
+    Database(#[from] data::dal::UpdateCommandError),
+}
+
+#[derive(Error, Debug)]
+pub enum PurgeTrashError {
+    #[error("Failed to get the unix timestamp: {0}")]
+    UnixTimestamp(#[from] SystemTimeError),
+    #[error("Failed to purge the trash: {0}")]
+    Database(#[from] data::dal::DeleteCommandError),
+    #[error("Failed to list the trash: {0}")]
+    List(#[from] SelectAllCommandsError),
+    #[error("Failed to back up the database before purging the trash: {0}")]
+    Backup(#[from] BackupError),
+}
+
+impl Logic {
+    /// Handles listing the commands in the trash, most recently deleted first
+    pub async fn list_trash(&self) -> Result<Vec<Command>, ListTrashError> {
+        Ok(self.dal.get_trashed_commands().await?)
+    }
+
+    /// Handles moving a command out of the trash
+    pub async fn restore_command(&self, command_id: i64) -> Result<(), RestoreCommandError> {
+        Ok(self.dal.restore_command(command_id).await?)
+    }
+
+    /// Handles permanently deleting commands from the trash. Unless `all` is
+    /// set, only the commands that have been in the trash for longer than the
+    /// retention period are deleted. Returns the number of deleted commands.
+    ///
+    /// The database is backed up automatically first, unless the trash is empty
+    pub async fn purge_trash(&self, all: bool) -> Result<u64, PurgeTrashError> {
+        if all {
+            if !self.dal.get_trashed_commands().await?.is_empty() {
+                self.auto_backup().await?;
+            }
+            return Ok(self.dal.purge_trash(i64::MAX).await?);
+        }
+        self.purge_expired_trash().await
+    }
+
+    /// Permanently deletes the commands that have been in the trash for longer
+    /// than the retention period, after backing up the database if there are
+    /// any
+    pub(crate) async fn purge_expired_trash(&self) -> Result<u64, PurgeTrashError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let retention = self.config.trash_retention_days as i64 * SECONDS_PER_DAY;
+        let deleted_before = now - retention;
+
+        let has_expired = self
+            .dal
+            .get_trashed_commands()
+            .await?
+            .iter()
+            .any(|command| command.deleted_at.is_some_and(|t| t < deleted_before));
+        if !has_expired {
+            return Ok(0);
+        }
+
+        self.auto_backup().await?;
+        Ok(self.dal.purge_trash(deleted_before).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data::dal::memory::MemoryStore;
+    use data::models::{CommandQuery, InternalCommand};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trash_restore_and_purge() {
+        let logic = Logic::new(MemoryStore::new()).unwrap();
+
+        for command in ["ls", "pwd"] {
+            let result = logic
+                .add_command(InternalCommand {
+                    command: command.to_string(),
+                    tags: Vec::new(),
+                    note: None,
+                    favourite: false,
+                })
+                .await;
+            assert!(result.is_ok());
+        }
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        let ls_id = commands[0].id;
+        let pwd_id = commands[1].id;
+
+        // Deleted commands are kept in the trash
+        assert!(logic.delete_command(ls_id).await.is_ok());
+        assert!(logic.delete_command(pwd_id).await.is_ok());
+        assert!(logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands
+            .is_empty());
+        assert_eq!(logic.list_trash().await.unwrap().len(), 2);
+
+        // Restored commands are listed again
+        assert!(logic.restore_command(ls_id).await.is_ok());
+        assert!(logic.restore_command(ls_id).await.is_err());
+        let commands = logic
+            .list_commands(CommandQuery::default())
+            .await
+            .unwrap()
+            .commands;
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].internal_command.command, "ls");
+
+        // Nothing has been in the trash for longer than the retention period
+        assert_eq!(logic.purge_trash(false).await.unwrap(), 0);
+        assert_eq!(logic.purge_trash(true).await.unwrap(), 1);
+        assert!(logic.list_trash().await.unwrap().is_empty());
+        assert!(logic.restore_command(pwd_id).await.is_err());
+    }
+}
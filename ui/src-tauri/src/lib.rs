@@ -1,24 +1,39 @@
-use std::sync::RwLock;
-
-use data::models::{Command, InternalCommand};
+use data::dal::store::ChangeWatcher;
+use data::models::{
+    Command, CommandCursor, CommandPage, CommandQuery, CommandRevision, InternalCommand,
+    InternalCommandUsage, UsageAction,
+};
 use itertools::interleave;
 use logic::{
     command::{
-        AddCommandError, DeleteCommandError, ListCommandError, SearchCommandArgs,
-        SearchCommandError, UpdateCommandError,
+        AddCommandError, AddCommandOutcome, DeleteCommandError, GetCommandError, ListCommandError,
+        SearchCommandArgs, SearchCommandError, UpdateCommandError,
     },
     config::{Config, ConfigReadError, ConfigWriteError, UiDefaultTerminal},
+    duplicates::{DedupeError, FindDuplicatesError},
+    history::{CommandHistoryError, RevertCommandError, RevisionEntry, UndoError},
     parameters::{parser::SerializableParameter, ParameterError},
+    secrets::SecretError,
+    stack::{list_stacks, StackError},
+    trash::{ListTrashError, PurgeTrashError, RestoreCommandError},
     Logic, LogicInitError,
 };
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use std::time::Duration;
+use tauri::{async_runtime::RwLock, AppHandle, Emitter, Manager, State};
 use thiserror::Error;
 
 pub struct Ui {
     logic: RwLock<Logic>,
 }
 
+/// The event that tells the frontend to reload the commands, because the
+/// database of the stack has changed
+const COMMANDS_CHANGED_EVENT: &str = "commands-changed";
+
+/// How often the database is checked for changes made outside of the app
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Error, Debug)]
 pub enum UiError {
     #[error("Failed to initialize logic")]
@@ -27,20 +42,46 @@ pub enum UiError {
     Parse(#[from] ParameterError),
     #[error("Failed to delete command")]
     DeleteCommand(#[from] DeleteCommandError),
+    #[error("The command is a duplicate of the command with ID {0}")]
+    DuplicateCommand(i64),
     #[error("Failed to add command")]
     AddCommand(#[from] AddCommandError),
+    #[error("No command with ID {0}")]
+    CommandNotFound(i64),
+    #[error("Failed to fetch command")]
+    GetCommand(#[from] GetCommandError),
     #[error("Failed to list commands")]
     ListCommand(#[from] ListCommandError),
     #[error("Failed to update command")]
     UpdateCommand(#[from] UpdateCommandError),
     #[error("Failed to search command")]
     SearchCommand(#[from] SearchCommandError),
+    #[error("Failed to list the trash")]
+    ListTrash(#[from] ListTrashError),
+    #[error("Failed to restore command")]
+    RestoreCommand(#[from] RestoreCommandError),
+    #[error("Failed to empty the trash")]
+    PurgeTrash(#[from] PurgeTrashError),
+    #[error("Failed to fetch command history")]
+    CommandHistory(#[from] CommandHistoryError),
+    #[error("Failed to revert command")]
+    RevertCommand(#[from] RevertCommandError),
+    #[error("Failed to undo the last change")]
+    Undo(#[from] UndoError),
+    #[error("Failed to find duplicate commands")]
+    FindDuplicates(#[from] FindDuplicatesError),
+    #[error("Failed to merge duplicate commands")]
+    Dedupe(#[from] DedupeError),
+    #[error("Incorrect passphrase")]
+    IncorrectPassphrase,
+    #[error("Failed to unlock secrets")]
+    Secret(#[from] SecretError),
+    #[error("Failed to switch stacks")]
+    Stack(#[from] StackError),
     #[error("Failed to write config")]
     WriteConfig(#[from] ConfigWriteError),
     #[error("Failed to read config")]
     ReadConfig(#[from] ConfigReadError),
-    #[error("Failed to obtain lock to complete the required action")]
-    Race,
     #[error("Failed to execute command in terminal")]
     ExecuteCommand,
 }
@@ -58,56 +99,89 @@ impl serde::Serialize for UiError {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DisplayCommand {
     pub id: i64,
+    pub uuid: String,
     pub last_used: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
     pub command: String,
-    pub tag: Option<String>,
+    pub tags: Vec<String>,
     pub note: Option<String>,
     pub favourite: bool,
+    pub deleted_at: Option<i64>,
 }
 
 impl From<&Command> for DisplayCommand {
     fn from(c: &Command) -> Self {
         DisplayCommand {
             id: c.id,
+            uuid: c.uuid.to_string(),
             last_used: c.last_used,
+            created_at: c.created_at,
+            updated_at: c.updated_at,
             command: c.internal_command.command.clone(),
-            tag: c.internal_command.tag.clone(),
+            tags: c.internal_command.tags.clone(),
             note: c.internal_command.note.clone(),
             favourite: c.internal_command.favourite,
+            deleted_at: c.deleted_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayCommandPage {
+    pub commands: Vec<DisplayCommand>,
+    pub next_cursor: Option<CommandCursor>,
+}
+
+impl From<CommandPage> for DisplayCommandPage {
+    fn from(page: CommandPage) -> Self {
+        DisplayCommandPage {
+            commands: page.commands.iter().map(DisplayCommand::from).collect(),
+            next_cursor: page.next_cursor,
         }
     }
 }
 
 #[tauri::command]
-fn list_commands(state: State<Ui>) -> Result<Vec<DisplayCommand>, UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic
-            .list_commands(false, false)?
-            .iter()
-            .map(DisplayCommand::from)
-            .collect());
+async fn get_command(command_id: i64, state: State<'_, Ui>) -> Result<DisplayCommand, UiError> {
+    let logic = state.logic.read().await;
+    match logic.get_command(command_id).await {
+        Ok(command) => Ok(DisplayCommand::from(&command)),
+        Err(GetCommandError::NotFound(id)) => Err(UiError::CommandNotFound(id)),
+        Err(e) => Err(e.into()),
     }
-    Err(UiError::Race)
 }
 
 #[tauri::command]
-fn add_command(command: InternalCommand, state: State<Ui>) -> Result<(), UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.add_command(command)?);
+async fn list_commands(
+    query: Option<CommandQuery>,
+    state: State<'_, Ui>,
+) -> Result<DisplayCommandPage, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.list_commands(query.unwrap_or_default()).await?.into())
+}
+
+#[tauri::command]
+async fn add_command(
+    command: InternalCommand,
+    state: State<'_, Ui>,
+) -> Result<AddCommandOutcome, UiError> {
+    let logic = state.logic.read().await;
+    match logic.add_command(command).await {
+        Ok(outcome) => Ok(outcome),
+        Err(AddCommandError::Duplicate(id)) => Err(UiError::DuplicateCommand(id)),
+        Err(e) => Err(e.into()),
     }
-    Err(UiError::Race)
 }
 
 #[tauri::command]
-fn update_command(
+async fn update_command(
     command_id: i64,
     command: InternalCommand,
-    state: State<Ui>,
+    state: State<'_, Ui>,
 ) -> Result<(), UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.update_command(command_id, command)?);
-    }
-    Err(UiError::Race)
+    let logic = state.logic.read().await;
+    Ok(logic.update_command(command_id, command).await?)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,132 +190,231 @@ pub struct DeleteCommand {
 }
 
 #[tauri::command]
-fn delete_command(command: DeleteCommand, state: State<Ui>) -> Result<(), UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.delete_command(command.id)?);
-    }
-    Err(UiError::Race)
+async fn delete_command(command: DeleteCommand, state: State<'_, Ui>) -> Result<(), UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.delete_command(command.id).await?)
+}
+
+#[tauri::command]
+async fn list_trash(state: State<'_, Ui>) -> Result<Vec<DisplayCommand>, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic
+        .list_trash()
+        .await?
+        .iter()
+        .map(DisplayCommand::from)
+        .collect())
+}
+
+#[tauri::command]
+async fn restore_command(command_id: i64, state: State<'_, Ui>) -> Result<(), UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.restore_command(command_id).await?)
+}
+
+#[tauri::command]
+async fn purge_trash(all: bool, state: State<'_, Ui>) -> Result<u64, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.purge_trash(all).await?)
+}
+
+#[tauri::command]
+async fn find_duplicate_commands(
+    state: State<'_, Ui>,
+) -> Result<Vec<Vec<DisplayCommand>>, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic
+        .find_duplicate_commands()
+        .await?
+        .iter()
+        .map(|group| group.iter().map(DisplayCommand::from).collect())
+        .collect())
+}
+
+#[tauri::command]
+async fn dedupe_commands(state: State<'_, Ui>) -> Result<u64, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.dedupe_commands().await?)
+}
+
+#[tauri::command]
+async fn get_command_history(
+    command_id: i64,
+    state: State<'_, Ui>,
+) -> Result<Vec<RevisionEntry>, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.get_command_history(command_id).await?)
+}
+
+#[tauri::command]
+async fn revert_command(
+    command_id: i64,
+    revision_id: i64,
+    state: State<'_, Ui>,
+) -> Result<(), UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.revert_command(command_id, revision_id).await?)
+}
+
+#[tauri::command]
+async fn undo_last_change(state: State<'_, Ui>) -> Result<Option<CommandRevision>, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.undo_last_change().await?)
 }
 
 #[tauri::command]
-fn parse_parameters(
+async fn parse_parameters(
     command: String,
-    state: State<Ui>,
+    state: State<'_, Ui>,
 ) -> Result<(Vec<String>, Vec<SerializableParameter>), UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.parse_parameters(command)?);
-    }
-    Err(UiError::Race)
+    let logic = state.logic.read().await;
+    Ok(logic.parse_parameters(command)?)
 }
 
 #[tauri::command]
-fn generate_parameters(
+async fn generate_parameters(
     command: String,
     blank_param_values: Vec<String>,
-    state: State<Ui>,
+    state: State<'_, Ui>,
 ) -> Result<(String, Vec<String>), UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.generate_parameters(command, blank_param_values)?);
-    }
-    Err(UiError::Race)
+    let logic = state.logic.read().await;
+    Ok(logic.generate_parameters(command, blank_param_values)?)
 }
 
 #[tauri::command]
-fn replace_parameters(
+async fn replace_parameters(
     command: String,
     param_values: Vec<String>,
-    state: State<Ui>,
+    state: State<'_, Ui>,
 ) -> Result<String, UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.replace_parameters(command, param_values)?);
-    }
-    Err(UiError::Race)
+    let logic = state.logic.read().await;
+    Ok(logic.replace_parameters(command, param_values)?)
 }
 
 #[tauri::command]
-fn index_blank_parameters(command: String, state: State<Ui>) -> Result<String, UiError> {
-    if let Ok(logic) = state.logic.read() {
-        let (other_strs, indexed_blank_params) = logic.index_parameters_for_display(&command);
-
-        let formatted_command: String = interleave(other_strs, indexed_blank_params)
-            .collect::<Vec<String>>()
-            .join("");
-        return Ok(formatted_command);
-    }
-    Err(UiError::Race)
+async fn index_blank_parameters(command: String, state: State<'_, Ui>) -> Result<String, UiError> {
+    let logic = state.logic.read().await;
+    let (other_strs, indexed_blank_params) = logic.index_parameters_for_display(&command);
+
+    let formatted_command: String = interleave(other_strs, indexed_blank_params)
+        .collect::<Vec<String>>()
+        .join("");
+    Ok(formatted_command)
 }
 
 #[tauri::command]
-fn search_commands(search: String, state: State<Ui>) -> Result<Vec<DisplayCommand>, UiError> {
-    if let Ok(logic) = state.logic.write() {
-        let commands = logic
-            .search_command(SearchCommandArgs {
-                command: if search.is_empty() {
-                    None
-                } else {
-                    Some(search)
-                },
-                tag: None,
-                order_by_recently_used: false,
-                favourites_only: false,
-            })?
-            .iter()
-            .map(DisplayCommand::from)
-            .collect();
-
-        return Ok(commands);
-    }
-    Err(UiError::Race)
+async fn search_commands(
+    search: String,
+    query: Option<CommandQuery>,
+    state: State<'_, Ui>,
+) -> Result<DisplayCommandPage, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic
+        .search_command(SearchCommandArgs {
+            command: if search.is_empty() {
+                None
+            } else {
+                Some(search)
+            },
+            tag: None,
+            query: query.unwrap_or_default(),
+        })
+        .await?
+        .into())
 }
 
 #[tauri::command]
-fn read_config(state: State<Ui>) -> Result<Config, UiError> {
-    if let Ok(mut logic) = state.logic.write() {
-        logic.config = Config::read()?;
-        return Ok(logic.config);
-    }
-    Err(UiError::Race)
+async fn read_config(state: State<'_, Ui>) -> Result<Config, UiError> {
+    let mut logic = state.logic.write().await;
+    logic.config = Config::read()?;
+    Ok(logic.config.clone())
+}
+
+#[tauri::command]
+async fn write_config(config: Config, state: State<'_, Ui>) -> Result<(), UiError> {
+    let mut logic = state.logic.write().await;
+    logic.config = config;
+    Ok(logic.config.write()?)
 }
 
 #[tauri::command]
-fn write_config(config: Config, state: State<Ui>) -> Result<(), UiError> {
-    if let Ok(mut logic) = state.logic.write() {
-        logic.config = config;
-        return Ok(logic.config.write()?);
+async fn secrets_unlocked(state: State<'_, Ui>) -> Result<bool, UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic.secrets_unlocked())
+}
+
+#[tauri::command]
+async fn unlock_secrets(passphrase: String, state: State<'_, Ui>) -> Result<(), UiError> {
+    let mut logic = state.logic.write().await;
+    match logic.unlock_secrets(&passphrase) {
+        Ok(()) => Ok(()),
+        Err(SecretError::IncorrectPassphrase) => Err(UiError::IncorrectPassphrase),
+        Err(e) => Err(e.into()),
     }
-    Err(UiError::Race)
 }
 
 #[tauri::command]
-fn execute_in_terminal(command: String, state: State<Ui>) -> Result<(), UiError> {
+async fn lock_secrets(state: State<'_, Ui>) -> Result<(), UiError> {
+    let mut logic = state.logic.write().await;
+    logic.lock_secrets();
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StackList {
+    pub stacks: Vec<String>,
+    pub active: String,
+}
+
+#[tauri::command]
+async fn get_stacks(state: State<'_, Ui>) -> Result<StackList, UiError> {
+    let logic = state.logic.read().await;
+    Ok(StackList {
+        stacks: list_stacks()?,
+        active: logic.stack().to_string(),
+    })
+}
+
+#[tauri::command]
+async fn switch_stack(name: String, state: State<'_, Ui>) -> Result<(), UiError> {
+    let mut new_logic = Logic::try_with_stack(Some(name.clone())).await?;
+    new_logic.use_stack(&name)?;
+    let mut logic = state.logic.write().await;
+    // The secrets are shared by every stack, so switching keeps them unlocked
+    new_logic.take_secrets_from(&mut logic);
+    *logic = new_logic;
+    Ok(())
+}
+
+#[tauri::command]
+async fn execute_in_terminal(command: String, state: State<'_, Ui>) -> Result<(), UiError> {
     let mut cmd = std::process::Command::new("osascript");
-    if let Ok(logic) = state.logic.read() {
-        match logic.config.default_terminal {
-            UiDefaultTerminal::Terminal => {
-                cmd.args([
-                    "-e",
-                    &format!(
-                        "tell application \"Terminal\" to activate do script \"{}\" in window 1",
-                        command
-                    ),
-                ]);
-            }
-            UiDefaultTerminal::Iterm => {
-                cmd.args([
-                    "-e",
-                    "tell application \"iTerm\"",
-                    "-e",
-                    "tell current session of current window",
-                    "-e",
-                    &format!("write text \"{}\"", command),
-                    "-e",
-                    "end tell",
-                    "-e",
-                    "activate",
-                    "-e",
-                    "end tell",
-                ]);
-            }
+    let logic = state.logic.read().await;
+    match logic.config.default_terminal {
+        UiDefaultTerminal::Terminal => {
+            cmd.args([
+                "-e",
+                &format!(
+                    "tell application \"Terminal\" to activate do script \"{}\" in window 1",
+                    command
+                ),
+            ]);
+        }
+        UiDefaultTerminal::Iterm => {
+            cmd.args([
+                "-e",
+                "tell application \"iTerm\"",
+                "-e",
+                "tell current session of current window",
+                "-e",
+                &format!("write text \"{}\"", command),
+                "-e",
+                "end tell",
+                "-e",
+                "activate",
+                "-e",
+                "end tell",
+            ]);
         }
     }
     cmd.spawn().map_err(|_| UiError::ExecuteCommand)?;
@@ -249,16 +422,58 @@ fn execute_in_terminal(command: String, state: State<Ui>) -> Result<(), UiError>
 }
 
 #[tauri::command]
-fn update_command_last_used(command_id: i64, state: State<Ui>) -> Result<(), UiError> {
-    if let Ok(logic) = state.logic.write() {
-        return Ok(logic.update_command_last_used_prop(command_id)?);
+async fn update_command_last_used(
+    command_id: i64,
+    action: UsageAction,
+    command: String,
+    state: State<'_, Ui>,
+) -> Result<(), UiError> {
+    let logic = state.logic.read().await;
+    Ok(logic
+        .record_command_usage(
+            command_id,
+            InternalCommandUsage {
+                action,
+                working_directory: None,
+                resolved_command: command,
+            },
+        )
+        .await?)
+}
+
+/// Checks the database of the stack in use for changes, such as those made by
+/// the CLI, until the app exits, and tells the frontend whenever it changed.
+/// The watcher is replaced whenever another stack is switched to
+async fn watch_for_changes(app: AppHandle) {
+    let mut watched: Option<(String, Box<dyn ChangeWatcher>)> = None;
+
+    loop {
+        tokio::time::sleep(WATCH_INTERVAL).await;
+
+        {
+            let state = app.state::<Ui>();
+            let logic = state.logic.read().await;
+            let stack = logic.stack();
+            if watched.as_ref().map(|(watched, _)| watched.as_str()) != Some(stack) {
+                watched = logic
+                    .watch_changes()
+                    .await
+                    .ok()
+                    .map(|watcher| (stack.to_string(), watcher));
+            }
+        }
+
+        if let Some((_, watcher)) = watched.as_mut() {
+            if watcher.has_changed().await.unwrap_or(false) {
+                let _ = app.emit(COMMANDS_CHANGED_EVENT, ());
+            }
+        }
     }
-    Err(UiError::Race)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let logic = Logic::try_default()
+    let logic = tauri::async_runtime::block_on(Logic::try_default())
         .map_err(|e| panic!("Failed to initialize Logic: {}", e))
         .unwrap();
 
@@ -267,10 +482,23 @@ pub fn run() {
             logic: logic.into(),
         })
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            tauri::async_runtime::spawn(watch_for_changes(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
+            get_command,
             list_commands,
             add_command,
             delete_command,
+            list_trash,
+            restore_command,
+            purge_trash,
+            find_duplicate_commands,
+            dedupe_commands,
+            get_command_history,
+            revert_command,
+            undo_last_change,
             generate_parameters,
             replace_parameters,
             parse_parameters,
@@ -279,6 +507,11 @@ pub fn run() {
             search_commands,
             read_config,
             write_config,
+            get_stacks,
+            switch_stack,
+            secrets_unlocked,
+            unlock_secrets,
+            lock_secrets,
             update_command_last_used,
             execute_in_terminal
         ])
@@ -17,6 +17,8 @@ import {
 } from '@/components/ui/form';
 import { Input } from '@/components/ui/input';
 import { toast } from '@/hooks/use-toast';
+import { splitTags } from '@/lib/utils';
+import { AddCommandOutcome } from '@/types/command';
 import { useCommands } from '@/use-command';
 import { Plus } from 'lucide-react';
 import { Checkbox } from './ui/checkbox';
@@ -26,7 +28,7 @@ const FormSchema = z.object({
   command: z.string().min(1, {
     message: 'Command must be at least 1 character.',
   }),
-  tag: z.string(),
+  tags: z.string(),
   note: z.string(),
   favourite: z.boolean(),
 });
@@ -42,18 +44,23 @@ export function AddForm({ onSuccess }: AddFormProps) {
     resolver: zodResolver(FormSchema),
     defaultValues: {
       command: '',
-      tag: '',
+      tags: '',
       note: '',
       favourite: false,
     },
   });
 
   function onSubmit(data: z.infer<typeof FormSchema>) {
-    invoke('add_command', { command: data })
+    invoke<AddCommandOutcome>('add_command', {
+      command: { ...data, tags: splitTags(data.tags) },
+    })
       .then((res) => {
         console.log(res);
         toast({
-          title: 'Command added ✅ ',
+          title:
+            'Merged' in res
+              ? 'Command merged into its duplicate ✅ '
+              : 'Command added ✅ ',
         });
 
         refreshCommands();
@@ -93,20 +100,20 @@ export function AddForm({ onSuccess }: AddFormProps) {
         />
         <FormField
           control={form.control}
-          name="tag"
+          name="tags"
           render={({ field }) => (
             <FormItem>
-              <FormLabel>Tag</FormLabel>
+              <FormLabel>Tags</FormLabel>
               <FormControl>
                 <Input
-                  placeholder="SQL/INSERT"
+                  placeholder="SQL/INSERT, users"
                   autoCapitalize="off"
                   autoCorrect="off"
                   {...field}
                 />
               </FormControl>
               <FormDescription>
-                This is your tag for the command.
+                These are the comma-separated tags for the command.
               </FormDescription>
               <FormMessage />
             </FormItem>
@@ -1,9 +1,18 @@
 import { Button } from '@/components/ui/button';
 import { Separator } from '@/components/ui/separator';
 import { toast } from '@/hooks/use-toast';
-import { cn } from '@/lib/utils';
+import { cn, splitTags } from '@/lib/utils';
 import { Command } from '@/types/command';
-import { Parameter, ParameterType } from '@/types/parameter';
+import {
+  Parameter,
+  ParameterType,
+  countInputs,
+  inputDefaults,
+  inputIndexes,
+  isBlank,
+  isInput,
+  unnamed,
+} from '@/types/parameter';
 import { useCommands } from '@/use-command';
 import { zodResolver } from '@hookform/resolvers/zod';
 import { invoke } from '@tauri-apps/api/core';
@@ -29,6 +38,7 @@ import { Textarea } from '../ui/textarea';
 import { Tooltip, TooltipContent, TooltipTrigger } from '../ui/tooltip';
 import { ParamViewer } from './param-viewer';
 import { UseCommandBox } from './use-command-box';
+import { UnlockSecrets } from './unlock-secrets';
 import { Input } from '../ui/input';
 
 interface CommandDisplayProps {
@@ -39,7 +49,7 @@ const FormSchema = z.object({
   command: z.string().min(1, {
     message: 'Command must be at least 1 character.',
   }),
-  tag: z.string(),
+  tags: z.string(),
   note: z.string(),
   favourite: z.boolean(),
 });
@@ -56,7 +66,7 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
     resolver: zodResolver(FormSchema),
     values: {
       command: command ? command.command : '',
-      tag: command && command.tag ? command.tag : '',
+      tags: command ? command.tags.join(', ') : '',
       note: command && command.note ? command.note : '',
       favourite: command ? command.favourite : false,
     },
@@ -67,7 +77,10 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
   const commandRef = useRef<HTMLTextAreaElement | null>(null);
 
   function onSubmit(data: z.infer<typeof FormSchema>) {
-    invoke('update_command', { commandId: command?.id, command: data })
+    invoke('update_command', {
+      commandId: command?.id,
+      command: { ...data, tags: splitTags(data.tags) },
+    })
       .then((res) => {
         console.log(res);
         toast({
@@ -110,6 +123,7 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
   const [generatedValues, setGeneratedValues] = useState<string[]>([]);
   const [blankParamValues, setBlankParamValues] = useState<string[]>([]);
   const [indexedCommand, setIndexedCommand] = useState<string>('');
+  const [secretsLocked, setSecretsLocked] = useState<boolean>(false);
 
   useEffect(() => {
     if (command) {
@@ -129,12 +143,19 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
       })
         .then((res) => {
           const parameters = res[1];
-          const numberOfBlankParameters = parameters.filter(
-            (p) => p.type == ParameterType.Blank,
-          ).length;
 
-          setBlankParamValues(Array(numberOfBlankParameters).fill(''));
+          // blank parameters start out with their defaults
+          setBlankParamValues(inputDefaults(parameters));
           setParameters(res[1]);
+
+          if (
+            parameters.some((p) => unnamed(p).type == ParameterType.Secret)
+          ) {
+            return invoke<boolean>('secrets_unlocked').then((unlocked) =>
+              setSecretsLocked(!unlocked),
+            );
+          }
+          setSecretsLocked(false);
         })
         .catch((error) => console.error(error));
     }
@@ -142,10 +163,8 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
 
   // This effect handles generating parameters
   useEffect(() => {
-    if (command) {
-      const blanks = parameters.filter(
-        (p) => p.type == ParameterType.Blank,
-      ).length;
+    if (command && !secretsLocked) {
+      const blanks = countInputs(parameters);
       const blankParamValues: string[] = Array(blanks).fill('');
 
       invoke<[string, string[]]>('generate_parameters', {
@@ -158,16 +177,21 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
         })
         .catch((error) => console.error(error));
     }
-  }, [parameters, parameterRefreshNumber]);
+  }, [parameters, parameterRefreshNumber, secretsLocked]);
 
   // This effect handles updating the generated command based on user editing
   useEffect(() => {
     if (command && generatedValues.length == parameters.length) {
       // Replace blank parameters
-      let blankIndex = 0;
+      const indexes = inputIndexes(parameters);
+      const defaults = inputDefaults(parameters);
+      // an empty blank parameter falls back to its default, and a choice that
+      // has not been made to the one made at random
       const paramValues: string[] = parameters.map((p, index) =>
-        p.type === ParameterType.Blank
-          ? blankParamValues[blankIndex++]
+        isInput(p)
+          ? blankParamValues[indexes[index]] ||
+            defaults[indexes[index]] ||
+            generatedValues[index]
           : generatedValues[index],
       );
 
@@ -228,7 +252,6 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
     form.reset();
   }
 
-  const tagParts = command?.tag ? command.tag.split('/') : [];
 
   return (
     <Form {...form}>
@@ -242,14 +265,14 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
                     {editing.tag ? (
                       <FormField
                         control={form.control}
-                        name="tag"
+                        name="tags"
                         render={({ field }) => {
                           const { ref: fieldRef, ...rest } = field;
                           return (
                             <FormItem>
                               <FormControl>
                                 <Input
-                                  placeholder="Add a tag"
+                                  placeholder="Add comma-separated tags"
                                   {...rest}
                                   ref={(input) => {
                                     fieldRef(input);
@@ -264,30 +287,39 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
                           );
                         }}
                       />
-                    ) : command.tag ? (
-                      tagParts.map((tag, index) => (
-                        <>
-                          <Badge
-                            key={index}
-                            variant={
-                              index == tagParts.length - 1
-                                ? 'outline'
-                                : 'secondary'
-                            }
-                            className={cn(
-                              index !== tagParts.length - 1 &&
-                                'text-secondary-foreground/40',
-                            )}
-                          >
-                            {tag}
-                          </Badge>
-                          {index !== tagParts.length - 1 && (
-                            <span className="text-xs font-semibold px-1.5">
-                              /
-                            </span>
-                          )}
-                        </>
-                      ))
+                    ) : command.tags.length > 0 ? (
+                      <div className="flex flex-wrap items-center gap-3">
+                        {command.tags.map((fullTag) => {
+                          const tagParts = fullTag.split('/');
+                          return (
+                            <div key={fullTag} className="flex items-center">
+                              {tagParts.map((tag, index) => (
+                                <>
+                                  <Badge
+                                    key={index}
+                                    variant={
+                                      index == tagParts.length - 1
+                                        ? 'outline'
+                                        : 'secondary'
+                                    }
+                                    className={cn(
+                                      index !== tagParts.length - 1 &&
+                                        'text-secondary-foreground/40',
+                                    )}
+                                  >
+                                    {tag}
+                                  </Badge>
+                                  {index !== tagParts.length - 1 && (
+                                    <span className="text-xs font-semibold px-1.5">
+                                      /
+                                    </span>
+                                  )}
+                                </>
+                              ))}
+                            </div>
+                          );
+                        })}
+                      </div>
                     ) : (
                       <div
                         className="text-sm underline cursor-pointer w-fit"
@@ -426,9 +458,7 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
                         <div className="flex items-center h-9">
                           <Label htmlFor="parameters">Parameters</Label>
                           {/* Only allow regenerating parameters if there are non-blank parameters */}
-                          {parameters.some(
-                            (p) => p.type !== ParameterType.Blank,
-                          ) && (
+                          {parameters.some((p) => !isBlank(p)) && (
                             <Tooltip>
                               <TooltipTrigger asChild>
                                 <Button
@@ -446,12 +476,18 @@ export function CommandDisplay({ command }: CommandDisplayProps) {
                             </Tooltip>
                           )}
                         </div>
-                        <ParamViewer
-                          parameters={parameters}
-                          generatedValues={generatedValues}
-                          blankParamValues={blankParamValues}
-                          setBlankParam={setBlankParam}
-                        />
+                        {secretsLocked ? (
+                          <UnlockSecrets
+                            onUnlock={() => setSecretsLocked(false)}
+                          />
+                        ) : (
+                          <ParamViewer
+                            parameters={parameters}
+                            generatedValues={generatedValues}
+                            blankParamValues={blankParamValues}
+                            setBlankParam={setBlankParam}
+                          />
+                        )}
                       </>
                     )}
                     <FormField
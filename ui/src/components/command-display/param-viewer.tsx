@@ -1,6 +1,20 @@
-import { Parameter, ParameterType } from '@/types/parameter';
+import {
+  Parameter,
+  ParameterType,
+  inputIndexes,
+  isBlank,
+  isRepeated,
+  unnamed,
+} from '@/types/parameter';
 import { Label } from '../ui/label';
 import { Input } from '../ui/input';
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from '../ui/select';
 
 interface ParamViewerProps {
   parameters: Parameter[];
@@ -15,20 +29,45 @@ export function ParamViewer({
   blankParamValues,
   setBlankParam,
 }: ParamViewerProps) {
+  const indexes = inputIndexes(parameters);
+
   return (
     <div className="py-2 px-4 space-y-4 rounded-md border mb-4">
       {parameters.map((parameter, index) => {
-        if (parameter.type == ParameterType.Blank) {
-          let blankIndex = parameters
-            .slice(0, index)
-            .filter((p) => p.type == ParameterType.Blank).length;
+        // named parameters are only shown where they are first used
+        if (isRepeated(parameters, index)) {
+          return null;
+        } else if (isBlank(parameter)) {
+          // unnamed blank parameters are numbered as in the displayed command
+          const label =
+            parameter.type == ParameterType.Named
+              ? parameter.data.name!
+              : parameters
+                  .slice(0, index + 1)
+                  .filter((p) => p.type == ParameterType.Blank)
+                  .length.toString();
 
           return (
             <BlankParam
               key={index}
               setBlankParam={setBlankParam}
-              blankIndex={blankIndex}
-              blankParamValue={blankParamValues[blankIndex]}
+              label={label}
+              defaultValue={unnamed(parameter).data?.default ?? ''}
+              blankIndex={indexes[index]}
+              blankParamValue={blankParamValues[indexes[index]]}
+            />
+          );
+        } else if (unnamed(parameter).type == ParameterType.Choice) {
+          return (
+            <ChoiceParam
+              key={index}
+              parameter={parameter}
+              setBlankParam={setBlankParam}
+              blankIndex={indexes[index]}
+              // a choice that has not been made is made at random
+              choice={
+                blankParamValues[indexes[index]] || generatedValues[index]
+              }
             />
           );
         } else {
@@ -51,28 +90,43 @@ interface ParamProps {
 }
 
 function Param({ parameter, generatedValue }: ParamProps) {
+  const name =
+    parameter.type == ParameterType.Named ? parameter.data.name : undefined;
+  parameter = unnamed(parameter);
+
   return (
     <div className="text-sm flex items-center">
       <Label className="font-normal w-[200px]">
+        {name && `${name}: `}
         {parameter.type}{' '}
         {(parameter.type == ParameterType.String ||
           parameter.type == ParameterType.Int) &&
           `(Min: ${parameter.data.min.toString()}, Max: ${parameter.data.max.toString()})`}
+        {parameter.type == ParameterType.Secret && `(${parameter.data.name})`}
+        {parameter.type == ParameterType.Regex && `(${parameter.data.pattern})`}
+        {parameter.type == ParameterType.Date && `(${parameter.data.format})`}
+        {parameter.type == ParameterType.Timestamp &&
+          (parameter.data.millis ? '(ms)' : '(s)')}
       </Label>
       <Label className="pl-3 py-[11px] flex-1 font-robotomono font-bold overflow-auto">
-        {generatedValue}
+        {/* The values of secrets are never shown */}
+        {parameter.type == ParameterType.Secret ? '••••••••' : generatedValue}
       </Label>
     </div>
   );
 }
 
 interface BlankParamProps {
+  label: string;
+  defaultValue: string;
   blankIndex: number;
   blankParamValue: string;
   setBlankParam: (index: number, value: string) => void;
 }
 
 function BlankParam({
+  label,
+  defaultValue,
   blankIndex,
   blankParamValue,
   setBlankParam,
@@ -84,13 +138,11 @@ function BlankParam({
 
   return (
     <div className="text-sm flex items-center">
-      <Label className="font-normal w-[200px]">{`Blank @{${
-        blankIndex + 1
-      }}`}</Label>
+      <Label className="font-normal w-[200px]">{`Blank @{${label}}`}</Label>
       <Input
         autoCapitalize="off"
         autoCorrect="off"
-        placeholder="Fill in"
+        placeholder={defaultValue || 'Fill in'}
         value={blankParamValue}
         onChange={onChange}
         className="flex-1 font-robotomono placeholder:font-sans"
@@ -98,3 +150,49 @@ function BlankParam({
     </div>
   );
 }
+
+interface ChoiceParamProps {
+  parameter: Parameter;
+  blankIndex: number;
+  choice: string;
+  setBlankParam: (index: number, value: string) => void;
+}
+
+function ChoiceParam({
+  parameter,
+  blankIndex,
+  choice,
+  setBlankParam,
+}: ChoiceParamProps) {
+  const name =
+    parameter.type == ParameterType.Named ? parameter.data.name : undefined;
+  const choices = unnamed(parameter).data.choices ?? [];
+
+  return (
+    <div className="text-sm flex items-center">
+      <Label className="font-normal w-[200px]">
+        {name && `${name}: `}
+        {ParameterType.Choice}
+      </Label>
+      <Select
+        value={choice}
+        onValueChange={(value) => setBlankParam(blankIndex, value)}
+      >
+        <SelectTrigger className="flex-1 font-robotomono">
+          <SelectValue />
+        </SelectTrigger>
+        <SelectContent>
+          {choices.map((choice, index) => (
+            <SelectItem
+              key={index}
+              value={choice}
+              className="font-robotomono"
+            >
+              {choice}
+            </SelectItem>
+          ))}
+        </SelectContent>
+      </Select>
+    </div>
+  );
+}
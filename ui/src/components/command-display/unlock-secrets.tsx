@@ -0,0 +1,62 @@
+import { toast } from '@/hooks/use-toast';
+import { invoke } from '@tauri-apps/api/core';
+import { LockKeyhole } from 'lucide-react';
+import { useState } from 'react';
+import { Button } from '../ui/button';
+import { Input } from '../ui/input';
+import { Label } from '../ui/label';
+
+interface UnlockSecretsProps {
+  onUnlock: () => void;
+}
+
+export function UnlockSecrets({ onUnlock }: UnlockSecretsProps) {
+  const [passphrase, setPassphrase] = useState<string>('');
+
+  function unlock() {
+    invoke('unlock_secrets', { passphrase: passphrase })
+      .then(() => {
+        setPassphrase('');
+        onUnlock();
+      })
+      .catch((error) => {
+        console.log(error);
+        toast({
+          title: `${error} ❌`,
+        });
+      });
+  }
+
+  // This is rendered inside the form of the command, so pressing enter must
+  // not submit it
+  function onKeyDown(e: React.KeyboardEvent<HTMLInputElement>) {
+    if (e.key == 'Enter') {
+      e.preventDefault();
+      unlock();
+    }
+  }
+
+  return (
+    <div className="py-2 px-4 rounded-md border mb-4 text-sm flex items-center">
+      <Label className="font-normal w-[200px]">Unlock secrets</Label>
+      <Input
+        type="password"
+        placeholder="Passphrase"
+        value={passphrase}
+        onChange={(e) => setPassphrase(e.target.value)}
+        onKeyDown={onKeyDown}
+        className="flex-1 placeholder:font-sans"
+      />
+      <Button
+        variant="ghost"
+        size="icon"
+        type="button"
+        className="ml-2"
+        onClick={unlock}
+      >
+        <LockKeyhole className="h-4 w-4" />
+        <span className="sr-only">Unlock secrets</span>
+      </Button>
+    </div>
+  );
+}
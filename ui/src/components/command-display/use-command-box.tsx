@@ -19,9 +19,11 @@ export function UseCommandBox({
 }: UseCommandBoxProps) {
   const [settings] = useSettings();
 
-  function onUseCommand() {
+  function onUseCommand(action: 'Copy' | 'Execute') {
     invoke('update_command_last_used', {
       commandId,
+      action,
+      command,
     }).catch((error) => {
       console.error(error);
       toast({
@@ -36,7 +38,7 @@ export function UseCommandBox({
       title: 'Copied to clipboard ✅',
     });
 
-    onUseCommand();
+    onUseCommand('Copy');
   }
 
   function onExecuteInTerminal() {
@@ -44,7 +46,7 @@ export function UseCommandBox({
       command,
     })
       .then(() => {
-        onUseCommand();
+        onUseCommand('Execute');
       })
       .catch((error) => {
         console.error(error);
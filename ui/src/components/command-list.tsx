@@ -1,9 +1,11 @@
 import { cn } from '@/lib/utils';
 import { Badge } from '@/components/ui/badge';
 import { invoke } from '@tauri-apps/api/core';
-import { useCommand, useCommands } from '@/use-command';
+import { Button } from '@/components/ui/button';
+import { useCommand, useCommands, useLoadMoreCommands } from '@/use-command';
 import { Command } from '@/types/command';
 import { Star } from 'lucide-react';
+import { startTransition } from 'react';
 
 interface CommandListProps {
   items: Command[];
@@ -12,6 +14,7 @@ interface CommandListProps {
 export function CommandList({ items }: CommandListProps) {
   const [command, setCommand] = useCommand();
   const [, refreshCommands] = useCommands();
+  const [hasMore, loadMore] = useLoadMoreCommands();
 
   return (
     <div className="flex flex-col gap-2 p-4 pt-0">
@@ -42,13 +45,20 @@ export function CommandList({ items }: CommandListProps) {
             </div>
           )}
           <div className="w-full flex items-center gap-2">
-            <Badge
-              key={item.tag}
-              variant={item.tag ? 'outline' : 'secondary'}
-              className={cn(!item.tag && 'text-secondary-foreground/40')}
-            >
-              {item.tag ? item.tag : 'Untagged'}
-            </Badge>
+            {item.tags.length > 0 ? (
+              item.tags.map((tag) => (
+                <Badge key={tag} variant="outline">
+                  {tag}
+                </Badge>
+              ))
+            ) : (
+              <Badge
+                variant="secondary"
+                className="text-secondary-foreground/40"
+              >
+                Untagged
+              </Badge>
+            )}
             <Star
               className={cn(
                 'ml-auto h-3.5 hover:stroke-foreground stroke-muted-foreground invisible group-hover:visible',
@@ -76,6 +86,11 @@ export function CommandList({ items }: CommandListProps) {
           </div>
         </button>
       ))}
+      {hasMore && (
+        <Button variant="ghost" onClick={() => startTransition(loadMore)}>
+          Load more
+        </Button>
+      )}
     </div>
   );
 }
@@ -9,9 +9,10 @@ import { cn } from '@/lib/utils';
 import { Command } from '@/types/command';
 import { useCommand } from '@/use-command';
 import { File, ListFilter, Star, Tags } from 'lucide-react';
-import { useMemo, useState } from 'react';
+import { useState } from 'react';
 import { AddDialog } from './add-dialog';
 import { SearchForm } from './search-form';
+import { SortToggle } from './sort-toggle';
 import { SettingsDialog } from './settings/settings-dialog';
 import { Badge } from './ui/badge';
 import {
@@ -50,15 +51,11 @@ export function MainCommandPage({
     setSelectedTagId(undefined);
   };
 
-  const sortedCommands = useMemo(() => {
-    return [...commands].sort((a, b) => (a.last_used > b.last_used ? -1 : 1));
-  }, [commands]);
-
   const tagFilteredCommands = selectedTagId
-    ? sortedCommands.filter(
-        (command) => command.tag && command.tag.startsWith(selectedTagId),
+    ? commands.filter((command) =>
+        command.tags.some((tag) => tag.startsWith(selectedTagId)),
       )
-    : sortedCommands;
+    : commands;
 
   return (
     <TooltipProvider delayDuration={0}>
@@ -165,7 +162,8 @@ export function MainCommandPage({
         >
           <div className="flex items-center pl-4 pr-2 py-2">
             <h1 className="text-xl font-bold cursor-default">Commands</h1>
-            <div className="ml-auto">
+            <div className="ml-auto flex gap-1">
+              <SortToggle />
               <AddDialog />
             </div>
           </div>
@@ -1,6 +1,6 @@
 'use client';
 
-import { useSettings } from '@/use-command';
+import { useSettings, useStacks } from '@/use-command';
 import { Settings } from 'lucide-react';
 import { startTransition, Suspense } from 'react';
 import { ThemeToggle } from './theme-toggle';
@@ -15,6 +15,7 @@ import {
 } from '../ui/dialog';
 import { Label } from '../ui/label';
 import { TerminalToggle } from './terminal-toggle';
+import { StackToggle } from './stack-toggle';
 
 interface SettingsDialogProps {
   isCollapsed: boolean;
@@ -22,10 +23,12 @@ interface SettingsDialogProps {
 
 export function SettingsDialog({ isCollapsed }: SettingsDialogProps) {
   const [_, refreshSettings] = useSettings();
+  const [_stacks, refreshStacks] = useStacks();
 
   function onDialogClick() {
     startTransition(() => {
       refreshSettings();
+      refreshStacks();
     });
   }
 
@@ -61,6 +64,12 @@ export function SettingsDialog({ isCollapsed }: SettingsDialogProps) {
                   <TerminalToggle />
                 </div>
               </div>
+              <div className="flex items-center">
+                <Label>Stack</Label>
+                <div className="ml-auto">
+                  <StackToggle />
+                </div>
+              </div>
             </DialogDescription>
           </DialogHeader>
         </DialogContent>
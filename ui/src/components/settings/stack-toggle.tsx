@@ -0,0 +1,72 @@
+import { Check, Layers } from 'lucide-react';
+
+import { Button } from '@/components/ui/button';
+import {
+  DropdownMenu,
+  DropdownMenuContent,
+  DropdownMenuItem,
+  DropdownMenuTrigger,
+} from '@/components/ui/dropdown-menu';
+import { toast } from '@/hooks/use-toast';
+import {
+  useCommand,
+  useCommands,
+  useSettings,
+  useStacks,
+} from '@/use-command';
+import { invoke } from '@tauri-apps/api/core';
+
+export function StackToggle() {
+  const [stackList, refreshStacks] = useStacks();
+  const [_settings, refreshSettings] = useSettings();
+  const [_commands, refreshCommands] = useCommands();
+  const [_command, setCommand] = useCommand();
+
+  function switchStack(name: string) {
+    if (name == stackList.active) {
+      return;
+    }
+
+    invoke('switch_stack', { name: name })
+      .then((res) => {
+        console.log(res);
+        toast({
+          title: `Switched to the ${name} stack ✅ `,
+        });
+        setCommand({ selected: null });
+        refreshStacks();
+        refreshSettings();
+        refreshCommands();
+      })
+      .catch((error) => {
+        console.log(error);
+        toast({
+          title: `${error} ❌`,
+        });
+      });
+  }
+
+  return (
+    <DropdownMenu>
+      <DropdownMenuTrigger asChild>
+        <Button variant="outline" className="w-24">
+          <Layers />
+          <div className="ml-auto truncate">{stackList.active}</div>
+          <span className="sr-only">Switch stack</span>
+        </Button>
+      </DropdownMenuTrigger>
+      <DropdownMenuContent align="end">
+        {stackList.stacks.map((stack) => (
+          <DropdownMenuItem
+            key={stack}
+            onClick={() => switchStack(stack)}
+            className="cursor-pointer hover:bg-accent"
+          >
+            {stack == stackList.active ? <Check /> : <Layers />}
+            {stack}
+          </DropdownMenuItem>
+        ))}
+      </DropdownMenuContent>
+    </DropdownMenu>
+  );
+}
@@ -0,0 +1,69 @@
+import { ArrowDownWideNarrow, Clock, Flame, ListOrdered } from 'lucide-react';
+
+import { Button } from '@/components/ui/button';
+import {
+  DropdownMenu,
+  DropdownMenuContent,
+  DropdownMenuItem,
+  DropdownMenuTrigger,
+} from '@/components/ui/dropdown-menu';
+import { CommandSort } from '@/types/command';
+import { useSort } from '@/use-command';
+import { startTransition } from 'react';
+
+export function SortToggle() {
+  const [sort, setSort] = useSort();
+
+  function onSelect(newSort: CommandSort) {
+    // avoid blocking the UI, this will trigger a refresh on the commands
+    startTransition(() => {
+      setSort(newSort);
+    });
+  }
+
+  return (
+    <DropdownMenu>
+      <DropdownMenuTrigger asChild>
+        <Button variant="ghost" type="button" size="icon">
+          <ArrowDownWideNarrow className="h-4 w-4" />
+          <span className="sr-only">Sort commands</span>
+        </Button>
+      </DropdownMenuTrigger>
+      <DropdownMenuContent align="end">
+        <DropdownMenuItem
+          onClick={() => onSelect(CommandSort.RecentlyUsed)}
+          className={
+            sort == CommandSort.RecentlyUsed
+              ? 'cursor-pointer bg-accent'
+              : 'cursor-pointer hover:bg-accent'
+          }
+        >
+          <Clock />
+          Recently used
+        </DropdownMenuItem>
+        <DropdownMenuItem
+          onClick={() => onSelect(CommandSort.Frecency)}
+          className={
+            sort == CommandSort.Frecency
+              ? 'cursor-pointer bg-accent'
+              : 'cursor-pointer hover:bg-accent'
+          }
+        >
+          <Flame />
+          Most used
+        </DropdownMenuItem>
+        <DropdownMenuItem
+          onClick={() => onSelect(CommandSort.Insertion)}
+          className={
+            sort == CommandSort.Insertion
+              ? 'cursor-pointer bg-accent'
+              : 'cursor-pointer hover:bg-accent'
+          }
+        >
+          <ListOrdered />
+          Date added
+        </DropdownMenuItem>
+      </DropdownMenuContent>
+    </DropdownMenu>
+  );
+}
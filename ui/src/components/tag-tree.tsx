@@ -27,23 +27,24 @@ export function TagTree({
     const root: Record<string, TagNode> = {};
 
     commands.forEach((command) => {
-      if (!command.tag) return;
-      const tagPath = command.tag.split('/');
-      let current = root;
+      command.tags.forEach((tag) => {
+        const tagPath = tag.split('/');
+        let current = root;
 
-      tagPath.forEach((tagPart, index) => {
-        if (!current[tagPart]) {
-          const tagId = tagPath.slice(0, index + 1).join('/');
-          current[tagPart] = {
-            id: tagId,
-            name: tagPart,
-            children: {},
-            onClick: () => {
-              handleSelectedTagIdChange(tagId);
-            },
-          };
-        }
-        current = current[tagPart].children;
+        tagPath.forEach((tagPart, index) => {
+          if (!current[tagPart]) {
+            const tagId = tagPath.slice(0, index + 1).join('/');
+            current[tagPart] = {
+              id: tagId,
+              name: tagPart,
+              children: {},
+              onClick: () => {
+                handleSelectedTagIdChange(tagId);
+              },
+            };
+          }
+          current = current[tagPart].children;
+        });
       });
     });
 
@@ -1,9 +1,23 @@
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+
 import { MainCommandPage } from '@/components/command';
 import { Toaster } from './components/ui/toaster';
 import { useCommands } from './use-command';
 
 export default function CommandPage() {
-  const [commands, _] = useCommands();
+  const [commands, refreshCommands] = useCommands();
+
+  // the database may be changed by the CLI while the app is open
+  useEffect(() => {
+    const unlisten = listen('commands-changed', () => {
+      refreshCommands();
+    });
+
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
 
   const defaultLayout = undefined;
   const defaultCollapsed = undefined;
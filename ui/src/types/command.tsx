@@ -1,8 +1,32 @@
 export type Command = {
   id: string;
+  uuid: string;
   last_used: number;
+  created_at: number;
+  updated_at: number;
   command: string;
-  tag?: string;
+  tags: string[];
   note?: string;
   favourite: boolean;
+  deleted_at?: number;
 };
+
+export enum CommandSort {
+  Insertion = 'Insertion',
+  RecentlyUsed = 'RecentlyUsed',
+  Frecency = 'Frecency',
+}
+
+export type CommandCursor = {
+  id: string;
+  last_used: number;
+  frecency: number;
+  scored_at: number;
+};
+
+export type CommandPage = {
+  commands: Command[];
+  next_cursor: CommandCursor | null;
+};
+
+export type AddCommandOutcome = { Added: number } | { Merged: number };
@@ -14,6 +14,12 @@ export enum DefaultTerminal {
   Terminal = 'Terminal',
 }
 
+export enum DuplicatePolicy {
+  Reject = 'Reject',
+  Merge = 'Merge',
+  KeepBoth = 'KeepBoth',
+}
+
 export type SettingsConfig = {
   cli_print_style: CliPrintStyle;
   cli_display_limit: number;
@@ -23,4 +29,14 @@ export type SettingsConfig = {
   param_int_range_max: number;
   application_theme: ApplicationTheme;
   default_terminal: DefaultTerminal;
+  trash_retention_days: number;
+  duplicate_policy: DuplicatePolicy;
+  duplicate_ignore_whitespace: boolean;
+  active_stack: string;
+  auto_backup_count: number;
+};
+
+export type StackList = {
+  stacks: string[];
+  active: string;
 };
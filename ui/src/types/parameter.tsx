@@ -2,13 +2,91 @@ export enum ParameterType {
     String = "String",
     Int = "Int",
     Boolean = "Boolean",
-    Blank = "Blank"
+    Blank = "Blank",
+    Uuid = "Uuid",
+    Secret = "Secret",
+    Named = "Named",
+    Choice = "Choice",
+    Regex = "Regex",
+    Date = "Date",
+    Timestamp = "Timestamp"
 };
 
 export type Parameter = {
     type: ParameterType,
     data: {
         min: Number,
-        max: Number
+        max: Number,
+        name?: string,
+        parameter?: Parameter,
+        default?: string | null,
+        choices?: string[],
+        pattern?: string,
+        offset?: number,
+        format?: string,
+        millis?: boolean
     }
 }
+
+// the parameter without its name, if it has one
+export function unnamed(parameter: Parameter): Parameter {
+    return parameter.type == ParameterType.Named
+        ? parameter.data.parameter!
+        : parameter;
+}
+
+export function isBlank(parameter: Parameter): boolean {
+    return unnamed(parameter).type == ParameterType.Blank;
+}
+
+// whether the value of the parameter is filled in or chosen, rather than generated
+export function isInput(parameter: Parameter): boolean {
+    return isBlank(parameter) || unnamed(parameter).type == ParameterType.Choice;
+}
+
+// whether a named parameter is used again, which gets the same value as its first use
+export function isRepeated(parameters: Parameter[], index: number): boolean {
+    const parameter = parameters[index];
+    return parameter.type == ParameterType.Named &&
+        parameters
+            .slice(0, index)
+            .some((p) => p.type == ParameterType.Named && p.data.name == parameter.data.name);
+}
+
+// the index of the value filled in or chosen for each blank and choice parameter, which is shared
+// by every use of a named parameter, and -1 for every other parameter
+export function inputIndexes(parameters: Parameter[]): number[] {
+    const inputs: (string | null)[] = [];
+    return parameters.map((parameter) => {
+        if (!isInput(parameter)) {
+            return -1;
+        }
+        if (parameter.type != ParameterType.Named) {
+            inputs.push(null);
+            return inputs.length - 1;
+        }
+        const index = inputs.indexOf(parameter.data.name!);
+        if (index != -1) {
+            return index;
+        }
+        inputs.push(parameter.data.name!);
+        return inputs.length - 1;
+    });
+}
+
+// the number of values that are filled in or chosen for the blank and choice parameters
+export function countInputs(parameters: Parameter[]): number {
+    return Math.max(-1, ...inputIndexes(parameters)) + 1;
+}
+
+// the default of each value that is filled in or chosen, which is empty if there is none
+export function inputDefaults(parameters: Parameter[]): string[] {
+    const indexes = inputIndexes(parameters);
+    const defaults: string[] = Array(countInputs(parameters)).fill('');
+    parameters.forEach((parameter, index) => {
+        if (indexes[index] != -1) {
+            defaults[indexes[index]] = unnamed(parameter).data?.default ?? '';
+        }
+    });
+    return defaults;
+}
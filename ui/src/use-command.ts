@@ -1,9 +1,9 @@
-import { atom, useAtom } from 'jotai';
+import { atom, useAtom, useSetAtom } from 'jotai';
 import { atomWithRefresh } from 'jotai/utils';
 import { invoke } from '@tauri-apps/api/core';
 
-import { Command } from './types/command';
-import { SettingsConfig } from './types/config';
+import { Command, CommandPage, CommandSort } from './types/command';
+import { SettingsConfig, StackList } from './types/config';
 
 type Config = {
   selected: Command['id'] | null;
@@ -23,15 +23,63 @@ export function useSearch() {
   return useAtom(searchAtom);
 }
 
+const sortAtom = atom<CommandSort>(CommandSort.RecentlyUsed);
+
+export function useSort() {
+  return useAtom(sortAtom);
+}
+
+// the number of commands shown at first, and added by each "load more"
+const PAGE_SIZE = 100;
+
 const commandsAtom = atomWithRefresh((get) => {
   const search = get(searchAtom);
-  return invoke<Command[]>('search_commands', { search: search }).then(
-    (r) => r,
-  );
+  const sort = get(sortAtom);
+  return invoke<CommandPage>('search_commands', {
+    search: search,
+    query: { sort: sort, limit: PAGE_SIZE },
+  }).then((r) => r);
+});
+
+// the pages loaded by "load more" after the first page they continue from,
+// which are dropped once the first page is fetched again
+type MorePages = { first: CommandPage; pages: CommandPage[] };
+
+const morePagesAtom = atom<MorePages | null>(null);
+
+// every page loaded so far, starting with the first
+const pagesAtom = atom(async (get) => {
+  const first = await get(commandsAtom);
+  const more = get(morePagesAtom);
+  return more?.first === first ? [first, ...more.pages] : [first];
 });
 
 export function useCommands() {
-  return useAtom(commandsAtom);
+  const [pages] = useAtom(pagesAtom);
+  const refreshCommands = useSetAtom(commandsAtom);
+  return [pages.flatMap((page) => page.commands), refreshCommands] as const;
+}
+
+export function useLoadMoreCommands() {
+  const [pages] = useAtom(pagesAtom);
+  const [search] = useAtom(searchAtom);
+  const [sort] = useAtom(sortAtom);
+  const setMorePages = useSetAtom(morePagesAtom);
+  const cursor = pages[pages.length - 1].next_cursor;
+
+  // continues from the end of the last page loaded
+  const loadMore = () =>
+    invoke<CommandPage>('search_commands', {
+      search: search,
+      query: { sort: sort, limit: PAGE_SIZE, cursor: cursor },
+    })
+      .then((page) =>
+        setMorePages({ first: pages[0], pages: [...pages.slice(1), page] }),
+      )
+      .catch((error) => {
+        console.log(error);
+      });
+  return [cursor !== null, loadMore] as const;
 }
 
 const settingsAtom = atomWithRefresh((_get) => {
@@ -43,3 +91,11 @@ const settingsAtom = atomWithRefresh((_get) => {
 export function useSettings() {
   return useAtom(settingsAtom);
 }
+
+const stacksAtom = atomWithRefresh((_get) => {
+  return invoke<StackList>('get_stacks').then((r) => r);
+});
+
+export function useStacks() {
+  return useAtom(stacksAtom);
+}